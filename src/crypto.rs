@@ -0,0 +1,438 @@
+//! Bluetooth security functions ([`e`], [`ah`]) on top of a small pure Rust AES-128 ([`Aes128`]).
+//! Only encryption is implemented because that's all the Core spec functions need. Doesn't
+//! require `std`.
+//!
+//! [`CryptoProvider`] abstracts where AES-128 and random numbers come from (software with
+//! [`SoftwareCrypto`] or the HCI controller with `LEAdapter`). Functions built on it
+//! ([`ah_with`], [`aes_cmac`]) work with either.
+use crate::BoxFuture;
+use alloc::boxed::Box;
+use core::convert::Infallible;
+
+pub const AES_BLOCK_LEN: usize = 16;
+pub const AES_KEY_LEN: usize = 16;
+const AES_ROUNDS: usize = 10;
+const SBOX: [u8; 256] = [
+    0x63, 0x7C, 0x77, 0x7B, 0xF2, 0x6B, 0x6F, 0xC5, 0x30, 0x01, 0x67, 0x2B, 0xFE, 0xD7, 0xAB, 0x76,
+    0xCA, 0x82, 0xC9, 0x7D, 0xFA, 0x59, 0x47, 0xF0, 0xAD, 0xD4, 0xA2, 0xAF, 0x9C, 0xA4, 0x72, 0xC0,
+    0xB7, 0xFD, 0x93, 0x26, 0x36, 0x3F, 0xF7, 0xCC, 0x34, 0xA5, 0xE5, 0xF1, 0x71, 0xD8, 0x31, 0x15,
+    0x04, 0xC7, 0x23, 0xC3, 0x18, 0x96, 0x05, 0x9A, 0x07, 0x12, 0x80, 0xE2, 0xEB, 0x27, 0xB2, 0x75,
+    0x09, 0x83, 0x2C, 0x1A, 0x1B, 0x6E, 0x5A, 0xA0, 0x52, 0x3B, 0xD6, 0xB3, 0x29, 0xE3, 0x2F, 0x84,
+    0x53, 0xD1, 0x00, 0xED, 0x20, 0xFC, 0xB1, 0x5B, 0x6A, 0xCB, 0xBE, 0x39, 0x4A, 0x4C, 0x58, 0xCF,
+    0xD0, 0xEF, 0xAA, 0xFB, 0x43, 0x4D, 0x33, 0x85, 0x45, 0xF9, 0x02, 0x7F, 0x50, 0x3C, 0x9F, 0xA8,
+    0x51, 0xA3, 0x40, 0x8F, 0x92, 0x9D, 0x38, 0xF5, 0xBC, 0xB6, 0xDA, 0x21, 0x10, 0xFF, 0xF3, 0xD2,
+    0xCD, 0x0C, 0x13, 0xEC, 0x5F, 0x97, 0x44, 0x17, 0xC4, 0xA7, 0x7E, 0x3D, 0x64, 0x5D, 0x19, 0x73,
+    0x60, 0x81, 0x4F, 0xDC, 0x22, 0x2A, 0x90, 0x88, 0x46, 0xEE, 0xB8, 0x14, 0xDE, 0x5E, 0x0B, 0xDB,
+    0xE0, 0x32, 0x3A, 0x0A, 0x49, 0x06, 0x24, 0x5C, 0xC2, 0xD3, 0xAC, 0x62, 0x91, 0x95, 0xE4, 0x79,
+    0xE7, 0xC8, 0x37, 0x6D, 0x8D, 0xD5, 0x4E, 0xA9, 0x6C, 0x56, 0xF4, 0xEA, 0x65, 0x7A, 0xAE, 0x08,
+    0xBA, 0x78, 0x25, 0x2E, 0x1C, 0xA6, 0xB4, 0xC6, 0xE8, 0xDD, 0x74, 0x1F, 0x4B, 0xBD, 0x8B, 0x8A,
+    0x70, 0x3E, 0xB5, 0x66, 0x48, 0x03, 0xF6, 0x0E, 0x61, 0x35, 0x57, 0xB9, 0x86, 0xC1, 0x1D, 0x9E,
+    0xE1, 0xF8, 0x98, 0x11, 0x69, 0xD9, 0x8E, 0x94, 0x9B, 0x1E, 0x87, 0xE9, 0xCE, 0x55, 0x28, 0xDF,
+    0x8C, 0xA1, 0x89, 0x0D, 0xBF, 0xE6, 0x42, 0x68, 0x41, 0x99, 0x2D, 0x0F, 0xB0, 0x54, 0xBB, 0x16,
+];
+const RCON: [u8; AES_ROUNDS] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1B, 0x36];
+
+/// AES-128 block cipher (encryption only) with an expanded key. Keys and blocks are in the
+/// usual FIPS-197 byte order (most significant octet first when written as a number).
+/// # Examples
+/// FIPS-197 Appendix C.1 example:
+/// ```
+/// use btle::crypto::Aes128;
+/// let key = [
+///     0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+///     0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F,
+/// ];
+/// let plaintext = [
+///     0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+///     0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF,
+/// ];
+/// assert_eq!(
+///     Aes128::new(&key).encrypt_block(&plaintext),
+///     [
+///         0x69, 0xC4, 0xE0, 0xD8, 0x6A, 0x7B, 0x04, 0x30,
+///         0xD8, 0xCD, 0xB7, 0x80, 0x70, 0xB4, 0xC5, 0x5A,
+///     ]
+/// );
+/// ```
+#[derive(Clone)]
+pub struct Aes128 {
+    round_keys: [[u8; AES_BLOCK_LEN]; AES_ROUNDS + 1],
+}
+impl Aes128 {
+    pub fn new(key: &[u8; AES_KEY_LEN]) -> Aes128 {
+        let mut round_keys = [[0_u8; AES_BLOCK_LEN]; AES_ROUNDS + 1];
+        round_keys[0] = *key;
+        for (round, rcon) in RCON.iter().enumerate() {
+            let previous = round_keys[round];
+            // RotWord + SubWord + Rcon on the last word of the previous round key.
+            let mut word = [
+                SBOX[usize::from(previous[13])] ^ rcon,
+                SBOX[usize::from(previous[14])],
+                SBOX[usize::from(previous[15])],
+                SBOX[usize::from(previous[12])],
+            ];
+            let next = &mut round_keys[round + 1];
+            for (next_word, previous_word) in next.chunks_mut(4).zip(previous.chunks(4)) {
+                for (w, p) in word.iter_mut().zip(previous_word) {
+                    *w ^= p;
+                }
+                next_word.copy_from_slice(&word);
+            }
+        }
+        Aes128 { round_keys }
+    }
+    pub fn encrypt_block(&self, block: &[u8; AES_BLOCK_LEN]) -> [u8; AES_BLOCK_LEN] {
+        let mut state = *block;
+        add_round_key(&mut state, &self.round_keys[0]);
+        for round_key in &self.round_keys[1..AES_ROUNDS] {
+            sub_bytes(&mut state);
+            shift_rows(&mut state);
+            mix_columns(&mut state);
+            add_round_key(&mut state, round_key);
+        }
+        sub_bytes(&mut state);
+        shift_rows(&mut state);
+        add_round_key(&mut state, &self.round_keys[AES_ROUNDS]);
+        state
+    }
+}
+fn add_round_key(state: &mut [u8; AES_BLOCK_LEN], round_key: &[u8; AES_BLOCK_LEN]) {
+    for (s, k) in state.iter_mut().zip(round_key) {
+        *s ^= k;
+    }
+}
+fn sub_bytes(state: &mut [u8; AES_BLOCK_LEN]) {
+    for s in state.iter_mut() {
+        *s = SBOX[usize::from(*s)];
+    }
+}
+/// The state is column major (`state[column * 4 + row]`). Row `r` is rotated left by `r`.
+fn shift_rows(state: &mut [u8; AES_BLOCK_LEN]) {
+    let old = *state;
+    for (i, s) in state.iter_mut().enumerate() {
+        let (column, row) = (i / 4, i % 4);
+        *s = old[((column + row) % 4) * 4 + row];
+    }
+}
+fn xtime(b: u8) -> u8 {
+    (b << 1) ^ if b & 0x80 == 0 { 0 } else { 0x1B }
+}
+fn mix_columns(state: &mut [u8; AES_BLOCK_LEN]) {
+    for column in state.chunks_mut(4) {
+        let [a0, a1, a2, a3] = [column[0], column[1], column[2], column[3]];
+        let all = a0 ^ a1 ^ a2 ^ a3;
+        column[0] = a0 ^ all ^ xtime(a0 ^ a1);
+        column[1] = a1 ^ all ^ xtime(a1 ^ a2);
+        column[2] = a2 ^ all ^ xtime(a2 ^ a3);
+        column[3] = a3 ^ all ^ xtime(a3 ^ a0);
+    }
+}
+/// Security function `e` (Core spec Vol 3, Part H, 2.2.1). AES-128 encrypts `plaintext` with
+/// `key`. Both (and the result) are most significant octet first like in the spec.
+pub fn e(key: &[u8; AES_KEY_LEN], plaintext: &[u8; AES_BLOCK_LEN]) -> [u8; AES_BLOCK_LEN] {
+    Aes128::new(key).encrypt_block(plaintext)
+}
+pub const AH_LEN: usize = 3;
+/// Random address hash function `ah` (Core spec Vol 3, Part H, 2.2.2) used to generate and
+/// resolve resolvable private addresses. `k` (the IRK), `r` (`prand`) and the returned hash are
+/// most significant octet first like in the spec.
+/// # Examples
+/// Core spec Vol 3, Part H, D.7 sample data:
+/// ```
+/// use btle::crypto::ah;
+/// let irk = [
+///     0xEC, 0x02, 0x34, 0xA3, 0x57, 0xC8, 0xAD, 0x05,
+///     0x34, 0x10, 0x10, 0xA6, 0x0A, 0x39, 0x7D, 0x9B,
+/// ];
+/// assert_eq!(ah(&irk, [0x70, 0x81, 0x94]), [0x0D, 0xFB, 0xAA]);
+/// ```
+pub fn ah(k: &[u8; AES_KEY_LEN], r: [u8; AH_LEN]) -> [u8; AH_LEN] {
+    let mut r_prime = [0_u8; AES_BLOCK_LEN];
+    r_prime[AES_BLOCK_LEN - AH_LEN..].copy_from_slice(&r);
+    let encrypted = e(k, &r_prime);
+    let mut out = [0_u8; AH_LEN];
+    out.copy_from_slice(&encrypted[AES_BLOCK_LEN - AH_LEN..]);
+    out
+}
+/// Source of AES-128 encryption and random numbers for the security functions (Security Manager,
+/// resolvable private addresses, mesh, etc).
+pub trait CryptoProvider {
+    type Error;
+    /// Security function `e`. Same byte order as [`e`] (most significant octet first).
+    fn e(
+        &mut self,
+        key: [u8; AES_KEY_LEN],
+        plaintext: [u8; AES_BLOCK_LEN],
+    ) -> BoxFuture<'_, Result<[u8; AES_BLOCK_LEN], Self::Error>>;
+    /// Fill `buf` with random bytes.
+    fn rand<'a>(&'a mut self, buf: &'a mut [u8]) -> BoxFuture<'a, Result<(), Self::Error>>;
+}
+/// Software [`CryptoProvider`] using [`Aes128`]. Random bytes come from `fill`, which should be a
+/// cryptographically secure random number generator.
+pub struct SoftwareCrypto<F: FnMut(&mut [u8])> {
+    fill: F,
+}
+impl<F: FnMut(&mut [u8])> SoftwareCrypto<F> {
+    pub fn new(fill: F) -> SoftwareCrypto<F> {
+        SoftwareCrypto { fill }
+    }
+}
+impl<F: FnMut(&mut [u8])> CryptoProvider for SoftwareCrypto<F> {
+    type Error = Infallible;
+
+    fn e(
+        &mut self,
+        key: [u8; AES_KEY_LEN],
+        plaintext: [u8; AES_BLOCK_LEN],
+    ) -> BoxFuture<'_, Result<[u8; AES_BLOCK_LEN], Self::Error>> {
+        Box::pin(futures_util::future::ready(Ok(e(&key, &plaintext))))
+    }
+
+    fn rand<'a>(&'a mut self, buf: &'a mut [u8]) -> BoxFuture<'a, Result<(), Self::Error>> {
+        (self.fill)(buf);
+        Box::pin(futures_util::future::ready(Ok(())))
+    }
+}
+/// [`ah`] using `provider` for the encryption.
+pub async fn ah_with<P: CryptoProvider>(
+    provider: &mut P,
+    k: [u8; AES_KEY_LEN],
+    r: [u8; AH_LEN],
+) -> Result<[u8; AH_LEN], P::Error> {
+    let mut r_prime = [0_u8; AES_BLOCK_LEN];
+    r_prime[AES_BLOCK_LEN - AH_LEN..].copy_from_slice(&r);
+    let encrypted = provider.e(k, r_prime).await?;
+    let mut out = [0_u8; AH_LEN];
+    out.copy_from_slice(&encrypted[AES_BLOCK_LEN - AH_LEN..]);
+    Ok(out)
+}
+/// Doubles `block` in GF(2^128) (used to derive the AES-CMAC subkeys).
+fn cmac_double(block: [u8; AES_BLOCK_LEN]) -> [u8; AES_BLOCK_LEN] {
+    let mut out = [0_u8; AES_BLOCK_LEN];
+    let mut carry = 0_u8;
+    for (o, b) in out.iter_mut().zip(block.iter()).rev() {
+        *o = (b << 1) | carry;
+        carry = b >> 7;
+    }
+    if carry != 0 {
+        out[AES_BLOCK_LEN - 1] ^= 0x87;
+    }
+    out
+}
+fn xor_block(a: &mut [u8; AES_BLOCK_LEN], b: &[u8]) {
+    for (a, b) in a.iter_mut().zip(b) {
+        *a ^= b;
+    }
+}
+/// AES-CMAC (RFC 4493) of `message` with `key` using `provider` for the encryption. Base of the
+/// LE Secure Connections (`f4`, `f5`, `f6`, `g2`) and mesh (`s1`, `k1`-`k4`) functions. `key`
+/// and the returned MAC are most significant octet first.
+/// # Examples
+/// RFC 4493 Example 2:
+/// ```
+/// use btle::crypto::{aes_cmac, SoftwareCrypto};
+/// use futures_util::FutureExt;
+/// let key = [
+///     0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6,
+///     0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F, 0x3C,
+/// ];
+/// let message = [
+///     0x6B, 0xC1, 0xBE, 0xE2, 0x2E, 0x40, 0x9F, 0x96,
+///     0xE9, 0x3D, 0x7E, 0x11, 0x73, 0x93, 0x17, 0x2A,
+/// ];
+/// let mut provider = SoftwareCrypto::new(|_: &mut [u8]| ());
+/// let mac = aes_cmac(&mut provider, key, &message).now_or_never().unwrap().unwrap();
+/// assert_eq!(
+///     mac,
+///     [
+///         0x07, 0x0A, 0x16, 0xB4, 0x6B, 0x4D, 0x41, 0x44,
+///         0xF7, 0x9B, 0xDD, 0x9D, 0xD0, 0x4A, 0x28, 0x7C,
+///     ]
+/// );
+/// let empty_mac = aes_cmac(&mut provider, key, &[]).now_or_never().unwrap().unwrap();
+/// assert_eq!(
+///     empty_mac,
+///     [
+///         0xBB, 0x1D, 0x69, 0x29, 0xE9, 0x59, 0x37, 0x28,
+///         0x7F, 0xA3, 0x7D, 0x12, 0x9B, 0x75, 0x67, 0x46,
+///     ]
+/// );
+/// ```
+pub async fn aes_cmac<P: CryptoProvider>(
+    provider: &mut P,
+    key: [u8; AES_KEY_LEN],
+    message: &[u8],
+) -> Result<[u8; AES_BLOCK_LEN], P::Error> {
+    let k1 = cmac_double(provider.e(key, [0_u8; AES_BLOCK_LEN]).await?);
+    // The last block is always handled separately (even if `message` is empty).
+    let last_start = message.len().saturating_sub(1) / AES_BLOCK_LEN * AES_BLOCK_LEN;
+    let (blocks, last) = message.split_at(last_start);
+    let mut x = [0_u8; AES_BLOCK_LEN];
+    for block in blocks.chunks(AES_BLOCK_LEN) {
+        xor_block(&mut x, block);
+        x = provider.e(key, x).await?;
+    }
+    if last.len() == AES_BLOCK_LEN {
+        xor_block(&mut x, &k1);
+    } else {
+        xor_block(&mut x, &cmac_double(k1));
+        x[last.len()] ^= 0x80;
+    }
+    xor_block(&mut x, last);
+    provider.e(key, x).await
+}
+#[cfg(test)]
+mod tests {
+    use super::{
+        aes_cmac, ah, ah_with, e, Aes128, CryptoProvider, SoftwareCrypto, AES_BLOCK_LEN,
+        AES_KEY_LEN,
+    };
+    use crate::BoxFuture;
+    use alloc::boxed::Box;
+    use futures_util::FutureExt;
+
+    const RFC_4493_KEY: [u8; AES_KEY_LEN] = [
+        0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6, 0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F,
+        0x3C,
+    ];
+    const RFC_4493_MESSAGE: [u8; 64] = [
+        0x6B, 0xC1, 0xBE, 0xE2, 0x2E, 0x40, 0x9F, 0x96, 0xE9, 0x3D, 0x7E, 0x11, 0x73, 0x93, 0x17,
+        0x2A, 0xAE, 0x2D, 0x8A, 0x57, 0x1E, 0x03, 0xAC, 0x9C, 0x9E, 0xB7, 0x6F, 0xAC, 0x45, 0xAF,
+        0x8E, 0x51, 0x30, 0xC8, 0x1C, 0x46, 0xA3, 0x5C, 0xE4, 0x11, 0xE5, 0xFB, 0xC1, 0x19, 0x1A,
+        0x0A, 0x52, 0xEF, 0xF6, 0x9F, 0x24, 0x45, 0xDF, 0x4F, 0x9B, 0x17, 0xAD, 0x2B, 0x41, 0x7B,
+        0xE6, 0x6C, 0x37, 0x10,
+    ];
+    /// Provider that fails every request (like a controller returning an error).
+    struct FailingCrypto;
+    impl CryptoProvider for FailingCrypto {
+        type Error = ();
+
+        fn e(
+            &mut self,
+            _key: [u8; AES_KEY_LEN],
+            _plaintext: [u8; AES_BLOCK_LEN],
+        ) -> BoxFuture<'_, Result<[u8; AES_BLOCK_LEN], Self::Error>> {
+            Box::pin(futures_util::future::ready(Err(())))
+        }
+
+        fn rand<'a>(&'a mut self, _buf: &'a mut [u8]) -> BoxFuture<'a, Result<(), Self::Error>> {
+            Box::pin(futures_util::future::ready(Err(())))
+        }
+    }
+
+    #[test]
+    fn test_aes128_fips197_appendix_b() {
+        let key = [
+            0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6, 0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF,
+            0x4F, 0x3C,
+        ];
+        let plaintext = [
+            0x32, 0x43, 0xF6, 0xA8, 0x88, 0x5A, 0x30, 0x8D, 0x31, 0x31, 0x98, 0xA2, 0xE0, 0x37,
+            0x07, 0x34,
+        ];
+        assert_eq!(
+            Aes128::new(&key).encrypt_block(&plaintext),
+            [
+                0x39, 0x25, 0x84, 0x1D, 0x02, 0xDC, 0x09, 0xFB, 0xDC, 0x11, 0x85, 0x97, 0x19, 0x6A,
+                0x0B, 0x32
+            ]
+        );
+    }
+    #[test]
+    fn test_aes128_zero_key() {
+        // NIST AESAVS GFSbox known answer test (all zero key).
+        let plaintext = [
+            0xF3, 0x44, 0x81, 0xEC, 0x3C, 0xC6, 0x27, 0xBA, 0xCD, 0x5D, 0xC3, 0xFB, 0x08, 0xF2,
+            0x73, 0xE6,
+        ];
+        assert_eq!(
+            e(&[0_u8; 16], &plaintext),
+            [
+                0x03, 0x36, 0x76, 0x3E, 0x96, 0x6D, 0x92, 0x59, 0x5A, 0x56, 0x7C, 0xC9, 0xCE, 0x53,
+                0x7F, 0x5E
+            ]
+        );
+    }
+    #[test]
+    fn test_ah() {
+        let irk = [
+            0xEC, 0x02, 0x34, 0xA3, 0x57, 0xC8, 0xAD, 0x05, 0x34, 0x10, 0x10, 0xA6, 0x0A, 0x39,
+            0x7D, 0x9B,
+        ];
+        assert_eq!(ah(&irk, [0x70, 0x81, 0x94]), [0x0D, 0xFB, 0xAA]);
+        // `ah` is the last 3 octets of `e(k, padding || r)`.
+        let mut r_prime = [0_u8; AES_BLOCK_LEN];
+        r_prime[AES_BLOCK_LEN - 3..].copy_from_slice(&[0x45, 0x12, 0x34]);
+        assert_eq!(
+            ah(&irk, [0x45, 0x12, 0x34])[..],
+            e(&irk, &r_prime)[AES_BLOCK_LEN - 3..]
+        );
+    }
+    #[test]
+    fn test_software_crypto() {
+        let mut calls = 0;
+        let mut provider = SoftwareCrypto::new(|buf: &mut [u8]| {
+            calls += 1;
+            for b in buf.iter_mut() {
+                *b = 0xA5;
+            }
+        });
+        let plaintext = [0x11_u8; AES_BLOCK_LEN];
+        assert_eq!(
+            provider.e(RFC_4493_KEY, plaintext).now_or_never().unwrap(),
+            Ok(e(&RFC_4493_KEY, &plaintext))
+        );
+        let mut buf = [0_u8; 20];
+        assert_eq!(provider.rand(&mut buf).now_or_never().unwrap(), Ok(()));
+        assert_eq!(buf, [0xA5; 20]);
+        let r = [0x70, 0x81, 0x94];
+        assert_eq!(
+            ah_with(&mut provider, RFC_4493_KEY, r)
+                .now_or_never()
+                .unwrap(),
+            Ok(ah(&RFC_4493_KEY, r))
+        );
+        assert_eq!(calls, 1);
+    }
+    #[test]
+    fn test_aes_cmac_rfc_4493() {
+        let mut provider = SoftwareCrypto::new(|_: &mut [u8]| ());
+        let mut cmac = |message: &[u8]| {
+            aes_cmac(&mut provider, RFC_4493_KEY, message)
+                .now_or_never()
+                .unwrap()
+                .unwrap()
+        };
+        // Example 3 (incomplete last block) and Example 4 (only complete blocks).
+        assert_eq!(
+            cmac(&RFC_4493_MESSAGE[..40]),
+            [
+                0xDF, 0xA6, 0x67, 0x47, 0xDE, 0x9A, 0xE6, 0x30, 0x30, 0xCA, 0x32, 0x61, 0x14, 0x97,
+                0xC8, 0x27
+            ]
+        );
+        assert_eq!(
+            cmac(&RFC_4493_MESSAGE[..]),
+            [
+                0x51, 0xF0, 0xBE, 0xBF, 0x7E, 0x3B, 0x9D, 0x92, 0xFC, 0x49, 0x74, 0x17, 0x79, 0x36,
+                0x3C, 0xFE
+            ]
+        );
+    }
+    #[test]
+    fn test_provider_errors() {
+        let mut provider = FailingCrypto;
+        assert_eq!(
+            aes_cmac(&mut provider, RFC_4493_KEY, &RFC_4493_MESSAGE[..])
+                .now_or_never()
+                .unwrap(),
+            Err(())
+        );
+        assert_eq!(
+            ah_with(&mut provider, RFC_4493_KEY, [0, 0, 1])
+                .now_or_never()
+                .unwrap(),
+            Err(())
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use crate::bytes::Storage;
 use crate::error::IOError;
 use crate::hci;
-use crate::hci::command::{Command, CommandPacket};
+use crate::hci::command::{AsyncCommand, Command, CommandPacket, PendingCommand};
 use crate::hci::event::EventPacket;
-use crate::hci::stream::HCI_EVENT_READ_TRIES;
-use crate::hci::StreamError;
+use crate::hci::timer::{self, Timer};
+use crate::hci::{Opcode, StreamError};
 use core::pin::Pin;
+use core::time::Duration;
 use futures_util::future::LocalBoxFuture;
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
@@ -14,6 +15,23 @@ pub enum Error {
     IOError(IOError),
     StreamError(hci::StreamError),
     ErrorCode(hci::ErrorCode),
+    /// No response to the command with this `Opcode` arrived before the command timeout. Only
+    /// adapters with a [`Timer`] (see [`Adapter::timer`]) time out.
+    CommandTimeout(Opcode),
+    /// The controller doesn't support the command with this `Opcode` (according to its
+    /// `SupportedCommands`) so it wasn't sent.
+    UnsupportedCommand(Opcode),
+}
+impl Error {
+    /// Returns `true` if sending the command again could succeed (the controller returned a
+    /// retryable `ErrorCode` or the command timed out). See `ErrorCode::is_retryable`.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::ErrorCode(code) => code.is_retryable(),
+            Error::CommandTimeout(_) => true,
+            _ => false,
+        }
+    }
 }
 impl core::fmt::Display for Error {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -45,16 +63,51 @@ impl From<hci::usb::Error> for Error {
 impl std::error::Error for Error {}
 
 impl crate::error::Error for Error {}
+/// Default time to wait for the response of a command (`CommandComplete` or `CommandStatus`).
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(2);
+/// Default time to wait for the completion event of an [`AsyncCommand`].
+pub const DEFAULT_COMPLETION_TIMEOUT: Duration = Duration::from_secs(30);
 ///WIP HCI Adapter trait
 pub trait Adapter {
     fn write_command<'s, 'p: 's>(
         self: Pin<&'s mut Self>,
         packet: CommandPacket<&'p [u8]>,
     ) -> LocalBoxFuture<'s, Result<(), Error>>;
+    /// Timer used for command timeouts. Commands never time out if this returns `None` (the
+    /// default).
+    fn timer(&self) -> Option<&dyn Timer> {
+        None
+    }
+    /// How long [`Adapter::wait_for_completion`] waits for the completion event. Defaults to
+    /// [`DEFAULT_COMPLETION_TIMEOUT`].
+    fn completion_timeout(&self) -> Duration {
+        DEFAULT_COMPLETION_TIMEOUT
+    }
+    /// How long [`Adapter::send_command`] waits for a response. Defaults to
+    /// [`DEFAULT_COMMAND_TIMEOUT`].
+    fn command_timeout(&self) -> Duration {
+        DEFAULT_COMMAND_TIMEOUT
+    }
+    /// Send `command` and wait for its `Cmd::Return` event. If the controller answers with a
+    /// `CommandStatus` that isn't `ErrorCode::Ok`, its `ErrorCode` is returned as
+    /// `Error::ErrorCode`. For [`AsyncCommand`]s, this returns once the `CommandStatus` arrives
+    /// (see [`Adapter::send_async_command`] to also wait for the completion event).
+    /// Returns `Error::CommandTimeout` if no response arrives within
+    /// [`Adapter::command_timeout`].
     fn send_command<'a, 'c: 'a, Cmd: Command + 'c>(
+        self: Pin<&'a mut Self>,
+        command: Cmd,
+    ) -> LocalBoxFuture<'a, Result<Cmd::Return, hci::adapter::Error>> {
+        let timeout = self.command_timeout();
+        self.send_command_timeout(command, timeout)
+    }
+    /// Same as [`Adapter::send_command`] but waits `timeout` for the response instead of
+    /// [`Adapter::command_timeout`].
+    fn send_command_timeout<'a, 'c: 'a, Cmd: Command + 'c>(
         mut self: Pin<&'a mut Self>,
         command: Cmd,
-    ) -> LocalBoxFuture<'_, Result<Cmd::Return, hci::adapter::Error>> {
+        timeout: Duration,
+    ) -> LocalBoxFuture<'a, Result<Cmd::Return, hci::adapter::Error>> {
         Box::pin(async move {
             type Buf = Box<[u8]>;
             // Pack Command
@@ -66,18 +119,114 @@ pub trait Adapter {
                         .as_ref(),
                 )
                 .await?;
-            for _try_i in 0..HCI_EVENT_READ_TRIES {
-                let event: EventPacket<Buf> = self.as_mut().read_event::<Buf>().await?;
-                if let Some(ret) =
-                    Cmd::unpack_return(event.as_ref()).map_err(StreamError::EventError)?
-                {
-                    return Ok(ret);
-                }
-            }
-            Err(hci::adapter::Error::StreamError(StreamError::StreamFailed))
+            self.read_command_return_timeout::<Cmd>(timeout).await
+        })
+    }
+    /// Read events until the `Cmd::Return` event (or a failed `CommandStatus`) for `Cmd` is
+    /// read. Other events are dropped. Returns `Error::CommandTimeout` if it takes longer than
+    /// `timeout`. Waits forever if [`Adapter::timer`] is `None`.
+    fn read_command_return_timeout<'a, Cmd: Command + 'a>(
+        self: Pin<&'a mut Self>,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'a, Result<Cmd::Return, hci::adapter::Error>> {
+        Box::pin(async move {
+            let sleep = self.timer().map(|timer| timer.sleep(timeout));
+            timer::timeout(sleep, read_events_until(self, unpack_command_return::<Cmd>))
+                .await
+                .unwrap_or(Err(Error::CommandTimeout(Cmd::opcode())))
+        })
+    }
+    /// Read events until the `Cmd::Return` event (or a failed `CommandStatus`) for `Cmd` is
+    /// read. Other events are dropped. Waits forever, see
+    /// [`Adapter::read_command_return_timeout`].
+    fn read_command_return<'a, Cmd: Command + 'a>(
+        self: Pin<&'a mut Self>,
+    ) -> LocalBoxFuture<'a, Result<Cmd::Return, hci::adapter::Error>> {
+        Box::pin(read_events_until(self, unpack_command_return::<Cmd>))
+    }
+    /// Send an [`AsyncCommand`] and wait for its `CommandStatus`. Returns a [`PendingCommand`]
+    /// if the controller accepted the command. Use [`Adapter::wait_for_completion`] to wait for
+    /// the completion event.
+    fn send_async_command<'a, Cmd: AsyncCommand + 'a>(
+        mut self: Pin<&'a mut Self>,
+        command: Cmd,
+    ) -> LocalBoxFuture<'a, Result<PendingCommand<Cmd>, hci::adapter::Error>> {
+        Box::pin(async move {
+            type Buf = Box<[u8]>;
+            self.as_mut()
+                .write_command(
+                    command
+                        .pack_command_packet::<Buf>()
+                        .map_err(StreamError::CommandError)?
+                        .as_ref(),
+                )
+                .await?;
+            let timeout = self.command_timeout();
+            let status = self.read_command_return_timeout::<Cmd>(timeout).await?;
+            Ok(PendingCommand::new(command, status))
+        })
+    }
+    /// Read events until the completion event of `pending` is read. Other events are dropped.
+    /// Returns `Error::CommandTimeout` if no completion event arrives within
+    /// [`Adapter::completion_timeout`].
+    fn wait_for_completion<'a, Cmd: AsyncCommand + 'a>(
+        self: Pin<&'a mut Self>,
+        pending: &'a PendingCommand<Cmd>,
+    ) -> LocalBoxFuture<'a, Result<Cmd::Completion, hci::adapter::Error>> {
+        let timeout = self.completion_timeout();
+        self.wait_for_completion_timeout(pending, timeout)
+    }
+    /// Same as [`Adapter::wait_for_completion`] but waits `timeout` for the completion event
+    /// instead of [`Adapter::completion_timeout`]. Waits forever if [`Adapter::timer`] is `None`.
+    fn wait_for_completion_timeout<'a, Cmd: AsyncCommand + 'a>(
+        self: Pin<&'a mut Self>,
+        pending: &'a PendingCommand<Cmd>,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'a, Result<Cmd::Completion, hci::adapter::Error>> {
+        Box::pin(async move {
+            let unpack = |event: &EventPacket<Box<[u8]>>| {
+                pending
+                    .command
+                    .unpack_completion(event.as_ref())
+                    .map_err(|e| StreamError::EventError(e).into())
+            };
+            let sleep = self.timer().map(|timer| timer.sleep(timeout));
+            timer::timeout(sleep, read_events_until(self, unpack))
+                .await
+                .unwrap_or(Err(Error::CommandTimeout(Cmd::opcode())))
         })
     }
     fn read_event<'s, 'p: 's, S: Storage<u8> + 'p>(
         self: Pin<&'s mut Self>,
     ) -> LocalBoxFuture<'s, Result<EventPacket<S>, Error>>;
 }
+/// Marker for [`Adapter`]s whose `read_event` future doesn't block the thread while waiting and
+/// can be dropped before it finishes without losing (part of) an event.
+/// [`crate::hci::adapters::dispatcher::Dispatcher`] requires it because it drops the pending read
+/// to write commands.
+pub trait CancelSafeRead: Adapter {}
+/// Reads events until `unpack` returns `Some`.
+async fn read_events_until<A, T, F>(mut adapter: Pin<&mut A>, mut unpack: F) -> Result<T, Error>
+where
+    A: Adapter + ?Sized,
+    F: FnMut(&EventPacket<Box<[u8]>>) -> Result<Option<T>, Error>,
+{
+    type Buf = Box<[u8]>;
+    loop {
+        let event: EventPacket<Buf> = adapter.as_mut().read_event::<Buf>().await?;
+        if let Some(t) = unpack(&event)? {
+            return Ok(t);
+        }
+    }
+}
+/// Returns the `Cmd::Return` in `event` (or the `ErrorCode` of a failed `CommandStatus`).
+fn unpack_command_return<Cmd: Command>(
+    event: &EventPacket<Box<[u8]>>,
+) -> Result<Option<Cmd::Return>, Error> {
+    if let Some(status) =
+        Cmd::unpack_command_status(event.as_ref()).map_err(StreamError::EventError)?
+    {
+        status.status.error()?;
+    }
+    Ok(Cmd::unpack_return(event.as_ref()).map_err(StreamError::EventError)?)
+}
@@ -0,0 +1,395 @@
+//! HCI Command flow control. The controller tells the host how many HCI Command packets it can
+//! currently accept with `Num_HCI_Command_Packets` in every `CommandComplete` and `CommandStatus`
+//! event. [`CommandCredits`] tracks that count and queues commands until the controller has room
+//! for them. [`CreditAdapter`] wraps any [`adapter::Adapter`] with a [`CommandCredits`].
+use crate::bytes::Storage;
+use crate::hci::adapter;
+use crate::hci::command::{AsyncCommand, Command, CommandPacket, PendingCommand};
+use crate::hci::event::{EventCode, EventPacket};
+use crate::hci::timer::Timer;
+use crate::hci::StreamError;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use core::pin::Pin;
+use core::time::Duration;
+use futures_util::future::LocalBoxFuture;
+
+/// Owned `CommandPacket` waiting in the [`CommandCredits`] queue.
+pub type QueuedCommand = CommandPacket<Box<[u8]>>;
+
+/// Tracks how many HCI Command packets the controller can accept (`Num_HCI_Command_Packets`)
+/// and queues outgoing commands until credits are granted.
+///
+/// # Example
+/// ```
+/// use btle::hci::adapters::credits::CommandCredits;
+/// use btle::hci::command::CommandPacket;
+/// use btle::hci::event::{EventCode, EventPacket};
+/// use btle::hci::Opcode;
+/// let mut credits = CommandCredits::new();
+/// let packet = || CommandPacket {
+///     opcode: Opcode::nop(),
+///     parameters: Vec::new().into_boxed_slice(),
+/// };
+/// credits.push(packet());
+/// credits.push(packet());
+/// // Only 1 credit after reset.
+/// assert!(credits.pop_ready().is_some());
+/// assert!(credits.pop_ready().is_none());
+/// // NOP Command Complete granting 1 credit.
+/// assert!(credits.update_from_event(&EventPacket::new(
+///     EventCode::CommandComplete,
+///     &[0x01, 0x00, 0x00][..]
+/// )));
+/// assert!(credits.pop_ready().is_some());
+/// assert_eq!(credits.queued(), 0);
+/// // Commands nobody waits for anymore can be removed before they are sent.
+/// let id = credits.push(packet());
+/// assert!(credits.remove(id).is_some());
+/// assert_eq!(credits.queued(), 0);
+/// ```
+pub struct CommandCredits {
+    credits: u8,
+    next_id: usize,
+    queue: VecDeque<(usize, QueuedCommand)>,
+}
+impl CommandCredits {
+    /// The host is allowed to send one command before the controller reports any credits.
+    pub const INITIAL_CREDITS: u8 = 1;
+    pub fn new() -> CommandCredits {
+        CommandCredits {
+            credits: Self::INITIAL_CREDITS,
+            next_id: 0,
+            queue: VecDeque::new(),
+        }
+    }
+    /// Number of commands the controller can currently accept.
+    pub fn credits(&self) -> u8 {
+        self.credits
+    }
+    pub fn set_credits(&mut self, credits: u8) {
+        self.credits = credits;
+    }
+    /// Number of commands waiting for credits.
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+    /// Returns `Num_HCI_Command_Packets` if `event` is a `CommandComplete` (including NOP
+    /// `CommandComplete`s with opcode `0x0000`) or a `CommandStatus` event.
+    pub fn event_credits(event: &EventPacket<&[u8]>) -> Option<u8> {
+        match event.event_code() {
+            EventCode::CommandComplete => event.parameters().first().copied(),
+            EventCode::CommandStatus => event.parameters().get(1).copied(),
+            _ => None,
+        }
+    }
+    /// Updates the credit count from `event`. The latest `Num_HCI_Command_Packets` replaces the
+    /// current count. Returns `true` if `event` carried a credit count.
+    pub fn update_from_event(&mut self, event: &EventPacket<&[u8]>) -> bool {
+        if let Some(credits) = Self::event_credits(event) {
+            self.credits = credits;
+            true
+        } else {
+            false
+        }
+    }
+    /// Queues `packet` to be sent once a credit is available. Returns an id for
+    /// [`CommandCredits::remove`].
+    pub fn push(&mut self, packet: QueuedCommand) -> usize {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.queue.push_back((id, packet));
+        id
+    }
+    /// Removes the command `id` (returned by [`CommandCredits::push`]) if it is still queued.
+    /// Used when nothing waits for the command's response anymore (it timed out or its future
+    /// was dropped) so it doesn't get sent later.
+    pub fn remove(&mut self, id: usize) -> Option<QueuedCommand> {
+        let index = self
+            .queue
+            .iter()
+            .position(|(queued_id, _)| *queued_id == id)?;
+        self.queue.remove(index).map(|(_, packet)| packet)
+    }
+    /// Takes the next queued command if the controller has a credit for it. Using the command
+    /// consumes one credit.
+    pub fn pop_ready(&mut self) -> Option<QueuedCommand> {
+        if self.credits == 0 {
+            return None;
+        }
+        let (_, packet) = self.queue.pop_front()?;
+        self.credits -= 1;
+        Some(packet)
+    }
+    /// Drops all queued commands (after a `Reset` for example) and restores the initial credit.
+    pub fn reset(&mut self) {
+        self.queue.clear();
+        self.credits = Self::INITIAL_CREDITS;
+    }
+}
+impl Default for CommandCredits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+/// [`adapter::Adapter`] wrapper that only writes commands to the inner adapter when the
+/// controller has credits for them. `write_command` queues the command and `read_event` updates
+/// the credits from each event and flushes the queue. Commands sent with `send_command` or
+/// `send_async_command` are removed from the queue if nothing waits for them anymore.
+pub struct CreditAdapter<A: adapter::Adapter> {
+    adapter: A,
+    credits: CommandCredits,
+}
+impl<A: adapter::Adapter> CreditAdapter<A> {
+    pub fn new(adapter: A) -> Self {
+        Self {
+            adapter,
+            credits: CommandCredits::new(),
+        }
+    }
+    pub fn into_inner(self) -> A {
+        self.adapter
+    }
+    pub fn credits(&self) -> &CommandCredits {
+        &self.credits
+    }
+    pub fn credits_mut(self: Pin<&mut Self>) -> &mut CommandCredits {
+        self.project().1
+    }
+    pub fn adapter_pinned(self: Pin<&mut Self>) -> Pin<&mut A> {
+        self.project().0
+    }
+    fn project(self: Pin<&mut Self>) -> (Pin<&mut A>, &mut CommandCredits) {
+        // Safety: `adapter` is structurally pinned and never moved out of a pinned `Self`.
+        // `credits` is never pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        (
+            unsafe { Pin::new_unchecked(&mut this.adapter) },
+            &mut this.credits,
+        )
+    }
+    /// Queues `command` and waits for its `Cmd::Return`. The command is removed from the queue if
+    /// it hasn't been written when this returns (for example after a timeout) or is dropped.
+    async fn send_queued<Cmd: Command>(
+        mut self: Pin<&mut Self>,
+        command: &Cmd,
+        timeout: Duration,
+    ) -> Result<Cmd::Return, adapter::Error> {
+        let packet: QueuedCommand = command
+            .pack_command_packet()
+            .map_err(StreamError::CommandError)?;
+        let id = self.as_mut().credits_mut().push(packet);
+        let mut guard = QueuedGuard { adapter: self, id };
+        guard.adapter.as_mut().flush().await?;
+        adapter::Adapter::read_command_return_timeout::<Cmd>(guard.adapter.as_mut(), timeout).await
+    }
+    /// Writes queued commands to the inner adapter while there are credits.
+    pub async fn flush(self: Pin<&mut Self>) -> Result<(), adapter::Error> {
+        let (mut adapter, credits) = self.project();
+        while let Some(packet) = credits.pop_ready() {
+            adapter.as_mut().write_command(packet.as_ref()).await?;
+        }
+        Ok(())
+    }
+}
+/// Removes the queued command `id` from the [`CreditAdapter`] on drop.
+struct QueuedGuard<'a, A: adapter::Adapter> {
+    adapter: Pin<&'a mut CreditAdapter<A>>,
+    id: usize,
+}
+impl<A: adapter::Adapter> Drop for QueuedGuard<'_, A> {
+    fn drop(&mut self) {
+        let id = self.id;
+        self.adapter.as_mut().credits_mut().remove(id);
+    }
+}
+impl<A: adapter::Adapter> adapter::Adapter for CreditAdapter<A> {
+    fn write_command<'s, 'p: 's>(
+        mut self: Pin<&'s mut Self>,
+        packet: CommandPacket<&'p [u8]>,
+    ) -> LocalBoxFuture<'s, Result<(), adapter::Error>> {
+        let owned = CommandPacket {
+            opcode: packet.opcode,
+            parameters: Box::from(packet.parameters),
+        };
+        Box::pin(async move {
+            self.as_mut().credits_mut().push(owned);
+            self.flush().await
+        })
+    }
+
+    fn timer(&self) -> Option<&dyn Timer> {
+        self.adapter.timer()
+    }
+
+    fn send_command_timeout<'a, 'c: 'a, Cmd: Command + 'c>(
+        self: Pin<&'a mut Self>,
+        command: Cmd,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'a, Result<Cmd::Return, adapter::Error>> {
+        Box::pin(async move { self.send_queued(&command, timeout).await })
+    }
+
+    fn send_async_command<'a, Cmd: AsyncCommand + 'a>(
+        self: Pin<&'a mut Self>,
+        command: Cmd,
+    ) -> LocalBoxFuture<'a, Result<PendingCommand<Cmd>, adapter::Error>> {
+        Box::pin(async move {
+            let timeout = self.command_timeout();
+            let status = self.send_queued(&command, timeout).await?;
+            Ok(PendingCommand::new(command, status))
+        })
+    }
+
+    fn command_timeout(&self) -> Duration {
+        self.adapter.command_timeout()
+    }
+
+    fn completion_timeout(&self) -> Duration {
+        self.adapter.completion_timeout()
+    }
+
+    fn read_event<'s, 'p: 's, S: Storage<u8> + 'p>(
+        mut self: Pin<&'s mut Self>,
+    ) -> LocalBoxFuture<'s, Result<EventPacket<S>, adapter::Error>> {
+        Box::pin(async move {
+            let event: EventPacket<S> = self.as_mut().adapter_pinned().read_event().await?;
+            if self
+                .as_mut()
+                .credits_mut()
+                .update_from_event(&event.as_ref())
+            {
+                self.flush().await?;
+            }
+            Ok(event)
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{CommandCredits, CreditAdapter, QueuedCommand};
+    use crate::bytes::Storage;
+    use crate::hci::adapter;
+    use crate::hci::baseband::Reset;
+    use crate::hci::command::{Command, CommandPacket};
+    use crate::hci::event::{EventCode, EventPacket};
+    use crate::hci::Opcode;
+    use alloc::boxed::Box;
+    use alloc::collections::VecDeque;
+    use alloc::vec::Vec;
+    use core::pin::Pin;
+    use core::task::Poll;
+    use futures_util::future::{poll_fn, LocalBoxFuture};
+    use futures_util::FutureExt;
+
+    /// Records written opcodes and returns queued events (then never finishes reading).
+    #[derive(Default)]
+    struct MockAdapter {
+        written: Vec<Opcode>,
+        events: VecDeque<EventPacket<Box<[u8]>>>,
+    }
+    impl adapter::Adapter for MockAdapter {
+        fn write_command<'s, 'p: 's>(
+            self: Pin<&'s mut Self>,
+            packet: CommandPacket<&'p [u8]>,
+        ) -> LocalBoxFuture<'s, Result<(), adapter::Error>> {
+            self.get_mut().written.push(packet.opcode);
+            Box::pin(futures_util::future::ready(Ok(())))
+        }
+
+        fn read_event<'s, 'p: 's, S: Storage<u8> + 'p>(
+            self: Pin<&'s mut Self>,
+        ) -> LocalBoxFuture<'s, Result<EventPacket<S>, adapter::Error>> {
+            let this = self.get_mut();
+            Box::pin(poll_fn(move |_| match this.events.pop_front() {
+                Some(event) => Poll::Ready(Ok(EventPacket::new(
+                    event.event_code,
+                    S::from_slice(event.parameters()),
+                ))),
+                None => Poll::Pending,
+            }))
+        }
+    }
+    fn packet() -> QueuedCommand {
+        CommandPacket {
+            opcode: Opcode::nop(),
+            parameters: Vec::new().into_boxed_slice(),
+        }
+    }
+    fn nop_complete(credits: u8) -> EventPacket<Box<[u8]>> {
+        EventPacket::new(
+            EventCode::CommandComplete,
+            Box::from(&[credits, 0x00, 0x00][..]),
+        )
+    }
+    #[test]
+    fn test_credits_from_events() {
+        let mut credits = CommandCredits::new();
+        // Command Status (status, Num_HCI_Command_Packets, opcode).
+        assert!(credits.update_from_event(&EventPacket::new(
+            EventCode::CommandStatus,
+            &[0x00, 0x02, 0x00, 0x00][..]
+        )));
+        assert_eq!(credits.credits(), 2);
+        assert!(
+            !credits.update_from_event(&EventPacket::new(EventCode::HardwareError, &[0x00][..]))
+        );
+        assert_eq!(credits.credits(), 2);
+        // Short events don't carry a count.
+        assert!(
+            !credits.update_from_event(&EventPacket::new(EventCode::CommandStatus, &[0x00][..]))
+        );
+        assert!(!credits.update_from_event(&EventPacket::new(EventCode::CommandComplete, &[][..])));
+        for _ in 0..3 {
+            credits.push(packet());
+        }
+        assert!(credits.pop_ready().is_some());
+        assert!(credits.pop_ready().is_some());
+        assert!(credits.pop_ready().is_none());
+        assert_eq!(credits.credits(), 0);
+        assert_eq!(credits.queued(), 1);
+        credits.reset();
+        assert_eq!(credits.queued(), 0);
+        assert_eq!(credits.credits(), CommandCredits::INITIAL_CREDITS);
+    }
+    #[test]
+    fn test_credits_remove() {
+        let mut credits = CommandCredits::new();
+        credits.set_credits(0);
+        let first = credits.push(packet());
+        let second = credits.push(packet());
+        assert!(credits.remove(first).is_some());
+        assert!(credits.remove(first).is_none());
+        assert_eq!(credits.queued(), 1);
+        credits.set_credits(1);
+        assert!(credits.pop_ready().is_some());
+        assert!(credits.remove(second).is_none());
+    }
+    #[test]
+    fn test_credit_adapter_drops_abandoned_command() {
+        let mut adapter = CreditAdapter::new(MockAdapter::default());
+        Pin::new(&mut adapter).credits_mut().set_credits(0);
+        {
+            let mut send = adapter::Adapter::send_command(Pin::new(&mut adapter), Reset);
+            assert!(send.as_mut().now_or_never().is_none());
+        }
+        assert_eq!(adapter.credits().queued(), 0);
+        // A credit arriving later doesn't send the abandoned command.
+        adapter.adapter.events.push_back(nop_complete(1));
+        let read = adapter::Adapter::read_event::<Box<[u8]>>(Pin::new(&mut adapter));
+        assert!(read.now_or_never().is_some());
+        assert!(adapter.adapter.written.is_empty());
+    }
+    #[test]
+    fn test_credit_adapter_sends_when_credit_arrives() {
+        let mut adapter = CreditAdapter::new(MockAdapter::default());
+        Pin::new(&mut adapter).credits_mut().set_credits(0);
+        adapter.adapter.events.push_back(nop_complete(1));
+        {
+            let mut send = adapter::Adapter::send_command(Pin::new(&mut adapter), Reset);
+            assert!(send.as_mut().now_or_never().is_none());
+        }
+        assert_eq!(adapter.adapter.written, [Reset::opcode()]);
+        assert_eq!(adapter.credits().credits(), 0);
+    }
+}
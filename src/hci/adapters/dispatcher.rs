@@ -0,0 +1,786 @@
+//! HCI event dispatcher. [`Dispatcher`] owns the transport ([`adapter::Adapter`]) and routes every
+//! incoming `EventPacket`: `CommandComplete`/`CommandStatus` events go to the command waiting for
+//! that opcode and every other event is copied to each [`Subscription`]. Any number of
+//! [`DispatcherHandle`]s can send commands and subscribe at the same time (for example, changing
+//! scan parameters while consuming an advertisement stream).
+//!
+//! The dispatcher is single threaded (`Rc`/`RefCell`) so `Dispatcher::run` and the handles must be
+//! polled on the same executor thread.
+//!
+//! The transport must implement [`adapter::CancelSafeRead`]: it has to read asynchronously (so
+//! pending reads don't block the handles) and its pending `read_event` must be droppable without
+//! losing an event. Blocking transports (like the USB adapter) can't be dispatched.
+use crate::bytes::Storage;
+use crate::hci::adapter;
+use crate::hci::adapters::credits::{CommandCredits, QueuedCommand};
+use crate::hci::command::{AsyncCommand, Command, CommandPacket, PendingCommand};
+use crate::hci::event::{EventCode, EventPacket};
+use crate::hci::timer::{self, Timer};
+use crate::hci::{Opcode, StreamError};
+use crate::Stream;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::ops::{Deref, DerefMut};
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+use futures_util::future::{poll_fn, select, Either, LocalBoxFuture};
+
+/// Owned `EventPacket` handed out by the dispatcher.
+pub type DispatchedEvent = EventPacket<Box<[u8]>>;
+
+struct ResponseSlot {
+    id: usize,
+    opcode: Opcode,
+    response: Option<DispatchedEvent>,
+    waker: Option<Waker>,
+}
+struct SubscriberQueue {
+    id: usize,
+    events: VecDeque<DispatchedEvent>,
+    /// Events were dropped because `events` was full.
+    overflowed: bool,
+    waker: Option<Waker>,
+}
+struct Inner {
+    next_id: usize,
+    handles: usize,
+    closed: bool,
+    credits: CommandCredits,
+    timer: Option<Box<dyn Timer>>,
+    command_timeout: Duration,
+    responses: Vec<ResponseSlot>,
+    subscribers: Vec<SubscriberQueue>,
+    dispatcher_waker: Option<Waker>,
+}
+impl Inner {
+    fn new() -> Inner {
+        Inner {
+            next_id: 0,
+            handles: 0,
+            closed: false,
+            credits: CommandCredits::new(),
+            timer: None,
+            command_timeout: adapter::DEFAULT_COMMAND_TIMEOUT,
+            responses: Vec::new(),
+            subscribers: Vec::new(),
+            dispatcher_waker: None,
+        }
+    }
+    fn next_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        id
+    }
+    fn wake_dispatcher(&mut self) {
+        if let Some(waker) = self.dispatcher_waker.take() {
+            waker.wake();
+        }
+    }
+    /// Ready when a queued command can be written or when every handle has been dropped.
+    fn poll_commands_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.handles == 0 || (self.credits.credits() > 0 && self.credits.queued() > 0) {
+            Poll::Ready(())
+        } else {
+            self.dispatcher_waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+    fn dispatch(&mut self, event: DispatchedEvent) {
+        self.credits.update_from_event(&event.as_ref());
+        if let Some(opcode) = response_opcode(&event.as_ref()) {
+            if let Some(slot) = self
+                .responses
+                .iter_mut()
+                .find(|slot| slot.opcode == opcode && slot.response.is_none())
+            {
+                slot.response = Some(event);
+                if let Some(waker) = slot.waker.take() {
+                    waker.wake();
+                }
+                return;
+            }
+        }
+        for subscriber in &mut self.subscribers {
+            if subscriber.events.len() < Subscription::MAX_QUEUED_EVENTS {
+                subscriber.events.push_back(EventPacket::new(
+                    event.event_code,
+                    Box::from(event.parameters()),
+                ));
+            } else {
+                subscriber.overflowed = true;
+            }
+            if let Some(waker) = subscriber.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+    /// Marks the dispatcher as stopped and wakes everything waiting on it.
+    fn close(&mut self) {
+        self.closed = true;
+        for slot in &mut self.responses {
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+        }
+        for subscriber in &mut self.subscribers {
+            if let Some(waker) = subscriber.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+/// Returns the opcode of the command `event` answers if `event` is a `CommandComplete` or
+/// `CommandStatus` event.
+fn response_opcode(event: &EventPacket<&[u8]>) -> Option<Opcode> {
+    let range = match event.event_code() {
+        EventCode::CommandComplete => 1..3,
+        EventCode::CommandStatus => 2..4,
+        _ => return None,
+    };
+    Opcode::unpack(event.parameters().get(range)?).ok()
+}
+/// Owns the HCI transport and routes its events to [`DispatcherHandle`]s. Nothing is read or
+/// written until [`Dispatcher::run`] is polled.
+pub struct Dispatcher<A: adapter::CancelSafeRead, S: Deref<Target = A>> {
+    adapter: Pin<S>,
+    inner: Rc<RefCell<Inner>>,
+}
+impl<A: adapter::CancelSafeRead, S: Deref<Target = A> + DerefMut> Dispatcher<A, S> {
+    pub fn new(adapter: Pin<S>) -> Self {
+        Self {
+            adapter,
+            inner: Rc::new(RefCell::new(Inner::new())),
+        }
+    }
+    pub fn pin(adapter: S) -> Self
+    where
+        A: Unpin,
+    {
+        Self::new(Pin::new(adapter))
+    }
+    /// Sets the [`Timer`] used for command (and completion event) timeouts. Commands never time
+    /// out without a timer.
+    pub fn set_timer<T: Timer + 'static>(&mut self, timer: T) {
+        self.inner.borrow_mut().timer = Some(Box::new(timer));
+    }
+    /// Sets how long [`DispatcherHandle::hci_send_command`] waits for a response. Defaults to
+    /// [`adapter::DEFAULT_COMMAND_TIMEOUT`].
+    pub fn set_command_timeout(&mut self, command_timeout: Duration) {
+        self.inner.borrow_mut().command_timeout = command_timeout;
+    }
+    /// Returns a new handle for sending commands and subscribing to events.
+    pub fn handle(&self) -> DispatcherHandle {
+        DispatcherHandle::new(self.inner.clone())
+    }
+    /// Writes queued commands (as HCI command credits allow) and dispatches events until the
+    /// transport fails or every [`DispatcherHandle`] is dropped. Commands still waiting when
+    /// this returns fail with `StreamError::StreamClosed`.
+    ///
+    /// `A::read_event` is dropped and restarted when a new command is queued (which is why `A`
+    /// must be [`adapter::CancelSafeRead`]).
+    pub async fn run(mut self) -> Result<(), adapter::Error> {
+        let result = self.run_loop().await;
+        self.inner.borrow_mut().close();
+        result
+    }
+    async fn run_loop(&mut self) -> Result<(), adapter::Error> {
+        loop {
+            loop {
+                let packet = self.inner.borrow_mut().credits.pop_ready();
+                match packet {
+                    Some(packet) => {
+                        self.adapter.as_mut().write_command(packet.as_ref()).await?;
+                    }
+                    None => break,
+                }
+            }
+            if self.inner.borrow().handles == 0 {
+                return Ok(());
+            }
+            let inner = &self.inner;
+            let commands_ready = poll_fn(|cx| inner.borrow_mut().poll_commands_ready(cx));
+            let read = self.adapter.as_mut().read_event::<Box<[u8]>>();
+            if let Either::Left((event, _)) = select(read, commands_ready).await {
+                inner.borrow_mut().dispatch(event?);
+            }
+        }
+    }
+}
+/// Clonable handle to a [`Dispatcher`]. Sends commands through the dispatcher and receives
+/// events through [`Subscription`]s.
+///
+/// `DispatcherHandle` also implements [`adapter::Adapter`] so it can be used with
+/// [`super::Adapter`] and [`super::le::LEAdapter`]. Its `read_event` returns the events of a
+/// subscription created the first time it is needed. Like any [`Subscription`], it buffers at
+/// most [`Subscription::MAX_QUEUED_EVENTS`] unread events.
+pub struct DispatcherHandle {
+    inner: Rc<RefCell<Inner>>,
+    subscription: Option<Subscription>,
+}
+impl DispatcherHandle {
+    fn new(inner: Rc<RefCell<Inner>>) -> DispatcherHandle {
+        inner.borrow_mut().handles += 1;
+        DispatcherHandle {
+            inner,
+            subscription: None,
+        }
+    }
+    /// Returns `true` if the [`Dispatcher`] stopped running.
+    pub fn is_closed(&self) -> bool {
+        self.inner.borrow().closed
+    }
+    /// Subscribes to all events that aren't command responses. Events are buffered until they are
+    /// read (up to [`Subscription::MAX_QUEUED_EVENTS`]) so a `Subscription` should be read or
+    /// dropped.
+    pub fn subscribe(&self) -> Subscription {
+        let mut inner = self.inner.borrow_mut();
+        let id = inner.next_id();
+        inner.subscribers.push(SubscriberQueue {
+            id,
+            events: VecDeque::new(),
+            overflowed: false,
+            waker: None,
+        });
+        Subscription {
+            inner: self.inner.clone(),
+            id,
+        }
+    }
+    fn handle_subscription(&mut self) -> &mut Subscription {
+        if self.subscription.is_none() {
+            self.subscription = Some(self.subscribe());
+        }
+        self.subscription
+            .as_mut()
+            .unwrap_or_else(|| unreachable!("subscription set above"))
+    }
+    /// How long commands wait for a response (see [`Dispatcher::set_command_timeout`]).
+    pub fn command_timeout(&self) -> Duration {
+        self.inner.borrow().command_timeout
+    }
+    /// Queues `command` and waits for its `Cmd::Return` event. If the controller answers with a
+    /// `CommandStatus` that isn't `ErrorCode::Ok`, its `ErrorCode` is returned as
+    /// `Error::ErrorCode`. Returns `Error::CommandTimeout` if no response arrives within
+    /// [`DispatcherHandle::command_timeout`].
+    pub async fn hci_send_command<Cmd: Command>(
+        &self,
+        command: Cmd,
+    ) -> Result<Cmd::Return, adapter::Error> {
+        self.send_command_ref(&command, self.command_timeout())
+            .await
+    }
+    /// Same as [`DispatcherHandle::hci_send_command`] but waits `timeout` for the response.
+    pub async fn hci_send_command_timeout<Cmd: Command>(
+        &self,
+        command: Cmd,
+        timeout: Duration,
+    ) -> Result<Cmd::Return, adapter::Error> {
+        self.send_command_ref(&command, timeout).await
+    }
+    async fn send_command_ref<Cmd: Command>(
+        &self,
+        command: &Cmd,
+        timeout: Duration,
+    ) -> Result<Cmd::Return, adapter::Error> {
+        let packet: QueuedCommand = command
+            .pack_command_packet()
+            .map_err(StreamError::CommandError)?;
+        let sleep = self
+            .inner
+            .borrow()
+            .timer
+            .as_ref()
+            .map(|timer| timer.sleep(timeout));
+        let slot = ResponseGuard::register(&self.inner, packet);
+        timer::timeout(sleep, Self::read_response::<Cmd>(&slot))
+            .await
+            .unwrap_or(Err(adapter::Error::CommandTimeout(Cmd::opcode())))
+    }
+    async fn read_response<Cmd: Command>(
+        slot: &ResponseGuard<'_>,
+    ) -> Result<Cmd::Return, adapter::Error> {
+        loop {
+            let event = slot.next_response().await?;
+            if let Some(status) =
+                Cmd::unpack_command_status(event.as_ref()).map_err(StreamError::EventError)?
+            {
+                status.status.error()?;
+            }
+            if let Some(ret) =
+                Cmd::unpack_return(event.as_ref()).map_err(StreamError::EventError)?
+            {
+                return Ok(ret);
+            }
+        }
+    }
+    /// Queues an [`AsyncCommand`] and waits for its `CommandStatus`.
+    pub async fn hci_send_async_command<Cmd: AsyncCommand>(
+        &self,
+        command: Cmd,
+    ) -> Result<PendingCommand<Cmd>, adapter::Error> {
+        let status = self
+            .send_command_ref(&command, self.command_timeout())
+            .await?;
+        Ok(PendingCommand::new(command, status))
+    }
+    /// Queues an [`AsyncCommand`] and waits for its completion event.
+    pub async fn hci_send_command_and_wait<Cmd: AsyncCommand>(
+        &self,
+        command: Cmd,
+    ) -> Result<Cmd::Completion, adapter::Error> {
+        // Subscribe before sending so the completion event can't be missed.
+        let mut subscription = self.subscribe();
+        let pending = self.hci_send_async_command(command).await?;
+        subscription.wait_for_completion(&pending).await
+    }
+}
+impl Clone for DispatcherHandle {
+    fn clone(&self) -> Self {
+        DispatcherHandle::new(self.inner.clone())
+    }
+}
+impl Drop for DispatcherHandle {
+    fn drop(&mut self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.handles -= 1;
+        if inner.handles == 0 {
+            inner.wake_dispatcher();
+        }
+    }
+}
+/// `read_event` only takes an event from the subscription when it returns it.
+impl adapter::CancelSafeRead for DispatcherHandle {}
+impl adapter::Adapter for DispatcherHandle {
+    fn write_command<'s, 'p: 's>(
+        self: Pin<&'s mut Self>,
+        packet: CommandPacket<&'p [u8]>,
+    ) -> LocalBoxFuture<'s, Result<(), adapter::Error>> {
+        let owned = CommandPacket {
+            opcode: packet.opcode,
+            parameters: Box::from(packet.parameters),
+        };
+        Box::pin(async move {
+            let mut inner = self.inner.borrow_mut();
+            if inner.closed {
+                return Err(adapter::Error::StreamError(StreamError::StreamClosed));
+            }
+            inner.credits.push(owned);
+            inner.wake_dispatcher();
+            Ok(())
+        })
+    }
+
+    fn command_timeout(&self) -> Duration {
+        DispatcherHandle::command_timeout(self)
+    }
+
+    fn send_command_timeout<'a, 'c: 'a, Cmd: Command + 'c>(
+        self: Pin<&'a mut Self>,
+        command: Cmd,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'a, Result<Cmd::Return, adapter::Error>> {
+        Box::pin(async move { self.hci_send_command_timeout(command, timeout).await })
+    }
+
+    fn send_async_command<'a, Cmd: AsyncCommand + 'a>(
+        self: Pin<&'a mut Self>,
+        command: Cmd,
+    ) -> LocalBoxFuture<'a, Result<PendingCommand<Cmd>, adapter::Error>> {
+        Box::pin(async move {
+            let this = self.get_mut();
+            // `wait_for_completion` takes the completion event from the handle subscription so it
+            // must exist before the command is sent.
+            this.handle_subscription();
+            this.hci_send_async_command(command).await
+        })
+    }
+
+    fn wait_for_completion_timeout<'a, Cmd: AsyncCommand + 'a>(
+        self: Pin<&'a mut Self>,
+        pending: &'a PendingCommand<Cmd>,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'a, Result<Cmd::Completion, adapter::Error>> {
+        Box::pin(async move {
+            let this = self.get_mut();
+            let sleep = this
+                .inner
+                .borrow()
+                .timer
+                .as_ref()
+                .map(|timer| timer.sleep(timeout));
+            timer::timeout(sleep, this.handle_subscription().take_completion(pending))
+                .await
+                .unwrap_or(Err(adapter::Error::CommandTimeout(Cmd::opcode())))
+        })
+    }
+
+    fn read_event<'s, 'p: 's, S: Storage<u8> + 'p>(
+        self: Pin<&'s mut Self>,
+    ) -> LocalBoxFuture<'s, Result<EventPacket<S>, adapter::Error>> {
+        Box::pin(async move {
+            let event = self.get_mut().handle_subscription().next_event().await?;
+            Ok(EventPacket::new(
+                event.event_code,
+                S::from_slice(event.parameters()),
+            ))
+        })
+    }
+}
+/// Waiting command registered with the dispatcher. Unregisters on drop (and removes the command
+/// from the queue if it hasn't been written yet).
+struct ResponseGuard<'a> {
+    inner: &'a Rc<RefCell<Inner>>,
+    id: usize,
+    queued_id: usize,
+}
+impl<'a> ResponseGuard<'a> {
+    fn register(inner: &'a Rc<RefCell<Inner>>, packet: QueuedCommand) -> ResponseGuard<'a> {
+        let mut borrowed = inner.borrow_mut();
+        let id = borrowed.next_id();
+        borrowed.responses.push(ResponseSlot {
+            id,
+            opcode: packet.opcode,
+            response: None,
+            waker: None,
+        });
+        let queued_id = borrowed.credits.push(packet);
+        borrowed.wake_dispatcher();
+        ResponseGuard {
+            inner,
+            id,
+            queued_id,
+        }
+    }
+    fn poll_response(&self, cx: &mut Context<'_>) -> Poll<Result<DispatchedEvent, adapter::Error>> {
+        let mut inner = self.inner.borrow_mut();
+        let closed = inner.closed;
+        if let Some(slot) = inner.responses.iter_mut().find(|slot| slot.id == self.id) {
+            if let Some(event) = slot.response.take() {
+                Poll::Ready(Ok(event))
+            } else if closed {
+                Poll::Ready(Err(StreamError::StreamClosed.into()))
+            } else {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        } else {
+            Poll::Ready(Err(StreamError::StreamClosed.into()))
+        }
+    }
+    async fn next_response(&self) -> Result<DispatchedEvent, adapter::Error> {
+        poll_fn(|cx| self.poll_response(cx)).await
+    }
+}
+impl Drop for ResponseGuard<'_> {
+    fn drop(&mut self) {
+        let id = self.id;
+        let mut inner = self.inner.borrow_mut();
+        inner.responses.retain(|slot| slot.id != id);
+        inner.credits.remove(self.queued_id);
+    }
+}
+/// Stream of every dispatched event that isn't a command response. Unsubscribes on drop.
+pub struct Subscription {
+    inner: Rc<RefCell<Inner>>,
+    id: usize,
+}
+impl Subscription {
+    /// How many unread events a `Subscription` buffers. Later events are dropped until the
+    /// buffered ones are read.
+    pub const MAX_QUEUED_EVENTS: usize = 256;
+    /// Waits for the next event. Returns `StreamError::StreamClosed` if the dispatcher stopped
+    /// and `StreamError::EventOverflow` (once, after the buffered events) if events were dropped
+    /// because the buffer was full.
+    pub async fn next_event(&mut self) -> Result<DispatchedEvent, adapter::Error> {
+        poll_fn(|cx| self.poll_event(cx)).await
+    }
+    fn poll_event(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<DispatchedEvent, adapter::Error>> {
+        let mut inner = self.inner.borrow_mut();
+        let closed = inner.closed;
+        let id = self.id;
+        if let Some(queue) = inner.subscribers.iter_mut().find(|s| s.id == id) {
+            if let Some(event) = queue.events.pop_front() {
+                Poll::Ready(Ok(event))
+            } else if queue.overflowed {
+                queue.overflowed = false;
+                Poll::Ready(Err(StreamError::EventOverflow.into()))
+            } else if closed {
+                Poll::Ready(Err(StreamError::StreamClosed.into()))
+            } else {
+                queue.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        } else {
+            Poll::Ready(Err(StreamError::StreamClosed.into()))
+        }
+    }
+    /// Reads events until the completion event of `pending` is read. Other events are dropped.
+    pub async fn wait_for_completion<Cmd: AsyncCommand>(
+        &mut self,
+        pending: &PendingCommand<Cmd>,
+    ) -> Result<Cmd::Completion, adapter::Error> {
+        loop {
+            let event = self.next_event().await?;
+            if let Some(completion) = pending
+                .command
+                .unpack_completion(event.as_ref())
+                .map_err(StreamError::EventError)?
+            {
+                return Ok(completion);
+            }
+        }
+    }
+    /// Waits for the completion event of `pending` and removes it from the buffered events. Other
+    /// events stay buffered for [`Subscription::next_event`], so the completion event is lost if
+    /// they fill the buffer (see [`Subscription::MAX_QUEUED_EVENTS`]).
+    async fn take_completion<Cmd: AsyncCommand>(
+        &mut self,
+        pending: &PendingCommand<Cmd>,
+    ) -> Result<Cmd::Completion, adapter::Error> {
+        poll_fn(|cx| self.poll_completion(pending, cx)).await
+    }
+    fn poll_completion<Cmd: AsyncCommand>(
+        &mut self,
+        pending: &PendingCommand<Cmd>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Cmd::Completion, adapter::Error>> {
+        let mut inner = self.inner.borrow_mut();
+        let closed = inner.closed;
+        let id = self.id;
+        if let Some(queue) = inner.subscribers.iter_mut().find(|s| s.id == id) {
+            for (i, event) in queue.events.iter().enumerate() {
+                if let Some(completion) = pending
+                    .command
+                    .unpack_completion(event.as_ref())
+                    .map_err(StreamError::EventError)?
+                {
+                    queue.events.remove(i);
+                    return Poll::Ready(Ok(completion));
+                }
+            }
+            if !closed {
+                queue.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+        }
+        Poll::Ready(Err(StreamError::StreamClosed.into()))
+    }
+}
+/// Ends when the dispatcher stops. Dropped events (see [`Subscription::MAX_QUEUED_EVENTS`]) are
+/// skipped, use [`Subscription::next_event`] to be told about them.
+impl Stream for Subscription {
+    type Item = DispatchedEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match this.poll_event(cx) {
+                Poll::Ready(Ok(event)) => return Poll::Ready(Some(event)),
+                Poll::Ready(Err(adapter::Error::StreamError(StreamError::EventOverflow))) => {}
+                Poll::Ready(Err(_)) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let id = self.id;
+        self.inner
+            .borrow_mut()
+            .subscribers
+            .retain(|subscriber| subscriber.id != id);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{DispatchedEvent, Dispatcher, Subscription};
+    use crate::bytes::Storage;
+    use crate::hci::adapter;
+    use crate::hci::baseband::Reset;
+    use crate::hci::command::{Command, CommandPacket};
+    use crate::hci::event::{EventCode, EventPacket};
+    use crate::hci::link_control::Disconnect;
+    use crate::hci::{ConnectionHandle, ErrorCode, Opcode, StreamError};
+    use alloc::boxed::Box;
+    use alloc::collections::VecDeque;
+    use alloc::rc::Rc;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+    use core::pin::Pin;
+    use core::task::Poll;
+    use futures_util::future::{poll_fn, LocalBoxFuture};
+    use futures_util::FutureExt;
+
+    #[derive(Default)]
+    struct MockState {
+        written: Vec<Opcode>,
+        events: VecDeque<DispatchedEvent>,
+    }
+    /// Answers every written command with a successful `CommandComplete` (granting 1 credit) or,
+    /// for `Disconnect`, a successful `CommandStatus`.
+    #[derive(Default)]
+    struct MockAdapter {
+        state: Rc<RefCell<MockState>>,
+    }
+    impl adapter::CancelSafeRead for MockAdapter {}
+    impl adapter::Adapter for MockAdapter {
+        fn write_command<'s, 'p: 's>(
+            self: Pin<&'s mut Self>,
+            packet: CommandPacket<&'p [u8]>,
+        ) -> LocalBoxFuture<'s, Result<(), adapter::Error>> {
+            let mut state = self.state.borrow_mut();
+            state.written.push(packet.opcode);
+            let event = if packet.opcode == Disconnect::opcode() {
+                let mut parameters = [ErrorCode::Ok.into(), 0x01, 0x00, 0x00];
+                packet
+                    .opcode
+                    .pack(&mut parameters[2..4])
+                    .expect("opcode fits");
+                EventPacket::new(EventCode::CommandStatus, Box::from(&parameters[..]))
+            } else {
+                let mut parameters = [0x01, 0x00, 0x00, ErrorCode::Ok.into()];
+                packet
+                    .opcode
+                    .pack(&mut parameters[1..3])
+                    .expect("opcode fits");
+                EventPacket::new(EventCode::CommandComplete, Box::from(&parameters[..]))
+            };
+            state.events.push_back(event);
+            Box::pin(futures_util::future::ready(Ok(())))
+        }
+
+        fn read_event<'s, 'p: 's, S: Storage<u8> + 'p>(
+            self: Pin<&'s mut Self>,
+        ) -> LocalBoxFuture<'s, Result<EventPacket<S>, adapter::Error>> {
+            let state = self.state.clone();
+            Box::pin(poll_fn(move |_| {
+                match state.borrow_mut().events.pop_front() {
+                    Some(event) => Poll::Ready(Ok(EventPacket::new(
+                        event.event_code,
+                        S::from_slice(event.parameters()),
+                    ))),
+                    None => Poll::Pending,
+                }
+            }))
+        }
+    }
+    fn hardware_error() -> DispatchedEvent {
+        EventPacket::new(EventCode::HardwareError, Box::from(&[0x00][..]))
+    }
+    #[test]
+    fn test_dispatch_responses_and_events() {
+        let mock = MockAdapter::default();
+        let state = mock.state.clone();
+        state.borrow_mut().events.push_back(hardware_error());
+        let dispatcher = Dispatcher::pin(Box::new(mock));
+        let handle = dispatcher.handle();
+        let mut subscription = handle.subscribe();
+        let mut run = Box::pin(dispatcher.run());
+        {
+            let mut send = Box::pin(handle.hci_send_command(Reset));
+            assert!(send.as_mut().now_or_never().is_none());
+            assert!(run.as_mut().now_or_never().is_none());
+            let complete = send.now_or_never().unwrap().unwrap();
+            assert_eq!(complete.params.status, ErrorCode::Ok);
+        }
+        assert_eq!(state.borrow().written, [Reset::opcode()]);
+        // The command response isn't copied to subscriptions.
+        let event = subscription.next_event().now_or_never().unwrap().unwrap();
+        assert_eq!(event.event_code, EventCode::HardwareError);
+        assert!(subscription.next_event().now_or_never().is_none());
+        drop(handle);
+        assert!(run.now_or_never().unwrap().is_ok());
+        assert_eq!(
+            subscription.next_event().now_or_never().unwrap().err(),
+            Some(StreamError::StreamClosed.into())
+        );
+    }
+    #[test]
+    fn test_subscription_overflow() {
+        let mock = MockAdapter::default();
+        for _ in 0..Subscription::MAX_QUEUED_EVENTS + 2 {
+            mock.state.borrow_mut().events.push_back(hardware_error());
+        }
+        let dispatcher = Dispatcher::pin(Box::new(mock));
+        let handle = dispatcher.handle();
+        let mut subscription = handle.subscribe();
+        let mut run = Box::pin(dispatcher.run());
+        assert!(run.as_mut().now_or_never().is_none());
+        for _ in 0..Subscription::MAX_QUEUED_EVENTS {
+            assert!(subscription.next_event().now_or_never().unwrap().is_ok());
+        }
+        assert_eq!(
+            subscription.next_event().now_or_never().unwrap().err(),
+            Some(StreamError::EventOverflow.into())
+        );
+        assert!(subscription.next_event().now_or_never().is_none());
+    }
+    #[test]
+    fn test_dropped_command_is_not_sent() {
+        let mock = MockAdapter::default();
+        let state = mock.state.clone();
+        let dispatcher = Dispatcher::pin(Box::new(mock));
+        let handle = dispatcher.handle();
+        handle.inner.borrow_mut().credits.set_credits(0);
+        {
+            let mut send = Box::pin(handle.hci_send_command(Reset));
+            assert!(send.as_mut().now_or_never().is_none());
+            assert_eq!(handle.inner.borrow().credits.queued(), 1);
+        }
+        assert_eq!(handle.inner.borrow().credits.queued(), 0);
+        assert!(handle.inner.borrow().responses.is_empty());
+        handle.inner.borrow_mut().credits.set_credits(1);
+        let mut run = Box::pin(dispatcher.run());
+        assert!(run.as_mut().now_or_never().is_none());
+        assert!(state.borrow().written.is_empty());
+    }
+    #[test]
+    fn test_completion_keeps_other_events() {
+        let mock = MockAdapter::default();
+        let state = mock.state.clone();
+        let dispatcher = Dispatcher::pin(Box::new(mock));
+        let mut handle = dispatcher.handle();
+        let mut run = Box::pin(dispatcher.run());
+        let disconnect = Disconnect {
+            handle: ConnectionHandle::new(1),
+            reason: ErrorCode::OtherEndTerminatedConnectionUserEndedConnection,
+        };
+        let pending = {
+            let mut send = adapter::Adapter::send_async_command(Pin::new(&mut handle), disconnect);
+            assert!(send.as_mut().now_or_never().is_none());
+            assert!(run.as_mut().now_or_never().is_none());
+            send.now_or_never().unwrap().unwrap()
+        };
+        let complete = [
+            ErrorCode::Ok.into(),
+            0x01,
+            0x00,
+            ErrorCode::ConnectionTerminatedByLocalHost.into(),
+        ];
+        state.borrow_mut().events.push_back(hardware_error());
+        state.borrow_mut().events.push_back(EventPacket::new(
+            EventCode::DisconnectionComplete,
+            Box::from(&complete[..]),
+        ));
+        assert!(run.as_mut().now_or_never().is_none());
+        let complete = adapter::Adapter::wait_for_completion(Pin::new(&mut handle), &pending)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(complete.handle, ConnectionHandle::new(1));
+        // The `HardwareError` that arrived before the completion event is still readable.
+        let event = adapter::Adapter::read_event::<Box<[u8]>>(Pin::new(&mut handle))
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.event_code, EventCode::HardwareError);
+    }
+}
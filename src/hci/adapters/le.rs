@@ -1,27 +1,83 @@
 use crate::hci::adapters::Adapter;
-use crate::hci::baseband::{EventMask, EventMaskFlags};
-use crate::hci::le::mask::{MetaEventMask, SetMetaEventMask};
+use crate::hci::baseband::EventMaskFlags;
+use crate::hci::command::PendingCommand;
+use crate::hci::le::advertise::TxPowerLevel;
+use crate::hci::le::connection::{
+    Connection, ConnectionUpdateComplete, RemoteConnectionParameterRequest,
+};
+use crate::hci::le::data_length::{DataLength, DataLengthChange, PacketLength};
+use crate::hci::le::extended_advertise::{
+    AdvertisingDataFragment, AdvertisingHandle, AdvertisingSetEnable,
+    ExtendedAdvertisingParameters, FragmentPreference, MAX_EXTENDED_ADVERTISING_DATA_LEN,
+};
+use crate::hci::le::extended_scan::{ExtendedScanParameters, FilterDuplicates};
+use crate::hci::le::features::{LEFeatureFlags, LEFeatures, SupportedStates};
+use crate::hci::le::mask::MetaEventMask;
+use crate::hci::le::periodic_advertise::{
+    PeriodicAdvertisingDataFragment, PeriodicAdvertisingParameters,
+    MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN,
+};
+use crate::hci::le::periodic_sync::{
+    PeriodicAdvertiserListEntry, PeriodicAdvertisingCreateSync, PeriodicAdvertisingReport,
+    PeriodicAdvertisingSyncEstablished, PeriodicAdvertisingSyncLost, SyncHandle,
+};
+use crate::hci::le::phy::{CodedPhyOptions, Phy, PhyPreference, PhyUpdateComplete};
+use crate::hci::le::privacy::{RPATimeout, ResolvingListEntry};
+use crate::hci::le::report::{
+    DataStatus, DirectedAdvertisingReport, ExtendedAdvertisingReport, ExtendedReportInfo,
+    ExtendedReportReassembler,
+};
+use crate::hci::le::whitelist::{Whitelist, WhitelistEntry};
 use crate::hci::le::MetaEventCode;
 use crate::{
     bytes::Storage,
+    crypto::{CryptoProvider, AES_BLOCK_LEN, AES_KEY_LEN},
     hci::{
         adapter,
         event::{EventCode, EventPacket},
         le::{self, random::RAND_LEN, report::AdvertisingReport, MetaEvent, RawMetaEvent},
-        StreamError,
+        ConnectionHandle, ErrorCode, StreamError,
     },
     le::{
-        advertisement::{StaticAdvBuffer, MAX_ADV_LEN},
-        advertiser::AdvertisingParameters,
-        report::ReportInfo,
-        scan::ScanParameters,
+        advertisement::{RawAdvertisement, StaticAdvBuffer, MAX_ADV_LEN},
+        advertiser::{AdvertisingParameters, PeerAddressType},
+        connection::{ConnectionParameters, ConnectionUpdateParameters},
+        privacy::IdentityAddress,
+        report::{AddressType, ReportInfo},
+        scan::{ScanParameters, ScanningFilterPolicy},
     },
-    Stream,
+    BTAddress, BoxFuture, Stream, BT_ADDRESS_LEN,
 };
+use core::cell::RefMut;
 use core::convert::TryFrom;
 use core::ops::{Deref, DerefMut};
 use futures_util::StreamExt;
 
+/// Event returned by [`LEAdapter::next_connection_event`].
+#[derive(Debug)]
+pub enum ConnectionEvent {
+    DataLengthChange(DataLengthChange),
+    RemoteConnectionParameterRequest(RemoteConnectionParameterRequest),
+    /// Any other event.
+    Other(EventPacket<Box<[u8]>>),
+}
+/// Advertising set created on the controller by [`LEAdapter::create_advertising_set`]. Pass it
+/// to the other `LEAdapter` advertising set methods.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct AdvertisingSet {
+    pub handle: AdvertisingHandle,
+    /// TX power selected by the controller for this set.
+    pub tx_power: TxPowerLevel,
+}
+
+/// LE controller state tracked by [`LEAdapter`]. Kept in the [`Adapter`] state so it outlives
+/// each `LEAdapter` borrowed with [`Adapter::le_mut`].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct State {
+    pub(crate) whitelist: Option<Whitelist>,
+    features: Option<LEFeatures>,
+    supported_states: Option<SupportedStates>,
+}
 pub struct LEAdapter<A: adapter::Adapter, S: Deref<Target = A> + DerefMut> {
     adapter: Adapter<A, S>,
 }
@@ -29,6 +85,9 @@ impl<A: adapter::Adapter, S: Deref<Target = A> + DerefMut> LEAdapter<A, S> {
     pub fn new(adapter: Adapter<A, S>) -> Self {
         Self { adapter }
     }
+    fn state(&self) -> RefMut<'_, State> {
+        RefMut::map(self.adapter.state(), |state| &mut state.le)
+    }
     pub fn adapter_mut(&mut self) -> Adapter<A, &'_ mut A> {
         self.adapter.as_mut()
     }
@@ -62,10 +121,18 @@ impl<A: adapter::Adapter, S: Deref<Target = A> + DerefMut> LEAdapter<A, S> {
         Ok(())
     }
     /// Set advertisement scanning parameters. See [`le::commands::SetScanParameters`] for more.
+    /// The directed scanning filter policies report directed advertisements as
+    /// [`DirectedAdvertisingReport`]s (see [`LEAdapter::directed_advertising_report_stream`]).
+    /// # Errors
+    /// Returns `adapter::Error::ErrorCode(ErrorCode::UnsupportedFeatureOrParameterValue)` if a
+    /// directed scanning filter policy is used but the controller doesn't support the LE
+    /// Extended Scanner Filter Policies feature.
     pub async fn set_scan_parameters(
         &mut self,
         scan_parameters: ScanParameters,
     ) -> Result<(), adapter::Error> {
+        self.check_scanning_filter_policy(scan_parameters.scanning_filter_policy)
+            .await?;
         self.adapter
             .hci_send_command(le::scan::SetScanParameters(scan_parameters))
             .await?
@@ -105,15 +172,65 @@ impl<A: adapter::Adapter, S: Deref<Target = A> + DerefMut> LEAdapter<A, S> {
         r.params.status.error()?;
         Ok(r.params.random_bytes)
     }
-    pub async fn set_meta_event_mask(&mut self, mask: MetaEventMask) -> Result<(), adapter::Error> {
+    /// AES-128 encrypt `plaintext` with `key` using the controller. All least significant octet
+    /// first (see [`le::encrypt::Encrypt`]). [`CryptoProvider::e`] takes care of the byte order.
+    pub async fn encrypt(
+        &mut self,
+        key: [u8; AES_KEY_LEN],
+        plaintext: [u8; AES_BLOCK_LEN],
+    ) -> Result<[u8; AES_BLOCK_LEN], adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::Encrypt { key, plaintext })
+            .await?
+            .params;
+        r.status.error()?;
+        Ok(r.encrypted)
+    }
+    /// Set the random device address of the controller. Used when `OwnAddressType::RandomDevice`
+    /// is selected in [`AdvertisingParameters`] or [`ScanParameters`].
+    pub async fn set_random_address(&mut self, address: BTAddress) -> Result<(), adapter::Error> {
         self.adapter
-            .hci_send_command(SetMetaEventMask(mask))
+            .hci_send_command(le::commands::SetRandomAddress(address))
             .await?
             .params
             .status
             .error()?;
         Ok(())
     }
+    /// Generate a static random address seeded from the controller's random number generator
+    /// (see [`LEAdapter::get_rand`]). The address isn't set, call
+    /// [`LEAdapter::set_random_address`] to use it.
+    pub async fn generate_static_random_address(&mut self) -> Result<BTAddress, adapter::Error> {
+        loop {
+            let rand = self.get_rand().await?;
+            let mut random = [0_u8; BT_ADDRESS_LEN];
+            random.copy_from_slice(&rand[..BT_ADDRESS_LEN]);
+            if let Some(address) = BTAddress::new_static_random(random) {
+                return Ok(address);
+            }
+        }
+    }
+    /// Generate a non-resolvable private address seeded from the controller's random number
+    /// generator (see [`LEAdapter::get_rand`]). The address isn't set, call
+    /// [`LEAdapter::set_random_address`] to use it.
+    pub async fn generate_non_resolvable_private_address(
+        &mut self,
+    ) -> Result<BTAddress, adapter::Error> {
+        loop {
+            let rand = self.get_rand().await?;
+            let mut random = [0_u8; BT_ADDRESS_LEN];
+            random.copy_from_slice(&rand[..BT_ADDRESS_LEN]);
+            if let Some(address) = BTAddress::new_non_resolvable_private(random) {
+                return Ok(address);
+            }
+        }
+    }
+    /// Replaces the `MetaEventMask`. See [`Adapter::enable_meta_events`] to only enable some
+    /// events.
+    pub async fn set_meta_event_mask(&mut self, mask: MetaEventMask) -> Result<(), adapter::Error> {
+        self.adapter.set_meta_event_mask(mask).await
+    }
     /// Set advertising data (0-31 bytes).
     /// # Errors
     /// Returns `adapter::Error::BadParameter` if `data.len() > MAX_ADV_LEN` (31).
@@ -129,81 +246,1233 @@ impl<A: adapter::Adapter, S: Deref<Target = A> + DerefMut> LEAdapter<A, S> {
             .error()?;
         Ok(())
     }
-    pub async fn meta_event_stream<'a, 'b: 'a, Buf: Storage<u8> + 'b>(
-        &'a mut self,
-    ) -> Result<impl Stream<Item = Result<RawMetaEvent<Buf>, adapter::Error>> + 'a, adapter::Error>
-    {
-        let mut mask = EventMask::zeroed();
-        mask.enable_event(EventMaskFlags::LEMetaEvent);
-        self.adapter.set_event_mask(mask).await?;
-        Ok(self.adapter.hci_event_stream().filter_map(
-            |p: Result<EventPacket<Buf>, adapter::Error>| async move {
-                let event = match p {
-                    Ok(event) => event,
-                    Err(e) => return Some(Err(e)),
-                };
-                // Ignore all non-LEMeta HCI Events
-                if event.event_code == EventCode::LEMeta {
-                    let meta_event = RawMetaEvent::try_from(event.as_ref())
-                        .map_err(|e| adapter::Error::StreamError(StreamError::EventError(e)));
-                    Some(meta_event.map(|e| e.to_owned()))
-                } else {
-                    None
-                }
-            },
-        ))
+    /// Set scan response data (0-31 bytes). Sent to active scanners that send a scan request to
+    /// a scannable advertisement.
+    /// # Errors
+    /// Returns `adapter::Error::BadParameter` if `data.len() > MAX_ADV_LEN` (31).
+    pub async fn set_scan_response_data<Buf: AsRef<[u8]>>(
+        &mut self,
+        data: &RawAdvertisement<Buf>,
+    ) -> Result<(), adapter::Error> {
+        let data = data.as_ref();
+        if data.len() > MAX_ADV_LEN {
+            return Err(adapter::Error::BadParameter);
+        }
+        self.adapter
+            .hci_send_command(le::commands::SetScanResponseData::new(data))
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
     }
-    pub async fn advertising_report_stream<
-        'a,
-        'b: 'a,
-        Buf: Storage<ReportInfo<StaticAdvBuffer>> + 'b,
-    >(
-        &'a mut self,
-    ) -> Result<
-        impl Stream<Item = Result<AdvertisingReport<Buf>, adapter::Error>> + 'a,
-        adapter::Error,
-    > {
-        let mut mask = MetaEventMask::zeroed();
-        mask.enable_event(MetaEventCode::AdvertisingReport);
-        self.set_meta_event_mask(mask).await?;
-        Ok(self.meta_event_stream().await?.filter_map(
-            |meta_event: Result<RawMetaEvent<Box<[u8]>>, adapter::Error>| async move {
-                // We expect only AdvertisingReport Meta events to get through because the HCI
-                // filter should be set for that. Otherwise if a non-`AdvertisingReport`
-                // packet gets through, this will return `PackError::BadOpcode` because
-                // an LEMeta event with an Event Code of anything but `AdvertisingReport` got
-                // through.
-                Some(meta_event.and_then(|event| {
-                    AdvertisingReport::meta_unpack_packet(event.as_ref().as_ref())
-                        .map_err(|e| adapter::Error::StreamError(StreamError::EventError(e)))
-                }))
-            },
-        ))
+    /// Returns `ErrorCode::UnsupportedFeatureOrParameterValue` if `policy` is directed and the
+    /// controller doesn't support the LE Extended Scanner Filter Policies feature.
+    async fn check_scanning_filter_policy(
+        &mut self,
+        policy: ScanningFilterPolicy,
+    ) -> Result<(), adapter::Error> {
+        if policy.is_directed()
+            && !self
+                .features()
+                .await?
+                .get_feature(LEFeatureFlags::ExtendedScannerFilterPolicies)
+        {
+            return Err(adapter::Error::ErrorCode(
+                ErrorCode::UnsupportedFeatureOrParameterValue,
+            ));
+        }
+        Ok(())
     }
-    pub async fn advertisement_stream<
-        'a,
-        'b: 'a,
-        Buf: Storage<ReportInfo<StaticAdvBuffer>> + 'b,
-    >(
-        &'a mut self,
-    ) -> Result<
-        impl Stream<Item = Result<ReportInfo<StaticAdvBuffer>, adapter::Error>> + 'a,
-        adapter::Error,
-    > {
-        Ok(self
-            .advertising_report_stream::<Buf>()
+    /// Set the extended scan parameters (scanning on the LE 1M and/or LE Coded PHY). Use instead
+    /// of [`LEAdapter::set_scan_parameters`] if [`LEAdapter::use_extended_scanning`].
+    pub async fn set_extended_scan_parameters(
+        &mut self,
+        parameters: ExtendedScanParameters,
+    ) -> Result<(), adapter::Error> {
+        self.check_scanning_filter_policy(parameters.scanning_filter_policy)
+            .await?;
+        self.adapter
+            .hci_send_command(le::commands::SetExtendedScanParameters(parameters))
             .await?
-            .map(
-                |r: Result<AdvertisingReport<Buf>, adapter::Error>| match r {
-                    Ok(report) => futures_util::future::Either::Left(
-                        futures_util::stream::iter(report).map(Ok),
-                    ),
-                    Err(err) => futures_util::future::Either::Right(futures_util::stream::once(
-                        async move { Err(err) },
-                    )),
-                },
-            )
-            .flatten())
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Enable/disable extended scanning. See [`le::commands::SetExtendedScanEnable`] for
+    /// `duration` and `period`.
+    pub async fn set_extended_scan_enable(
+        &mut self,
+        is_enabled: bool,
+        filter_duplicates: FilterDuplicates,
+        duration: u16,
+        period: u16,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::SetExtendedScanEnable {
+                is_enabled,
+                filter_duplicates,
+                duration,
+                period,
+            })
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Read the LE features supported by the controller (and cache them for
+    /// [`LEAdapter::features`]).
+    pub async fn read_local_supported_features(&mut self) -> Result<LEFeatures, adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::ReadLocalSupportedFeatures {})
+            .await?;
+        r.params.status.error()?;
+        self.state().features = Some(r.params.features);
+        Ok(r.params.features)
+    }
+    /// Returns the controller's LE features. Only the first call reads them from the controller.
+    pub async fn features(&mut self) -> Result<LEFeatures, adapter::Error> {
+        let features = self.state().features;
+        match features {
+            Some(features) => Ok(features),
+            None => self.read_local_supported_features().await,
+        }
+    }
+    /// Use already known LE features (from `Adapter::initialize`, etc) instead of reading them
+    /// from the controller.
+    pub fn set_features(&mut self, features: LEFeatures) {
+        self.state().features = Some(features);
+    }
+    /// Read the Link Layer states and state combinations supported by the controller (and cache
+    /// them for [`LEAdapter::supported_states`]).
+    pub async fn read_supported_states(&mut self) -> Result<SupportedStates, adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::ReadSupportedStates {})
+            .await?;
+        r.params.status.error()?;
+        self.state().supported_states = Some(r.params.states);
+        Ok(r.params.states)
+    }
+    /// Returns the controller's supported states. Only the first call reads them from the
+    /// controller.
+    pub async fn supported_states(&mut self) -> Result<SupportedStates, adapter::Error> {
+        let supported_states = self.state().supported_states;
+        match supported_states {
+            Some(states) => Ok(states),
+            None => self.read_supported_states().await,
+        }
+    }
+    /// Returns `true` if the extended advertising commands should be used instead of the legacy
+    /// ones (the controller supports the LE Extended Advertising feature).
+    pub async fn use_extended_advertising(&mut self) -> Result<bool, adapter::Error> {
+        Ok(self.features().await?.supports_extended_advertising())
+    }
+    /// Returns `true` if the extended scanning commands should be used instead of the legacy
+    /// ones. Extended scanning is part of the LE Extended Advertising feature.
+    pub async fn use_extended_scanning(&mut self) -> Result<bool, adapter::Error> {
+        self.use_extended_advertising().await
+    }
+    /// Read the max length of the advertising (or scan response) data of an advertising set.
+    pub async fn read_maximum_advertising_data_length(&mut self) -> Result<u16, adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::ReadMaximumAdvertisingDataLength {})
+            .await?;
+        r.params.status.error()?;
+        Ok(r.params.max_len)
+    }
+    /// Read the number of advertising sets the controller can run at the same time.
+    pub async fn read_number_of_supported_advertising_sets(
+        &mut self,
+    ) -> Result<u8, adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::ReadNumberOfSupportedAdvertisingSets {})
+            .await?;
+        r.params.status.error()?;
+        Ok(r.params.num_sets)
+    }
+    /// Create the advertising set `handle` with `parameters` (or change the parameters of the
+    /// disabled advertising set `handle`). Requires the LE Extended Advertising feature.
+    pub async fn create_advertising_set(
+        &mut self,
+        handle: AdvertisingHandle,
+        parameters: ExtendedAdvertisingParameters,
+    ) -> Result<AdvertisingSet, adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::SetExtendedAdvertisingParameters { handle, parameters })
+            .await?;
+        r.params.status.error()?;
+        Ok(AdvertisingSet {
+            handle,
+            tx_power: r.params.power_level,
+        })
+    }
+    /// Set the random address used by `set`.
+    pub async fn set_advertising_set_random_address(
+        &mut self,
+        set: &AdvertisingSet,
+        address: BTAddress,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::SetAdvertisingSetRandomAddress {
+                handle: set.handle,
+                address,
+            })
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Set the advertising data of `set` (0-1650 bytes). Data longer than one command allows is
+    /// sent in fragments.
+    /// # Errors
+    /// Returns `adapter::Error::BadParameter` if
+    /// `data.len() > MAX_EXTENDED_ADVERTISING_DATA_LEN` (1650).
+    pub async fn set_extended_advertising_data(
+        &mut self,
+        set: &AdvertisingSet,
+        data: &[u8],
+    ) -> Result<(), adapter::Error> {
+        if data.len() > MAX_EXTENDED_ADVERTISING_DATA_LEN {
+            return Err(adapter::Error::BadParameter);
+        }
+        for fragment in
+            AdvertisingDataFragment::fragments(set.handle, FragmentPreference::DEFAULT, data)
+        {
+            self.adapter
+                .hci_send_command(le::commands::SetExtendedAdvertisingData(fragment))
+                .await?
+                .params
+                .status
+                .error()?;
+        }
+        Ok(())
+    }
+    /// Set the scan response data of `set` (0-1650 bytes). Data longer than one command allows
+    /// is sent in fragments.
+    /// # Errors
+    /// Returns `adapter::Error::BadParameter` if
+    /// `data.len() > MAX_EXTENDED_ADVERTISING_DATA_LEN` (1650).
+    pub async fn set_extended_scan_response_data(
+        &mut self,
+        set: &AdvertisingSet,
+        data: &[u8],
+    ) -> Result<(), adapter::Error> {
+        if data.len() > MAX_EXTENDED_ADVERTISING_DATA_LEN {
+            return Err(adapter::Error::BadParameter);
+        }
+        for fragment in
+            AdvertisingDataFragment::fragments(set.handle, FragmentPreference::DEFAULT, data)
+        {
+            self.adapter
+                .hci_send_command(le::commands::SetExtendedScanResponseData(fragment))
+                .await?
+                .params
+                .status
+                .error()?;
+        }
+        Ok(())
+    }
+    /// Enable or disable the advertising `sets`. Disabling with no `sets` disables every
+    /// advertising set.
+    pub async fn set_extended_advertising_enable(
+        &mut self,
+        is_enabled: bool,
+        sets: &[AdvertisingSetEnable],
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::SetExtendedAdvertisingEnable {
+                is_enabled,
+                sets: sets.to_vec(),
+            })
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Start advertising with `set` until it's disabled.
+    pub async fn enable_advertising_set(
+        &mut self,
+        set: &AdvertisingSet,
+    ) -> Result<(), adapter::Error> {
+        self.set_extended_advertising_enable(true, &[AdvertisingSetEnable::new(set.handle)])
+            .await
+    }
+    /// Stop advertising with `set`.
+    pub async fn disable_advertising_set(
+        &mut self,
+        set: &AdvertisingSet,
+    ) -> Result<(), adapter::Error> {
+        self.set_extended_advertising_enable(false, &[AdvertisingSetEnable::new(set.handle)])
+            .await
+    }
+    /// Set the periodic advertising parameters of `set`. `set` must be non-connectable and
+    /// non-scannable and its periodic advertising must be disabled. Requires the LE Periodic
+    /// Advertising feature.
+    pub async fn set_periodic_advertising_parameters(
+        &mut self,
+        set: &AdvertisingSet,
+        parameters: PeriodicAdvertisingParameters,
+    ) -> Result<(), adapter::Error> {
+        if parameters.interval_min > parameters.interval_max {
+            return Err(adapter::Error::BadParameter);
+        }
+        self.adapter
+            .hci_send_command(le::commands::SetPeriodicAdvertisingParameters {
+                handle: set.handle,
+                parameters,
+            })
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Set the periodic advertising data of `set` (0-1650 bytes). Data longer than one command
+    /// allows is sent in fragments. While the periodic advertising of `set` is enabled, the data
+    /// can only be replaced by data that fits in one command (252 bytes).
+    /// # Errors
+    /// Returns `adapter::Error::BadParameter` if
+    /// `data.len() > MAX_EXTENDED_ADVERTISING_DATA_LEN` (1650).
+    pub async fn set_periodic_advertising_data(
+        &mut self,
+        set: &AdvertisingSet,
+        data: &[u8],
+    ) -> Result<(), adapter::Error> {
+        if data.len() > MAX_EXTENDED_ADVERTISING_DATA_LEN {
+            return Err(adapter::Error::BadParameter);
+        }
+        for fragment in PeriodicAdvertisingDataFragment::fragments(set.handle, data) {
+            self.adapter
+                .hci_send_command(le::commands::SetPeriodicAdvertisingData(fragment))
+                .await?
+                .params
+                .status
+                .error()?;
+        }
+        Ok(())
+    }
+    /// Replace the periodic advertising data of `set` while it is running.
+    /// # Errors
+    /// Returns `adapter::Error::BadParameter` if
+    /// `data.len() > MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN` (252).
+    pub async fn update_periodic_advertising_data(
+        &mut self,
+        set: &AdvertisingSet,
+        data: &[u8],
+    ) -> Result<(), adapter::Error> {
+        if data.len() > MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN {
+            return Err(adapter::Error::BadParameter);
+        }
+        self.set_periodic_advertising_data(set, data).await
+    }
+    /// Enable or disable the periodic advertising of `set`. Periodic advertising is only sent
+    /// while `set` itself is also enabled (see [`LEAdapter::enable_advertising_set`]).
+    pub async fn set_periodic_advertising_enable(
+        &mut self,
+        set: &AdvertisingSet,
+        is_enabled: bool,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::SetPeriodicAdvertisingEnable {
+                is_enabled,
+                include_adi: false,
+                handle: set.handle,
+            })
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Attach a periodic advertising train with `parameters` and `data` to `set` and start it
+    /// (and `set`). Update the data afterwards with
+    /// [`LEAdapter::update_periodic_advertising_data`].
+    pub async fn start_periodic_advertising(
+        &mut self,
+        set: &AdvertisingSet,
+        parameters: PeriodicAdvertisingParameters,
+        data: &[u8],
+    ) -> Result<(), adapter::Error> {
+        self.set_periodic_advertising_parameters(set, parameters)
+            .await?;
+        self.set_periodic_advertising_data(set, data).await?;
+        self.set_periodic_advertising_enable(set, true).await?;
+        self.enable_advertising_set(set).await
+    }
+    /// Stop the periodic advertising of `set` (`set` itself keeps advertising).
+    pub async fn stop_periodic_advertising(
+        &mut self,
+        set: &AdvertisingSet,
+    ) -> Result<(), adapter::Error> {
+        self.set_periodic_advertising_enable(set, false).await
+    }
+    /// Remove the (disabled) advertising `set` from the controller.
+    pub async fn remove_advertising_set(
+        &mut self,
+        set: AdvertisingSet,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::RemoveAdvertisingSet { handle: set.handle })
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Remove every advertising set from the controller.
+    pub async fn clear_advertising_sets(&mut self) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::ClearAdvertisingSets {})
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Synchronize to a periodic advertising train and wait for the
+    /// [`PeriodicAdvertisingSyncEstablished`] event. Enables the periodic advertising sync
+    /// events in the `MetaEventMask` first (other events stay enabled).
+    ///
+    /// Use [`LEAdapter::periodic_advertising_create_sync_cancel`] to stop a pending sync. The
+    /// pending `periodic_advertising_create_sync` then returns
+    /// `adapter::Error::ErrorCode(ErrorCode::OperationCancelledByHost)`.
+    pub async fn periodic_advertising_create_sync(
+        &mut self,
+        create_sync: PeriodicAdvertisingCreateSync,
+    ) -> Result<PeriodicAdvertisingSyncEstablished, adapter::Error> {
+        if create_sync.sid > PeriodicAdvertisingCreateSync::MAX_SID {
+            return Err(adapter::Error::BadParameter);
+        }
+        self.adapter
+            .enable_meta_events(&[
+                MetaEventCode::PeriodicAdvertisingSyncEstablished,
+                MetaEventCode::PeriodicAdvertisingReport,
+                MetaEventCode::PeriodicAdvertisingSyncLost,
+            ])
+            .await?;
+        let established = self.adapter.hci_send_command_and_wait(create_sync).await?;
+        established.status.error()?;
+        Ok(established)
+    }
+    /// Cancel a pending [`LEAdapter::periodic_advertising_create_sync`].
+    pub async fn periodic_advertising_create_sync_cancel(&mut self) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::PeriodicAdvertisingCreateSyncCancel {})
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Stop receiving the periodic advertising train `sync_handle`.
+    pub async fn periodic_advertising_terminate_sync(
+        &mut self,
+        sync_handle: SyncHandle,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::PeriodicAdvertisingTerminateSync { sync_handle })
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Read the total number of Periodic Advertiser List entries the controller can store.
+    pub async fn read_periodic_advertiser_list_size(&mut self) -> Result<u8, adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::ReadPeriodicAdvertiserListSize {})
+            .await?
+            .params;
+        r.status.error()?;
+        Ok(r.size)
+    }
+    pub async fn periodic_advertiser_list_add(
+        &mut self,
+        entry: PeriodicAdvertiserListEntry,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::AddDeviceToPeriodicAdvertiserList(entry))
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    pub async fn periodic_advertiser_list_remove(
+        &mut self,
+        entry: PeriodicAdvertiserListEntry,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::RemoveDeviceFromPeriodicAdvertiserList(entry))
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    pub async fn periodic_advertiser_list_clear(&mut self) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::ClearPeriodicAdvertiserList {})
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Read the total number of White List entries the controller can store.
+    pub async fn read_whitelist_size(&mut self) -> Result<u8, adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::ReadWhitelistSize {})
+            .await?;
+        r.params.status.error()?;
+        Ok(r.params.size)
+    }
+    /// Returns the host side copy of the controller's White List or `None` if its content is
+    /// unknown. The White List is tracked after the first [`LEAdapter::whitelist_clear`] or
+    /// [`LEAdapter::whitelist_add`]. Doesn't send anything to the controller.
+    pub fn whitelist(&self) -> Option<Whitelist> {
+        self.state().whitelist.clone()
+    }
+    /// Remove every device from the controller's White List and start tracking it. The first call
+    /// also reads the White List size (see [`LEAdapter::read_whitelist_size`]).
+    pub async fn whitelist_clear(&mut self) -> Result<(), adapter::Error> {
+        let capacity = self.state().whitelist.as_ref().map(Whitelist::capacity);
+        let capacity = match capacity {
+            Some(capacity) => capacity,
+            None => usize::from(self.read_whitelist_size().await?),
+        };
+        self.adapter
+            .hci_send_command(le::commands::ClearWhitelist {})
+            .await?
+            .params
+            .status
+            .error()?;
+        self.state().whitelist = Some(Whitelist::new(capacity));
+        Ok(())
+    }
+    /// Add `entry` to the controller's White List. Adding an entry already in the White List does
+    /// nothing. If the White List isn't tracked yet (see [`LEAdapter::whitelist`]), the first call
+    /// reads the White List size and starts tracking it (assuming it holds only the entries added
+    /// from now on).
+    /// # Errors
+    /// Returns `adapter::Error::ErrorCode(ErrorCode::MemoryFull)` without sending anything to the
+    /// controller if the tracked White List is already full.
+    pub async fn whitelist_add(&mut self, entry: WhitelistEntry) -> Result<(), adapter::Error> {
+        if self.state().whitelist.is_none() {
+            let capacity = usize::from(self.read_whitelist_size().await?);
+            self.state().whitelist = Some(Whitelist::new(capacity));
+        }
+        if let Some(whitelist) = self.state().whitelist.as_ref() {
+            if !whitelist.check_insert(&entry)? {
+                return Ok(());
+            }
+        }
+        self.adapter
+            .hci_send_command(le::commands::AddDeviceToWhitelist(entry))
+            .await?
+            .params
+            .status
+            .error()?;
+        if let Some(whitelist) = self.state().whitelist.as_mut() {
+            whitelist.insert(entry)?;
+        }
+        Ok(())
+    }
+    /// Remove `entry` from the controller's White List. If the White List is tracked, returns
+    /// `false` if `entry` wasn't in it (nothing is sent to the controller). Otherwise returns
+    /// `true` once the controller removed `entry`.
+    pub async fn whitelist_remove(
+        &mut self,
+        entry: WhitelistEntry,
+    ) -> Result<bool, adapter::Error> {
+        if let Some(whitelist) = self.state().whitelist.as_ref() {
+            if !whitelist.contains(&entry) {
+                return Ok(false);
+            }
+        }
+        self.adapter
+            .hci_send_command(le::commands::RemoveDeviceFromWhitelist(entry))
+            .await?
+            .params
+            .status
+            .error()?;
+        if let Some(whitelist) = self.state().whitelist.as_mut() {
+            whitelist.remove(&entry);
+        }
+        Ok(true)
+    }
+    /// Read the total number of Resolving List entries the controller can store.
+    pub async fn read_resolving_list_size(&mut self) -> Result<u8, adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::ReadResolvingListSize {})
+            .await?
+            .params;
+        r.status.error()?;
+        Ok(r.size)
+    }
+    /// Add `entry` to the controller's Resolving List. Address resolution has to be disabled
+    /// while advertising, scanning or connecting.
+    pub async fn resolving_list_add(
+        &mut self,
+        entry: ResolvingListEntry,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::AddDeviceToResolvingList(entry))
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    pub async fn resolving_list_remove(
+        &mut self,
+        peer_identity: IdentityAddress,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::RemoveDeviceFromResolvingList(peer_identity))
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    pub async fn resolving_list_clear(&mut self) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::ClearResolvingList {})
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Enable or disable resolving addresses with the Resolving List. Also required for the
+    /// controller to generate our resolvable private addresses
+    /// (`OwnAddressType::PrivateOrPublic`/`OwnAddressType::PrivateOrRandom`).
+    pub async fn set_address_resolution_enable(
+        &mut self,
+        is_enabled: bool,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::SetAddressResolutionEnable { is_enabled })
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    pub async fn set_resolvable_private_address_timeout(
+        &mut self,
+        timeout: RPATimeout,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::SetResolvablePrivateAddressTimeout(timeout))
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Read the resolvable private address currently used by the peer with `peer_identity`.
+    pub async fn read_peer_resolvable_address(
+        &mut self,
+        peer_identity: IdentityAddress,
+    ) -> Result<BTAddress, adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::ReadPeerResolvableAddress(peer_identity))
+            .await?
+            .params;
+        r.status.error()?;
+        Ok(r.address)
+    }
+    /// Read the resolvable private address we currently use with the peer with `peer_identity`.
+    pub async fn read_local_resolvable_address(
+        &mut self,
+        peer_identity: IdentityAddress,
+    ) -> Result<BTAddress, adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::ReadLocalResolvableAddress(peer_identity))
+            .await?
+            .params;
+        r.status.error()?;
+        Ok(r.address)
+    }
+    /// Replace the controller's Resolving List with `entries` (identity address and IRK pairs)
+    /// and enable address resolution. Advertising, scanning and connecting must be stopped.
+    /// # Errors
+    /// Returns `adapter::Error::ErrorCode(ErrorCode::MemoryFull)` without sending anything else to
+    /// the controller if `entries` doesn't fit in the Resolving List.
+    pub async fn load_resolving_list(
+        &mut self,
+        entries: &[ResolvingListEntry],
+    ) -> Result<(), adapter::Error> {
+        let size = self.read_resolving_list_size().await?;
+        if entries.len() > usize::from(size) {
+            return Err(adapter::Error::ErrorCode(ErrorCode::MemoryFull));
+        }
+        self.set_address_resolution_enable(false).await?;
+        self.resolving_list_clear().await?;
+        for entry in entries {
+            self.resolving_list_add(*entry).await?;
+        }
+        self.set_address_resolution_enable(true).await
+    }
+    /// Enables the events used by connections: `DisconnectionComplete` and the
+    /// `ConnectionComplete`, `EnhancedConnectionComplete`, `ConnectionUpdateComplete`,
+    /// `RemoteConnectionParametersRequest`, `PHYUpdateCompleteEvent` and `DataLengthChange` LE
+    /// Meta events. Events already enabled stay enabled (see [`Adapter::enable_events`]).
+    pub async fn enable_connection_events(&mut self) -> Result<(), adapter::Error> {
+        self.adapter
+            .enable_events(&[EventMaskFlags::DisconnectionComplete])
+            .await?;
+        self.adapter
+            .enable_meta_events(&[
+                MetaEventCode::ConnectionComplete,
+                MetaEventCode::EnhancedConnectionComplete,
+                MetaEventCode::ConnectionUpdateComplete,
+                MetaEventCode::RemoteConnectionParametersRequest,
+                MetaEventCode::PHYUpdateCompleteEvent,
+                MetaEventCode::DataLengthChange,
+            ])
+            .await
+    }
+    /// Connect to `address` as the master (Central role). Waits for the connection to be
+    /// established. Calls [`LEAdapter::enable_connection_events`] first.
+    ///
+    /// `connect` borrows the `LEAdapter` until the connection is established so it can't be
+    /// cancelled with [`LEAdapter::connect_cancel`]. To be able to cancel, use
+    /// [`LEAdapter::create_connection`] and [`LEAdapter::wait_for_connection`] instead (or send
+    /// `CreateConnectionCancel` from another `DispatcherHandle` when using a
+    /// [`Dispatcher`](super::dispatcher::Dispatcher)).
+    pub async fn connect(
+        &mut self,
+        address: BTAddress,
+        address_type: AddressType,
+        parameters: ConnectionParameters,
+    ) -> Result<Connection, adapter::Error> {
+        let pending = self
+            .create_connection(address, address_type, parameters)
+            .await?;
+        self.wait_for_connection(&pending).await
+    }
+    /// Start connecting to `address` as the master (Central role). Returns once the controller
+    /// accepted the LE Create Connection command. Calls
+    /// [`LEAdapter::enable_connection_events`] first.
+    ///
+    /// Wait for the connection with [`LEAdapter::wait_for_connection`]. Until then, the attempt
+    /// can be stopped with [`LEAdapter::connect_cancel`] and `wait_for_connection` then returns
+    /// `adapter::Error::ErrorCode(ErrorCode::NoConnection)`.
+    pub async fn create_connection(
+        &mut self,
+        address: BTAddress,
+        address_type: AddressType,
+        parameters: ConnectionParameters,
+    ) -> Result<PendingCommand<le::commands::CreateConnection>, adapter::Error> {
+        self.enable_connection_events().await?;
+        self.adapter
+            .hci_send_async_command(le::commands::CreateConnection {
+                peer_address_type: address_type,
+                peer_address: address,
+                parameters,
+            })
+            .await
+    }
+    /// Wait for the connection started by [`LEAdapter::create_connection`] to be established.
+    /// Returns `adapter::Error::CommandTimeout` after the adapter's completion timeout (see
+    /// `adapter::Adapter::completion_timeout`). The controller keeps trying to connect until
+    /// [`LEAdapter::connect_cancel`] is called.
+    pub async fn wait_for_connection(
+        &mut self,
+        pending: &PendingCommand<le::commands::CreateConnection>,
+    ) -> Result<Connection, adapter::Error> {
+        let complete = self.adapter.hci_wait_for_completion(pending).await?;
+        Ok(complete.connection()?)
+    }
+    /// Cancel a pending [`LEAdapter::create_connection`].
+    pub async fn connect_cancel(&mut self) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::CreateConnectionCancel {})
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Change the connection parameters of the connection `handle` and wait for the
+    /// `ConnectionUpdateComplete` event. The connection events must be enabled (see
+    /// [`LEAdapter::enable_connection_events`]).
+    pub async fn connection_update(
+        &mut self,
+        handle: ConnectionHandle,
+        parameters: ConnectionUpdateParameters,
+    ) -> Result<ConnectionUpdateComplete, adapter::Error> {
+        if !parameters.is_valid() {
+            return Err(adapter::Error::BadParameter);
+        }
+        let complete = self
+            .adapter
+            .hci_send_command_and_wait(le::commands::ConnectionUpdate { handle, parameters })
+            .await?;
+        complete.status.error()?;
+        Ok(complete)
+    }
+    /// Read the current TX and RX PHY of the connection `handle`.
+    pub async fn read_phy(
+        &mut self,
+        handle: ConnectionHandle,
+    ) -> Result<(Phy, Phy), adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::ReadPhy { handle })
+            .await?
+            .params;
+        r.status.error()?;
+        Ok((r.tx_phy, r.rx_phy))
+    }
+    /// Set the PHYs preferred for new connections.
+    pub async fn set_default_phy(
+        &mut self,
+        preference: PhyPreference,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::SetDefaultPhy(preference))
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Request a PHY change on `connection` and wait for the [`PhyUpdateComplete`] event. The
+    /// new PHYs are stored in `connection`. The connection events must be enabled (see
+    /// [`LEAdapter::enable_connection_events`]).
+    pub async fn set_phy(
+        &mut self,
+        connection: &mut Connection,
+        preference: PhyPreference,
+        coded_phy_options: CodedPhyOptions,
+    ) -> Result<PhyUpdateComplete, adapter::Error> {
+        let complete = self
+            .adapter
+            .hci_send_command_and_wait(le::commands::SetPhy {
+                handle: connection.handle,
+                preference,
+                coded_phy_options,
+            })
+            .await?;
+        complete.status.error()?;
+        connection.apply_phy_update(&complete);
+        Ok(complete)
+    }
+    /// Suggest the max TX packet length `tx` for the connection `handle`. If the packet lengths
+    /// change, a [`DataLengthChange`] event is sent (see [`LEAdapter::next_connection_event`]).
+    /// # Errors
+    /// Returns `adapter::Error::BadParameter` if `tx` isn't valid (see
+    /// [`PacketLength::is_valid`]).
+    pub async fn set_data_length(
+        &mut self,
+        handle: ConnectionHandle,
+        tx: PacketLength,
+    ) -> Result<(), adapter::Error> {
+        if !tx.is_valid() {
+            return Err(adapter::Error::BadParameter);
+        }
+        self.adapter
+            .hci_send_command(le::commands::SetDataLength { handle, tx })
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Read the max TX packet length the controller uses for new connections.
+    pub async fn read_suggested_default_data_length(
+        &mut self,
+    ) -> Result<PacketLength, adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::ReadSuggestedDefaultDataLength {})
+            .await?
+            .params;
+        r.status.error()?;
+        Ok(r.tx)
+    }
+    /// Set the max TX packet length the controller uses for new connections.
+    /// # Errors
+    /// Returns `adapter::Error::BadParameter` if `tx` isn't valid (see
+    /// [`PacketLength::is_valid`]).
+    pub async fn write_suggested_default_data_length(
+        &mut self,
+        tx: PacketLength,
+    ) -> Result<(), adapter::Error> {
+        if !tx.is_valid() {
+            return Err(adapter::Error::BadParameter);
+        }
+        self.adapter
+            .hci_send_command(le::commands::WriteSuggestedDefaultDataLength { tx })
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Read the longest packets the controller supports.
+    pub async fn read_maximum_data_length(&mut self) -> Result<DataLength, adapter::Error> {
+        let r = self
+            .adapter
+            .hci_send_command(le::commands::ReadMaximumDataLength {})
+            .await?
+            .params;
+        r.status.error()?;
+        Ok(r.supported)
+    }
+    /// Read the next HCI event and decode it if it is a [`DataLengthChange`] or a
+    /// [`RemoteConnectionParameterRequest`]. Every other event is returned as
+    /// [`ConnectionEvent::Other`] so nothing is lost. The connection events must be enabled (see
+    /// [`LEAdapter::enable_connection_events`]).
+    ///
+    /// Apply a `DataLengthChange` to its connection with
+    /// [`Connection::apply_data_length_change`]. Answer a `RemoteConnectionParameterRequest` with
+    /// [`LEAdapter::remote_connection_parameter_request_reply`] or
+    /// [`LEAdapter::remote_connection_parameter_request_negative_reply`].
+    pub async fn next_connection_event(&mut self) -> Result<ConnectionEvent, adapter::Error> {
+        let event = self.adapter.hci_read_event::<Box<[u8]>>().await?;
+        if event.event_code == EventCode::LEMeta {
+            let meta_event = RawMetaEvent::try_from(event.as_ref())
+                .map_err(|e| adapter::Error::StreamError(StreamError::EventError(e)))?;
+            match meta_event.code {
+                MetaEventCode::DataLengthChange => {
+                    return DataLengthChange::meta_unpack_packet(meta_event)
+                        .map(ConnectionEvent::DataLengthChange)
+                        .map_err(|e| adapter::Error::StreamError(StreamError::EventError(e)));
+                }
+                MetaEventCode::RemoteConnectionParametersRequest => {
+                    return RemoteConnectionParameterRequest::meta_unpack_packet(meta_event)
+                        .map(ConnectionEvent::RemoteConnectionParameterRequest)
+                        .map_err(|e| adapter::Error::StreamError(StreamError::EventError(e)));
+                }
+                _ => (),
+            }
+        }
+        Ok(ConnectionEvent::Other(event))
+    }
+    /// Accept a [`RemoteConnectionParameterRequest`] for the connection `handle`.
+    pub async fn remote_connection_parameter_request_reply(
+        &mut self,
+        handle: ConnectionHandle,
+        parameters: ConnectionUpdateParameters,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(le::commands::RemoteConnectionParameterRequestReply {
+                handle,
+                parameters,
+            })
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    /// Reject a [`RemoteConnectionParameterRequest`] for the connection `handle`.
+    pub async fn remote_connection_parameter_request_negative_reply(
+        &mut self,
+        handle: ConnectionHandle,
+        reason: ErrorCode,
+    ) -> Result<(), adapter::Error> {
+        self.adapter
+            .hci_send_command(
+                le::commands::RemoteConnectionParameterRequestNegativeReply { handle, reason },
+            )
+            .await?
+            .params
+            .status
+            .error()?;
+        Ok(())
+    }
+    pub async fn meta_event_stream<'a, 'b: 'a, Buf: Storage<u8> + 'b>(
+        &'a mut self,
+    ) -> Result<impl Stream<Item = Result<RawMetaEvent<Buf>, adapter::Error>> + 'a, adapter::Error>
+    {
+        self.adapter
+            .enable_events(&[EventMaskFlags::LEMetaEvent])
+            .await?;
+        Ok(self.adapter.hci_event_stream().filter_map(
+            |p: Result<EventPacket<Buf>, adapter::Error>| async move {
+                let event = match p {
+                    Ok(event) => event,
+                    Err(e) => return Some(Err(e)),
+                };
+                // Ignore all non-LEMeta HCI Events
+                if event.event_code == EventCode::LEMeta {
+                    let meta_event = RawMetaEvent::try_from(event.as_ref())
+                        .map_err(|e| adapter::Error::StreamError(StreamError::EventError(e)));
+                    Some(meta_event.map(|e| e.to_owned()))
+                } else {
+                    None
+                }
+            },
+        ))
+    }
+    pub async fn advertising_report_stream<
+        'a,
+        'b: 'a,
+        Buf: Storage<ReportInfo<StaticAdvBuffer>> + 'b,
+    >(
+        &'a mut self,
+    ) -> Result<
+        impl Stream<Item = Result<AdvertisingReport<Buf>, adapter::Error>> + 'a,
+        adapter::Error,
+    > {
+        self.adapter
+            .enable_meta_events(&[MetaEventCode::AdvertisingReport])
+            .await?;
+        Ok(self.meta_event_stream().await?.filter_map(
+            |meta_event: Result<RawMetaEvent<Box<[u8]>>, adapter::Error>| async move {
+                // Other enabled LE Meta events are skipped.
+                match meta_event {
+                    Ok(event) if event.code == MetaEventCode::AdvertisingReport => Some(
+                        AdvertisingReport::meta_unpack_packet(event.as_ref().as_ref())
+                            .map_err(|e| adapter::Error::StreamError(StreamError::EventError(e))),
+                    ),
+                    Ok(_) => None,
+                    Err(e) => Some(Err(e)),
+                }
+            },
+        ))
+    }
+    /// Stream of [`DirectedAdvertisingReport`]s. Directed advertisements are only reported when
+    /// scanning with a directed scanning filter policy (see [`LEAdapter::set_scan_parameters`]).
+    pub async fn directed_advertising_report_stream(
+        &mut self,
+    ) -> Result<
+        impl Stream<Item = Result<DirectedAdvertisingReport, adapter::Error>> + '_,
+        adapter::Error,
+    > {
+        self.adapter
+            .enable_meta_events(&[MetaEventCode::DirectedAdvertisingReport])
+            .await?;
+        Ok(self.meta_event_stream().await?.filter_map(
+            |meta_event: Result<RawMetaEvent<Box<[u8]>>, adapter::Error>| async move {
+                match meta_event {
+                    Ok(event) if event.code == MetaEventCode::DirectedAdvertisingReport => Some(
+                        DirectedAdvertisingReport::meta_unpack_packet(event.as_ref())
+                            .map_err(|e| adapter::Error::StreamError(StreamError::EventError(e))),
+                    ),
+                    Ok(_) => None,
+                    Err(e) => Some(Err(e)),
+                }
+            },
+        ))
+    }
+    /// Stream of [`ExtendedAdvertisingReport`]s (possibly fragmented). The stream ends when a
+    /// [`ScanTimeout`](le::extended_scan::ScanTimeout) event is read.
+    pub async fn extended_advertising_report_stream(
+        &mut self,
+    ) -> Result<
+        impl Stream<Item = Result<ExtendedAdvertisingReport, adapter::Error>> + '_,
+        adapter::Error,
+    > {
+        self.adapter
+            .enable_meta_events(&[
+                MetaEventCode::ExtendedAdvertisingReport,
+                MetaEventCode::ScanTimeout,
+            ])
+            .await?;
+        Ok(self
+            .meta_event_stream()
+            .await?
+            .filter_map(
+                |meta_event: Result<RawMetaEvent<Box<[u8]>>, adapter::Error>| async move {
+                    match meta_event {
+                        Ok(event) => match event.code {
+                            MetaEventCode::ScanTimeout => Some(None),
+                            MetaEventCode::ExtendedAdvertisingReport => Some(Some(
+                                ExtendedAdvertisingReport::meta_unpack_packet(event.as_ref())
+                                    .map_err(|e| {
+                                        adapter::Error::StreamError(StreamError::EventError(e))
+                                    }),
+                            )),
+                            _ => None,
+                        },
+                        Err(e) => Some(Some(Err(e))),
+                    }
+                },
+            )
+            .take_while(|report| futures_util::future::ready(report.is_some()))
+            .filter_map(futures_util::future::ready))
+    }
+    /// Stream of extended advertisements with their data fragments joined together (see
+    /// [`ExtendedReportReassembler`]). Reports advertisements on every PHY scanned on, including
+    /// legacy advertisements. The stream ends when a [`ScanTimeout`](le::extended_scan::ScanTimeout) event is read.
+    pub async fn extended_advertisement_stream(
+        &mut self,
+    ) -> Result<impl Stream<Item = Result<ExtendedReportInfo, adapter::Error>> + '_, adapter::Error>
+    {
+        Ok(self
+            .extended_advertising_report_stream()
+            .await?
+            .scan(ExtendedReportReassembler::new(), |reassembler, report| {
+                let reports: Vec<Result<ExtendedReportInfo, adapter::Error>> = match report {
+                    Ok(report) => report
+                        .reports
+                        .into_iter()
+                        .filter_map(|report| reassembler.push(report))
+                        .map(Ok)
+                        .collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                futures_util::future::ready(Some(futures_util::stream::iter(reports)))
+            })
+            .flatten())
+    }
+    /// Synchronize to the periodic advertising of advertising set `sid` of `address` (see
+    /// [`LEAdapter::periodic_advertising_create_sync`]). Returns the established sync and a
+    /// stream of its [`PeriodicAdvertisingReport`]s with their data fragments joined together.
+    /// The stream ends when a [`PeriodicAdvertisingSyncLost`] event is read for the sync.
+    pub async fn periodic_sync(
+        &mut self,
+        address_type: PeerAddressType,
+        address: BTAddress,
+        sid: u8,
+    ) -> Result<
+        (
+            PeriodicAdvertisingSyncEstablished,
+            impl Stream<Item = Result<PeriodicAdvertisingReport, adapter::Error>> + '_,
+        ),
+        adapter::Error,
+    > {
+        let established = self
+            .periodic_advertising_create_sync(PeriodicAdvertisingCreateSync::new(
+                address_type,
+                address,
+                sid,
+            ))
+            .await?;
+        let sync_handle = established.sync_handle;
+        let reports = self
+            .meta_event_stream()
+            .await?
+            .filter_map(
+                move |meta_event: Result<RawMetaEvent<Box<[u8]>>, adapter::Error>| async move {
+                    let event = match meta_event {
+                        Ok(event) => event,
+                        Err(e) => return Some(Some(Err(e))),
+                    };
+                    let to_error = |e| adapter::Error::StreamError(StreamError::EventError(e));
+                    match event.code {
+                        MetaEventCode::PeriodicAdvertisingSyncLost => {
+                            match PeriodicAdvertisingSyncLost::meta_unpack_packet(event.as_ref()) {
+                                Ok(lost) if lost.sync_handle == sync_handle => Some(None),
+                                Ok(_) => None,
+                                Err(e) => Some(Some(Err(to_error(e)))),
+                            }
+                        }
+                        MetaEventCode::PeriodicAdvertisingReport => {
+                            match PeriodicAdvertisingReport::meta_unpack_packet(event.as_ref()) {
+                                Ok(report) if report.sync_handle == sync_handle => {
+                                    Some(Some(Ok(report)))
+                                }
+                                Ok(_) => None,
+                                Err(e) => Some(Some(Err(to_error(e)))),
+                            }
+                        }
+                        _ => None,
+                    }
+                },
+            )
+            .take_while(|report| futures_util::future::ready(report.is_some()))
+            .filter_map(futures_util::future::ready)
+            .scan(
+                None,
+                |pending: &mut Option<PeriodicAdvertisingReport>, report| {
+                    let report = match report {
+                        Ok(report) => report,
+                        Err(e) => return futures_util::future::ready(Some(Some(Err(e)))),
+                    };
+                    let joined = match pending.take() {
+                        Some(mut joined) => {
+                            joined.data.extend_from_slice(&report.data);
+                            joined.data_status = report.data_status;
+                            joined.rssi = report.rssi;
+                            joined
+                        }
+                        None => report,
+                    };
+                    if joined.data_status == DataStatus::Incomplete {
+                        *pending = Some(joined);
+                        futures_util::future::ready(Some(None))
+                    } else {
+                        futures_util::future::ready(Some(Some(Ok(joined))))
+                    }
+                },
+            )
+            .filter_map(futures_util::future::ready);
+        Ok((established, reports))
+    }
+    pub async fn advertisement_stream<
+        'a,
+        'b: 'a,
+        Buf: Storage<ReportInfo<StaticAdvBuffer>> + 'b,
+    >(
+        &'a mut self,
+    ) -> Result<
+        impl Stream<Item = Result<ReportInfo<StaticAdvBuffer>, adapter::Error>> + 'a,
+        adapter::Error,
+    > {
+        Ok(self
+            .advertising_report_stream::<Buf>()
+            .await?
+            .map(
+                |r: Result<AdvertisingReport<Buf>, adapter::Error>| match r {
+                    Ok(report) => futures_util::future::Either::Left(
+                        futures_util::stream::iter(report).map(Ok),
+                    ),
+                    Err(err) => futures_util::future::Either::Right(futures_util::stream::once(
+                        async move { Err(err) },
+                    )),
+                },
+            )
+            .flatten())
+    }
+}
+/// Uses the controller's LE Encrypt and LE Rand commands.
+impl<A: adapter::Adapter, S: Deref<Target = A> + DerefMut> CryptoProvider for LEAdapter<A, S> {
+    type Error = adapter::Error;
+
+    fn e(
+        &mut self,
+        mut key: [u8; AES_KEY_LEN],
+        mut plaintext: [u8; AES_BLOCK_LEN],
+    ) -> BoxFuture<'_, Result<[u8; AES_BLOCK_LEN], Self::Error>> {
+        Box::pin(async move {
+            key.reverse();
+            plaintext.reverse();
+            let mut encrypted = self.encrypt(key, plaintext).await?;
+            encrypted.reverse();
+            Ok(encrypted)
+        })
+    }
+
+    fn rand<'a>(&'a mut self, buf: &'a mut [u8]) -> BoxFuture<'a, Result<(), Self::Error>> {
+        Box::pin(async move {
+            for chunk in buf.chunks_mut(RAND_LEN) {
+                let rand = self.get_rand().await?;
+                chunk.copy_from_slice(&rand[..chunk.len()]);
+            }
+            Ok(())
+        })
     }
 }
 /*
@@ -1,22 +1,60 @@
 //! Contains logic for HCI Adapters (usually byte streams).
+pub mod credits;
+pub mod dispatcher;
 pub mod le;
+pub mod timeout;
 
 use crate::bytes::Storage;
 use crate::hci::adapter;
 use crate::hci::adapters::le::LEAdapter;
-use crate::hci::baseband::{EventMask, Reset, SetEventMask};
-use crate::hci::command::Command;
+use crate::hci::baseband::{EventMask, EventMaskFlags, Reset, SetEventMask};
+use crate::hci::command::{AsyncCommand, Command, PendingCommand};
 use crate::hci::event::EventPacket;
+use crate::hci::info::{
+    ControllerInfo, ReadBDADDR, ReadLocalSupportedCommands, ReadLocalSupportedFeatures,
+    ReadLocalVersionInformation, SupportedCommands,
+};
+use crate::hci::le::commands as le_commands;
+use crate::hci::le::mask::{MetaEventMask, SetMetaEventMask};
+use crate::hci::le::MetaEventCode;
+use crate::hci::link_control::{Disconnect, DisconnectionComplete};
+use crate::hci::{ConnectionHandle, ErrorCode, Opcode};
 use crate::Stream;
+use alloc::rc::Rc;
+use core::cell::{RefCell, RefMut};
 use core::ops::{Deref, DerefMut};
 use core::pin::Pin;
+use core::time::Duration;
 
+/// Controller state tracked by [`Adapter`]. Shared with the adapters borrowed from it
+/// ([`Adapter::as_mut`], [`Adapter::le_mut`], etc) so changes made through them are kept.
+#[derive(Clone, Debug)]
+pub(crate) struct State {
+    supported_commands: Option<SupportedCommands>,
+    event_mask: EventMask,
+    meta_event_mask: MetaEventMask,
+    pub(crate) le: le::State,
+}
+impl Default for State {
+    fn default() -> Self {
+        State {
+            supported_commands: None,
+            event_mask: EventMask::DEFAULT,
+            meta_event_mask: MetaEventMask::DEFAULT,
+            le: le::State::default(),
+        }
+    }
+}
 pub struct Adapter<A: adapter::Adapter, S: Deref<Target = A>> {
     pub adapter: Pin<S>,
+    state: Rc<RefCell<State>>,
 }
 impl<A: adapter::Adapter, S: Deref<Target = A> + DerefMut> Adapter<A, S> {
     pub fn new(adapter: Pin<S>) -> Self {
-        Self { adapter }
+        Self {
+            adapter,
+            state: Rc::new(RefCell::new(State::default())),
+        }
     }
     pub fn pin(adapter: S) -> Self
     where
@@ -33,11 +71,37 @@ impl<A: adapter::Adapter, S: Deref<Target = A> + DerefMut> Adapter<A, S> {
     pub fn as_ref(&self) -> Adapter<A, &'_ A> {
         Adapter {
             adapter: self.adapter.as_ref(),
+            state: Rc::clone(&self.state),
         }
     }
     pub fn as_mut(&mut self) -> Adapter<A, &'_ mut A> {
         Adapter {
             adapter: self.adapter.as_mut(),
+            state: Rc::clone(&self.state),
+        }
+    }
+    pub(crate) fn state(&self) -> RefMut<'_, State> {
+        self.state.borrow_mut()
+    }
+    /// Commands the controller supports (set by [`Adapter::initialize`]). If set, commands the
+    /// controller doesn't support fail with [`adapter::Error::UnsupportedCommand`] without being
+    /// sent.
+    pub fn supported_commands(&self) -> Option<SupportedCommands> {
+        self.state().supported_commands
+    }
+    pub fn set_supported_commands(&mut self, supported_commands: Option<SupportedCommands>) {
+        self.state().supported_commands = supported_commands;
+    }
+    /// Returns [`adapter::Error::UnsupportedCommand`] if `opcode` is known to be unsupported.
+    /// Commands not in the [`SupportedCommands`] bitmap are assumed to be supported.
+    pub fn check_supported(&self, opcode: Opcode) -> Result<(), adapter::Error> {
+        match self
+            .state()
+            .supported_commands
+            .and_then(|c| c.is_supported(opcode))
+        {
+            Some(false) => Err(adapter::Error::UnsupportedCommand(opcode)),
+            _ => Ok(()),
         }
     }
     pub fn le(self) -> le::LEAdapter<A, S> {
@@ -50,8 +114,44 @@ impl<A: adapter::Adapter, S: Deref<Target = A> + DerefMut> Adapter<A, S> {
         &mut self,
         cmd: Cmd,
     ) -> Result<Cmd::Return, adapter::Error> {
+        self.check_supported(Cmd::opcode())?;
         self.adapter_mut().send_command(cmd).await
     }
+    /// Same as [`Adapter::hci_send_command`] but waits `timeout` for the response instead of the
+    /// adapter's command timeout.
+    pub async fn hci_send_command_timeout<'a, 'c: 'a, Cmd: Command + 'c>(
+        &mut self,
+        cmd: Cmd,
+        timeout: Duration,
+    ) -> Result<Cmd::Return, adapter::Error> {
+        self.check_supported(Cmd::opcode())?;
+        self.adapter_mut().send_command_timeout(cmd, timeout).await
+    }
+    /// Send an [`AsyncCommand`] and wait for the controller to accept it (`CommandStatus`).
+    /// See [`adapter::Adapter::send_async_command`].
+    pub async fn hci_send_async_command<Cmd: AsyncCommand>(
+        &mut self,
+        cmd: Cmd,
+    ) -> Result<PendingCommand<Cmd>, adapter::Error> {
+        self.check_supported(Cmd::opcode())?;
+        self.adapter_mut().send_async_command(cmd).await
+    }
+    /// Wait for the completion event of `pending`.
+    /// See [`adapter::Adapter::wait_for_completion`].
+    pub async fn hci_wait_for_completion<Cmd: AsyncCommand>(
+        &mut self,
+        pending: &PendingCommand<Cmd>,
+    ) -> Result<Cmd::Completion, adapter::Error> {
+        self.adapter_mut().wait_for_completion(pending).await
+    }
+    /// Send an [`AsyncCommand`] and wait for its completion event.
+    pub async fn hci_send_command_and_wait<Cmd: AsyncCommand>(
+        &mut self,
+        cmd: Cmd,
+    ) -> Result<Cmd::Completion, adapter::Error> {
+        let pending = self.hci_send_async_command(cmd).await?;
+        self.hci_wait_for_completion(&pending).await
+    }
     pub async fn hci_read_event<Buf: Storage<u8>>(
         &mut self,
     ) -> Result<EventPacket<Buf>, adapter::Error> {
@@ -64,18 +164,144 @@ impl<A: adapter::Adapter, S: Deref<Target = A> + DerefMut> Adapter<A, S> {
             Some((s.adapter.as_mut().read_event().await, s))
         })
     }
+    /// `EventMask` last set with [`Adapter::set_event_mask`]. `EventMask::DEFAULT` (the
+    /// controller default after a `Reset`) until then.
+    pub fn event_mask(&self) -> EventMask {
+        self.state().event_mask
+    }
     pub async fn set_event_mask(&mut self, mask: EventMask) -> Result<(), adapter::Error> {
         self.hci_send_command(SetEventMask(mask))
             .await?
             .params
             .status
             .error()?;
+        self.state().event_mask = mask;
         Ok(())
     }
+    /// Enables `flags` in the tracked `EventMask` (see [`Adapter::event_mask`]). Other events
+    /// stay as they are. Nothing is sent if `flags` are already enabled.
+    pub async fn enable_events(&mut self, flags: &[EventMaskFlags]) -> Result<(), adapter::Error> {
+        let current = self.event_mask();
+        let mut mask = current;
+        for flag in flags {
+            mask.enable_event(*flag);
+        }
+        if mask == current {
+            return Ok(());
+        }
+        self.set_event_mask(mask).await
+    }
+    /// `MetaEventMask` last set with [`Adapter::set_meta_event_mask`]. `MetaEventMask::DEFAULT`
+    /// (the controller default after a `Reset`) until then.
+    pub fn meta_event_mask(&self) -> MetaEventMask {
+        self.state().meta_event_mask
+    }
+    pub async fn set_meta_event_mask(&mut self, mask: MetaEventMask) -> Result<(), adapter::Error> {
+        self.hci_send_command(SetMetaEventMask(mask))
+            .await?
+            .params
+            .status
+            .error()?;
+        self.state().meta_event_mask = mask;
+        Ok(())
+    }
+    /// Enables `events` in the tracked `MetaEventMask` (see [`Adapter::meta_event_mask`]) and LE
+    /// Meta events in the `EventMask`. Other events stay as they are. Nothing is sent for masks
+    /// that already have the events enabled.
+    pub async fn enable_meta_events(
+        &mut self,
+        events: &[MetaEventCode],
+    ) -> Result<(), adapter::Error> {
+        self.enable_events(&[EventMaskFlags::LEMetaEvent]).await?;
+        let current = self.meta_event_mask();
+        let mut mask = current;
+        for event in events {
+            mask.enable_event(*event);
+        }
+        if mask == current {
+            return Ok(());
+        }
+        self.set_meta_event_mask(mask).await
+    }
+    /// Resets the controller. The tracked event masks go back to their defaults and a tracked
+    /// White List (see [`LEAdapter::whitelist`]) is emptied.
     pub async fn reset(&mut self) -> Result<(), adapter::Error> {
         self.hci_send_command(Reset).await?.params.status.error()?;
+        let mut state = self.state();
+        state.event_mask = EventMask::DEFAULT;
+        state.meta_event_mask = MetaEventMask::DEFAULT;
+        if let Some(whitelist) = state.le.whitelist.as_mut() {
+            whitelist.clear();
+        }
         Ok(())
     }
+    /// Runs the standard controller bring-up sequence and returns what was read from the
+    /// controller. Sends (in order) `Reset`, `ReadLocalVersionInformation`,
+    /// `ReadLocalSupportedCommands`, `ReadLocalSupportedFeatures`, `ReadBDADDR`,
+    /// LE `ReadBufferSize`, LE `ReadLocalSupportedFeatures` and then sets the default `EventMask`
+    /// (with LE Meta Events enabled) and the default `MetaEventMask`. The `SupportedCommands`
+    /// are kept (see [`Adapter::supported_commands`]) to fail fast on unsupported commands.
+    pub async fn initialize(&mut self) -> Result<ControllerInfo, adapter::Error> {
+        self.set_supported_commands(None);
+        self.reset().await?;
+        let version = self
+            .hci_send_command(ReadLocalVersionInformation {})
+            .await?
+            .params;
+        version.status.error()?;
+        let supported_commands = self
+            .hci_send_command(ReadLocalSupportedCommands {})
+            .await?
+            .params;
+        supported_commands.status.error()?;
+        self.set_supported_commands(Some(supported_commands.supported_commands));
+        let features = self
+            .hci_send_command(ReadLocalSupportedFeatures {})
+            .await?
+            .params;
+        features.status.error()?;
+        let address = self.hci_send_command(ReadBDADDR {}).await?.params;
+        address.status.error()?;
+        let le_buffer_size = self
+            .hci_send_command(le_commands::ReadBufferSize {})
+            .await?
+            .params;
+        le_buffer_size.status.error()?;
+        let le_features = self
+            .hci_send_command(le_commands::ReadLocalSupportedFeatures {})
+            .await?
+            .params;
+        le_features.status.error()?;
+        let mut event_mask = EventMask::DEFAULT;
+        event_mask.enable_event(EventMaskFlags::LEMetaEvent);
+        self.set_event_mask(event_mask).await?;
+        self.set_meta_event_mask(MetaEventMask::DEFAULT).await?;
+        Ok(ControllerInfo {
+            version,
+            supported_commands: supported_commands.supported_commands,
+            features: features.features,
+            address: address.address,
+            le_buffer_size: le_buffer_size.buffer_size,
+            le_features: le_features.features,
+        })
+    }
+    /// Disconnect the connection `handle` (with reason
+    /// `ErrorCode::OtherEndTerminatedConnectionUserEndedConnection`) and wait for the
+    /// `DisconnectionComplete` event. The `DisconnectionComplete` event must be enabled in the
+    /// `EventMask` (it is by default).
+    pub async fn disconnect(
+        &mut self,
+        handle: ConnectionHandle,
+    ) -> Result<DisconnectionComplete, adapter::Error> {
+        let complete = self
+            .hci_send_command_and_wait(Disconnect {
+                handle,
+                reason: ErrorCode::OtherEndTerminatedConnectionUserEndedConnection,
+            })
+            .await?;
+        complete.status.error()?;
+        Ok(complete)
+    }
 }
 /*
 use crate::hci::{
@@ -140,3 +366,145 @@ impl<R: HCIStreamable> AsMut<Stream<R>> for Adapter<R> {
     }
 }
 */
+#[cfg(test)]
+mod tests {
+    use super::Adapter;
+    use crate::bytes::Storage;
+    use crate::hci::adapter;
+    use crate::hci::baseband::{EventMask, Reset, SetEventMask};
+    use crate::hci::command::{Command, CommandPacket};
+    use crate::hci::event::{EventCode, EventPacket};
+    use crate::hci::info::SupportedCommands;
+    use crate::hci::le::mask::SetMetaEventMask;
+    use crate::hci::le::MetaEventCode;
+    use crate::hci::{ErrorCode, Opcode};
+    use alloc::boxed::Box;
+    use alloc::collections::VecDeque;
+    use alloc::rc::Rc;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+    use core::pin::Pin;
+    use core::task::Poll;
+    use futures_util::future::{poll_fn, LocalBoxFuture};
+    use futures_util::FutureExt;
+
+    #[derive(Default)]
+    struct MockState {
+        written: Vec<(Opcode, Box<[u8]>)>,
+        events: VecDeque<EventPacket<Box<[u8]>>>,
+    }
+    /// Answers every written command with a successful `CommandComplete`.
+    #[derive(Default)]
+    struct MockAdapter {
+        state: Rc<RefCell<MockState>>,
+    }
+    impl adapter::Adapter for MockAdapter {
+        fn write_command<'s, 'p: 's>(
+            self: Pin<&'s mut Self>,
+            packet: CommandPacket<&'p [u8]>,
+        ) -> LocalBoxFuture<'s, Result<(), adapter::Error>> {
+            let mut state = self.state.borrow_mut();
+            state
+                .written
+                .push((packet.opcode, Box::from(packet.parameters)));
+            let mut parameters = [0x01, 0x00, 0x00, ErrorCode::Ok.into()];
+            packet
+                .opcode
+                .pack(&mut parameters[1..3])
+                .expect("opcode fits");
+            state.events.push_back(EventPacket::new(
+                EventCode::CommandComplete,
+                Box::from(&parameters[..]),
+            ));
+            Box::pin(futures_util::future::ready(Ok(())))
+        }
+
+        fn read_event<'s, 'p: 's, S: Storage<u8> + 'p>(
+            self: Pin<&'s mut Self>,
+        ) -> LocalBoxFuture<'s, Result<EventPacket<S>, adapter::Error>> {
+            let state = self.state.clone();
+            Box::pin(poll_fn(move |_| {
+                match state.borrow_mut().events.pop_front() {
+                    Some(event) => Poll::Ready(Ok(EventPacket::new(
+                        event.event_code,
+                        S::from_slice(event.parameters()),
+                    ))),
+                    None => Poll::Pending,
+                }
+            }))
+        }
+    }
+    #[test]
+    fn test_masks_shared_with_le_mut() {
+        let mock = MockAdapter::default();
+        let state = mock.state.clone();
+        let mut adapter = Adapter::pin(Box::new(mock));
+        adapter
+            .le_mut()
+            .adapter_mut()
+            .enable_meta_events(&[MetaEventCode::DataLengthChange])
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        adapter
+            .le_mut()
+            .adapter_mut()
+            .enable_meta_events(&[MetaEventCode::PHYUpdateCompleteEvent])
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        let state = state.borrow();
+        // LE Meta events were only enabled in the `EventMask` once.
+        let set_event_masks = state
+            .written
+            .iter()
+            .filter(|(opcode, _)| *opcode == SetEventMask::opcode())
+            .count();
+        assert_eq!(set_event_masks, 1);
+        let (opcode, parameters) = state.written.last().unwrap();
+        assert_eq!(*opcode, SetMetaEventMask::opcode());
+        let mask = SetMetaEventMask::unpack_from(parameters).unwrap().0;
+        assert!(mask.get_event(MetaEventCode::DataLengthChange));
+        assert!(mask.get_event(MetaEventCode::PHYUpdateCompleteEvent));
+        assert_eq!(adapter.meta_event_mask(), mask);
+    }
+    #[test]
+    fn test_unsupported_command_not_sent() {
+        let mock = MockAdapter::default();
+        let state = mock.state.clone();
+        let mut adapter = Adapter::pin(Box::new(mock));
+        let mut supported_commands = SupportedCommands::ZEROED;
+        assert!(supported_commands.set_supported(SetEventMask::opcode(), true));
+        adapter.set_supported_commands(Some(supported_commands));
+        assert_eq!(
+            adapter
+                .hci_send_command(Reset)
+                .now_or_never()
+                .unwrap()
+                .err(),
+            Some(adapter::Error::UnsupportedCommand(Reset::opcode()))
+        );
+        // Borrowed adapters check the same `SupportedCommands`.
+        assert_eq!(
+            adapter
+                .le_mut()
+                .adapter_mut()
+                .hci_send_command(Reset)
+                .now_or_never()
+                .unwrap()
+                .err(),
+            Some(adapter::Error::UnsupportedCommand(Reset::opcode()))
+        );
+        assert!(state.borrow().written.is_empty());
+        adapter
+            .set_event_mask(EventMask::DEFAULT)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.borrow().written.len(), 1);
+        // Without `SupportedCommands` every command is sent.
+        adapter.set_supported_commands(None);
+        adapter.reset().now_or_never().unwrap().unwrap();
+        assert_eq!(state.borrow().written[1].0, Reset::opcode());
+    }
+}
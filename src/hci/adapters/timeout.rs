@@ -0,0 +1,74 @@
+//! [`TimeoutAdapter`] sets the [`Timer`], command timeout and completion timeout of any
+//! [`adapter::Adapter`].
+use crate::bytes::Storage;
+use crate::hci::adapter;
+use crate::hci::command::CommandPacket;
+use crate::hci::event::EventPacket;
+use crate::hci::timer::Timer;
+use core::pin::Pin;
+use core::time::Duration;
+use futures_util::future::LocalBoxFuture;
+
+/// [`adapter::Adapter`] wrapper that makes commands sent through it time out after
+/// `command_timeout` and completion events after `completion_timeout` (measured with `timer`).
+pub struct TimeoutAdapter<A: adapter::Adapter, T: Timer> {
+    adapter: A,
+    timer: T,
+    command_timeout: Duration,
+    completion_timeout: Duration,
+}
+impl<A: adapter::Adapter, T: Timer> TimeoutAdapter<A, T> {
+    /// Wraps `adapter` with the [`adapter::DEFAULT_COMMAND_TIMEOUT`] and
+    /// [`adapter::DEFAULT_COMPLETION_TIMEOUT`].
+    pub fn new(adapter: A, timer: T) -> Self {
+        Self::with_timeout(adapter, timer, adapter::DEFAULT_COMMAND_TIMEOUT)
+    }
+    pub fn with_timeout(adapter: A, timer: T, command_timeout: Duration) -> Self {
+        Self {
+            adapter,
+            timer,
+            command_timeout,
+            completion_timeout: adapter::DEFAULT_COMPLETION_TIMEOUT,
+        }
+    }
+    pub fn into_inner(self) -> A {
+        self.adapter
+    }
+    pub fn set_command_timeout(&mut self, command_timeout: Duration) {
+        self.command_timeout = command_timeout;
+    }
+    pub fn set_completion_timeout(&mut self, completion_timeout: Duration) {
+        self.completion_timeout = completion_timeout;
+    }
+    pub fn adapter_pinned(self: Pin<&mut Self>) -> Pin<&mut A> {
+        // Safety: `adapter` is structurally pinned and never moved out of a pinned `Self`.
+        unsafe { Pin::new_unchecked(&mut self.get_unchecked_mut().adapter) }
+    }
+}
+impl<A: adapter::CancelSafeRead, T: Timer> adapter::CancelSafeRead for TimeoutAdapter<A, T> {}
+impl<A: adapter::Adapter, T: Timer> adapter::Adapter for TimeoutAdapter<A, T> {
+    fn write_command<'s, 'p: 's>(
+        self: Pin<&'s mut Self>,
+        packet: CommandPacket<&'p [u8]>,
+    ) -> LocalBoxFuture<'s, Result<(), adapter::Error>> {
+        self.adapter_pinned().write_command(packet)
+    }
+
+    fn timer(&self) -> Option<&dyn Timer> {
+        Some(&self.timer)
+    }
+
+    fn completion_timeout(&self) -> Duration {
+        self.completion_timeout
+    }
+
+    fn command_timeout(&self) -> Duration {
+        self.command_timeout
+    }
+
+    fn read_event<'s, 'p: 's, S: Storage<u8> + 'p>(
+        self: Pin<&'s mut Self>,
+    ) -> LocalBoxFuture<'s, Result<EventPacket<S>, adapter::Error>> {
+        self.adapter_pinned().read_event()
+    }
+}
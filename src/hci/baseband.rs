@@ -55,6 +55,7 @@ impl Command for Reset {
         Ok(Reset)
     }
 }
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
 pub enum EventMaskFlags {
     InquiryComplete = 0x00,
     InquiryResult = 0x01,
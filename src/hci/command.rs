@@ -1,6 +1,6 @@
 //! HCI Command and command utilities.
 use crate::bytes::Storage;
-use crate::hci::event::{Event, EventPacket, ReturnEvent};
+use crate::hci::event::{CommandStatus, Event, EventCode, EventPacket, ReturnEvent};
 use crate::hci::packet::{PacketType, RawPacket};
 use crate::hci::{Opcode, OPCODE_LEN};
 use crate::PackError;
@@ -105,4 +105,54 @@ pub trait Command {
         }
         Ok(None)
     }
+    /// Unpacks `event` if it is a `CommandStatus` for this command. Some controllers answer
+    /// commands that normally return a `CommandComplete` with a `CommandStatus` if they fail.
+    fn unpack_command_status(
+        event: EventPacket<&[u8]>,
+    ) -> Result<Option<CommandStatus>, PackError> {
+        if event.event_code() == EventCode::CommandStatus {
+            if let Some(guess) = CommandStatus::guess_command_opcode(event.parameters()) {
+                if Self::opcode() == guess {
+                    return Ok(Some(CommandStatus::event_unpack_from(event.parameters())?));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+/// HCI Command that is answered with a `CommandStatus` first and finishes later with a separate
+/// completion event (`AsyncCommand::Completion`). For example, `CreateConnection` finishes
+/// with a `ConnectionComplete` event.
+pub trait AsyncCommand: Command<Return = CommandStatus> {
+    type Completion: Event;
+    /// Returns `true` if `completion` finishes this command. Used to tell apart completion
+    /// events of different commands (by connection handle for example). Defaults to `true`.
+    fn is_completion(&self, _completion: &Self::Completion) -> bool {
+        true
+    }
+    /// Unpacks `event` if it is the completion event of this command.
+    fn unpack_completion(
+        &self,
+        event: EventPacket<&[u8]>,
+    ) -> Result<Option<Self::Completion>, PackError> {
+        if Self::Completion::matches_event_packet(&event) {
+            let completion = Self::Completion::event_unpack_from(event.parameters())?;
+            if self.is_completion(&completion) {
+                return Ok(Some(completion));
+            }
+        }
+        Ok(None)
+    }
+}
+/// [`AsyncCommand`] accepted by the controller (`CommandStatus` with `ErrorCode::Ok`) that is
+/// waiting for its completion event.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PendingCommand<Cmd: AsyncCommand> {
+    pub command: Cmd,
+    pub status: CommandStatus,
+}
+impl<Cmd: AsyncCommand> PendingCommand<Cmd> {
+    pub fn new(command: Cmd, status: CommandStatus) -> Self {
+        Self { command, status }
+    }
 }
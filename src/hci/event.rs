@@ -1,9 +1,12 @@
 //! HCI Event and event utilities.
 use crate::bytes::{StaticBuf, Storage};
+use crate::hci::le::RawMetaEvent;
+use crate::hci::link_control::DisconnectionComplete;
 use crate::hci::packet::{PacketType, RawPacket};
-use crate::hci::{ErrorCode, Opcode, EVENT_CODE_LEN, EVENT_MAX_LEN, OPCODE_LEN};
-use crate::ConversionError;
+use crate::hci::{ConnectionHandle, ErrorCode, Opcode, EVENT_CODE_LEN, EVENT_MAX_LEN, OPCODE_LEN};
 use crate::PackError;
+use crate::{CompanyID, ConversionError};
+use alloc::vec::Vec;
 use core::convert::{TryFrom, TryInto};
 use core::fmt::Formatter;
 
@@ -25,6 +28,7 @@ pub enum EventCode {
     QoSSetupComplete = 0x0D,
     CommandComplete = 0x0E,
     CommandStatus = 0x0F,
+    HardwareError = 0x10,
     FlushOccurred = 0x11,
     RoleChange = 0x12,
     NumberOfCompletedPackets = 0x13,
@@ -105,6 +109,7 @@ impl TryFrom<u8> for EventCode {
             0x0D => Ok(EventCode::QoSSetupComplete),
             0x0E => Ok(EventCode::CommandComplete),
             0x0F => Ok(EventCode::CommandStatus),
+            0x10 => Ok(EventCode::HardwareError),
             0x11 => Ok(EventCode::FlushOccurred),
             0x12 => Ok(EventCode::RoleChange),
             0x13 => Ok(EventCode::NumberOfCompletedPackets),
@@ -169,6 +174,11 @@ pub trait Event {
     fn event_unpack_from(buf: &[u8]) -> Result<Self, PackError>
     where
         Self: Sized;
+    /// Returns `true` if `packet` could contain this `Event` (without unpacking it). Only checks
+    /// the `EventCode` by default.
+    fn matches_event_packet(packet: &EventPacket<&[u8]>) -> bool {
+        packet.event_code == Self::EVENT_CODE
+    }
     fn unpack_event_packet<S: AsRef<[u8]>>(packet: &EventPacket<S>) -> Result<Self, PackError>
     where
         Self: Sized,
@@ -217,6 +227,7 @@ impl From<FullEventBuffer> for [u8; EVENT_MAX_LEN] {
 }
 pub type StaticEventBuffer = StaticBuf<u8, FullEventBuffer>;
 /// Unprocessed HCI Event Packet
+#[derive(Clone)]
 pub struct EventPacket<Storage> {
     pub event_code: EventCode,
     pub parameters: Storage,
@@ -424,3 +435,503 @@ impl Event for CommandStatus {
         })
     }
 }
+impl ReturnEvent for CommandStatus {
+    fn command_opcode(&self) -> Opcode {
+        self.opcode
+    }
+    fn guess_command_opcode(buf: &[u8]) -> Option<Opcode> {
+        if buf.len() >= COMMAND_STATUS_LEN {
+            Opcode::unpack(&buf[2..4]).ok()
+        } else {
+            None
+        }
+    }
+}
+/// Encryption state reported by [`EncryptionChange`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum EncryptionEnabled {
+    Off = 0x00,
+    /// E0 (BR/EDR) or AES-CCM (LE) encryption.
+    On = 0x01,
+    /// AES-CCM encryption on a BR/EDR link.
+    OnAESCCM = 0x02,
+}
+impl From<EncryptionEnabled> for u8 {
+    fn from(e: EncryptionEnabled) -> Self {
+        e as u8
+    }
+}
+impl TryFrom<u8> for EncryptionEnabled {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(EncryptionEnabled::Off),
+            0x01 => Ok(EncryptionEnabled::On),
+            0x02 => Ok(EncryptionEnabled::OnAESCCM),
+            _ => Err(ConversionError(())),
+        }
+    }
+}
+/// Encryption Change Event. Sent when encryption of a connection is turned on or off.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct EncryptionChange {
+    pub status: ErrorCode,
+    pub handle: ConnectionHandle,
+    pub encryption_enabled: EncryptionEnabled,
+}
+impl EncryptionChange {
+    pub const BYTE_LEN: usize = 1 + ConnectionHandle::BYTE_LEN + 1;
+}
+impl Event for EncryptionChange {
+    const EVENT_CODE: EventCode = EventCode::EncryptionChange;
+
+    fn event_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn event_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(EncryptionChange {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            handle: ConnectionHandle::unpack_from(&buf[1..3])?,
+            encryption_enabled: EncryptionEnabled::try_from(buf[3])
+                .map_err(|_| PackError::bad_index(3))?,
+        })
+    }
+
+    fn event_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.handle.pack_into(&mut buf[1..3])?;
+        buf[3] = self.encryption_enabled.into();
+        Ok(())
+    }
+}
+/// Number of packets completed (transmitted or flushed) on one connection since the last
+/// [`NumberOfCompletedPackets`] event.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct CompletedPackets {
+    pub handle: ConnectionHandle,
+    pub num_completed_packets: u16,
+}
+impl CompletedPackets {
+    pub const BYTE_LEN: usize = ConnectionHandle::BYTE_LEN + 2;
+}
+/// Number Of Completed Packets Event. Used by the controller to return ACL data buffer credits
+/// to the host.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct NumberOfCompletedPackets {
+    pub completed: Vec<CompletedPackets>,
+}
+impl Event for NumberOfCompletedPackets {
+    const EVENT_CODE: EventCode = EventCode::NumberOfCompletedPackets;
+
+    fn event_byte_len(&self) -> usize {
+        1 + self.completed.len() * CompletedPackets::BYTE_LEN
+    }
+
+    fn event_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        let num_handles = usize::from(*buf.first().ok_or(PackError::BadLength {
+            expected: 1,
+            got: 0,
+        })?);
+        PackError::expect_length(1 + num_handles * CompletedPackets::BYTE_LEN, buf)?;
+        let completed = buf[1..]
+            .chunks_exact(CompletedPackets::BYTE_LEN)
+            .map(|chunk| {
+                Ok(CompletedPackets {
+                    handle: ConnectionHandle::unpack_from(&chunk[..2])?,
+                    num_completed_packets: u16::from_le_bytes([chunk[2], chunk[3]]),
+                })
+            })
+            .collect::<Result<Vec<_>, PackError>>()?;
+        Ok(NumberOfCompletedPackets { completed })
+    }
+
+    fn event_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(self.event_byte_len(), buf)?;
+        buf[0] = u8::try_from(self.completed.len()).map_err(|_| PackError::InvalidFields)?;
+        for (completed, chunk) in self
+            .completed
+            .iter()
+            .zip(buf[1..].chunks_exact_mut(CompletedPackets::BYTE_LEN))
+        {
+            completed.handle.pack_into(&mut chunk[..2])?;
+            chunk[2..].copy_from_slice(&completed.num_completed_packets.to_le_bytes());
+        }
+        Ok(())
+    }
+}
+/// Hardware Error Event. `hardware_code` is controller specific.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct HardwareError {
+    pub hardware_code: u8,
+}
+impl HardwareError {
+    pub const BYTE_LEN: usize = 1;
+}
+impl Event for HardwareError {
+    const EVENT_CODE: EventCode = EventCode::HardwareError;
+
+    fn event_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn event_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(HardwareError {
+            hardware_code: buf[0],
+        })
+    }
+
+    fn event_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.hardware_code;
+        Ok(())
+    }
+}
+/// Type of link whose buffers overflowed ([`DataBufferOverflow`]).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum LinkType {
+    Synchronous = 0x00,
+    ACL = 0x01,
+}
+impl From<LinkType> for u8 {
+    fn from(t: LinkType) -> Self {
+        t as u8
+    }
+}
+impl TryFrom<u8> for LinkType {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(LinkType::Synchronous),
+            0x01 => Ok(LinkType::ACL),
+            _ => Err(ConversionError(())),
+        }
+    }
+}
+/// Data Buffer Overflow Event. The host sent more data packets than the controller has buffers
+/// for.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct DataBufferOverflow {
+    pub link_type: LinkType,
+}
+impl DataBufferOverflow {
+    pub const BYTE_LEN: usize = 1;
+}
+impl Event for DataBufferOverflow {
+    const EVENT_CODE: EventCode = EventCode::DataBufferOverflow;
+
+    fn event_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn event_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(DataBufferOverflow {
+            link_type: LinkType::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+        })
+    }
+
+    fn event_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.link_type.into();
+        Ok(())
+    }
+}
+/// Read Remote Version Information Complete Event. Link Layer version information of the remote
+/// device.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ReadRemoteVersionInformationComplete {
+    pub status: ErrorCode,
+    pub handle: ConnectionHandle,
+    /// Link Layer version (Bluetooth Assigned Numbers).
+    pub version: u8,
+    pub manufacturer_name: CompanyID,
+    pub subversion: u16,
+}
+impl ReadRemoteVersionInformationComplete {
+    pub const BYTE_LEN: usize = 1 + ConnectionHandle::BYTE_LEN + 1 + 2 + 2;
+}
+impl Event for ReadRemoteVersionInformationComplete {
+    const EVENT_CODE: EventCode = EventCode::ReadRemoteVersionInformationComplete;
+
+    fn event_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn event_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(ReadRemoteVersionInformationComplete {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            handle: ConnectionHandle::unpack_from(&buf[1..3])?,
+            version: buf[3],
+            manufacturer_name: CompanyID(u16::from_le_bytes([buf[4], buf[5]])),
+            subversion: u16::from_le_bytes([buf[6], buf[7]]),
+        })
+    }
+
+    fn event_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.handle.pack_into(&mut buf[1..3])?;
+        buf[3] = self.version;
+        buf[4..6].copy_from_slice(&self.manufacturer_name.0.to_le_bytes());
+        buf[6..8].copy_from_slice(&self.subversion.to_le_bytes());
+        Ok(())
+    }
+}
+/// Encryption Key Refresh Complete Event. Sent when the encryption key of a connection was
+/// refreshed.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct EncryptionKeyRefreshComplete {
+    pub status: ErrorCode,
+    pub handle: ConnectionHandle,
+}
+impl EncryptionKeyRefreshComplete {
+    pub const BYTE_LEN: usize = 1 + ConnectionHandle::BYTE_LEN;
+}
+impl Event for EncryptionKeyRefreshComplete {
+    const EVENT_CODE: EventCode = EventCode::EncryptionKeyRefreshComplete;
+
+    fn event_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn event_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(EncryptionKeyRefreshComplete {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            handle: ConnectionHandle::unpack_from(&buf[1..3])?,
+        })
+    }
+
+    fn event_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.handle.pack_into(&mut buf[1..3])
+    }
+}
+/// HCI Event decoded by its `EventCode`. Events without a typed parser (including
+/// `CommandComplete`, whose return parameters depend on the command) are kept as
+/// [`DecodedEvent::Raw`].
+#[derive(Clone, Debug)]
+pub enum DecodedEvent<Buf: AsRef<[u8]>> {
+    DisconnectionComplete(DisconnectionComplete),
+    EncryptionChange(EncryptionChange),
+    CommandStatus(CommandStatus),
+    HardwareError(HardwareError),
+    NumberOfCompletedPackets(NumberOfCompletedPackets),
+    DataBufferOverflow(DataBufferOverflow),
+    ReadRemoteVersionInformationComplete(ReadRemoteVersionInformationComplete),
+    EncryptionKeyRefreshComplete(EncryptionKeyRefreshComplete),
+    LEMeta(RawMetaEvent<Buf>),
+    Raw(EventPacket<Buf>),
+}
+impl<Buf: Storage<u8>> DecodedEvent<Buf> {
+    /// Decodes `packet`. Parameters of `LEMeta` and unknown events are copied into `Buf`.
+    /// # Example
+    /// ```
+    /// use btle::hci::event::{DecodedEvent, EventCode, EventPacket};
+    /// let packet = EventPacket::new(
+    ///     EventCode::NumberOfCompletedPackets,
+    ///     &[0x01, 0x40, 0x00, 0x02, 0x00][..],
+    /// );
+    /// match DecodedEvent::<Box<[u8]>>::decode(&packet).unwrap() {
+    ///     DecodedEvent::NumberOfCompletedPackets(e) => {
+    ///         assert_eq!(u16::from(e.completed[0].handle), 0x0040);
+    ///         assert_eq!(e.completed[0].num_completed_packets, 2);
+    ///     }
+    ///     _ => panic!("wrong event"),
+    /// }
+    /// ```
+    /// # Errors
+    /// Returns a `PackError` if `packet` has a typed parser but its parameters are invalid.
+    pub fn decode<S: AsRef<[u8]>>(packet: &EventPacket<S>) -> Result<Self, PackError> {
+        let buf = packet.parameters();
+        Ok(match packet.event_code() {
+            EventCode::DisconnectionComplete => {
+                DecodedEvent::DisconnectionComplete(DisconnectionComplete::event_unpack_from(buf)?)
+            }
+            EventCode::EncryptionChange => {
+                DecodedEvent::EncryptionChange(EncryptionChange::event_unpack_from(buf)?)
+            }
+            EventCode::CommandStatus => {
+                DecodedEvent::CommandStatus(CommandStatus::event_unpack_from(buf)?)
+            }
+            EventCode::HardwareError => {
+                DecodedEvent::HardwareError(HardwareError::event_unpack_from(buf)?)
+            }
+            EventCode::NumberOfCompletedPackets => DecodedEvent::NumberOfCompletedPackets(
+                NumberOfCompletedPackets::event_unpack_from(buf)?,
+            ),
+            EventCode::DataBufferOverflow => {
+                DecodedEvent::DataBufferOverflow(DataBufferOverflow::event_unpack_from(buf)?)
+            }
+            EventCode::ReadRemoteVersionInformationComplete => {
+                DecodedEvent::ReadRemoteVersionInformationComplete(
+                    ReadRemoteVersionInformationComplete::event_unpack_from(buf)?,
+                )
+            }
+            EventCode::EncryptionKeyRefreshComplete => DecodedEvent::EncryptionKeyRefreshComplete(
+                EncryptionKeyRefreshComplete::event_unpack_from(buf)?,
+            ),
+            EventCode::LEMeta => {
+                DecodedEvent::LEMeta(RawMetaEvent::try_from(packet.as_ref())?.to_owned())
+            }
+            event_code => DecodedEvent::Raw(EventPacket::new(event_code, Buf::from_slice(buf))),
+        })
+    }
+}
+impl<Buf: AsRef<[u8]>> DecodedEvent<Buf> {
+    pub fn event_code(&self) -> EventCode {
+        match self {
+            DecodedEvent::DisconnectionComplete(_) => EventCode::DisconnectionComplete,
+            DecodedEvent::EncryptionChange(_) => EventCode::EncryptionChange,
+            DecodedEvent::CommandStatus(_) => EventCode::CommandStatus,
+            DecodedEvent::HardwareError(_) => EventCode::HardwareError,
+            DecodedEvent::NumberOfCompletedPackets(_) => EventCode::NumberOfCompletedPackets,
+            DecodedEvent::DataBufferOverflow(_) => EventCode::DataBufferOverflow,
+            DecodedEvent::ReadRemoteVersionInformationComplete(_) => {
+                EventCode::ReadRemoteVersionInformationComplete
+            }
+            DecodedEvent::EncryptionKeyRefreshComplete(_) => {
+                EventCode::EncryptionKeyRefreshComplete
+            }
+            DecodedEvent::LEMeta(_) => EventCode::LEMeta,
+            DecodedEvent::Raw(packet) => packet.event_code,
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{
+        CommandStatus, DataBufferOverflow, DecodedEvent, EncryptionChange, EncryptionEnabled,
+        EncryptionKeyRefreshComplete, Event, EventCode, EventPacket, HardwareError,
+        NumberOfCompletedPackets, ReadRemoteVersionInformationComplete,
+    };
+    use crate::hci::link_control::DisconnectionComplete;
+    use crate::hci::{ConnectionHandle, ErrorCode};
+    use crate::PackError;
+    use alloc::boxed::Box;
+
+    /// Checks that `E` rejects buffers one byte shorter or longer than `len`.
+    fn check_lengths<E: Event + core::fmt::Debug>(len: usize) {
+        let buf = [0_u8; 16];
+        assert!(E::event_unpack_from(&buf[..len - 1]).is_err());
+        assert!(E::event_unpack_from(&buf[..len + 1]).is_err());
+        assert!(E::event_unpack_from(&[]).is_err());
+    }
+    #[test]
+    fn test_fixed_length_events_bad_lengths() {
+        check_lengths::<DisconnectionComplete>(DisconnectionComplete::BYTE_LEN);
+        check_lengths::<EncryptionChange>(EncryptionChange::BYTE_LEN);
+        check_lengths::<CommandStatus>(super::COMMAND_STATUS_LEN);
+        check_lengths::<HardwareError>(HardwareError::BYTE_LEN);
+        check_lengths::<DataBufferOverflow>(DataBufferOverflow::BYTE_LEN);
+        check_lengths::<ReadRemoteVersionInformationComplete>(
+            ReadRemoteVersionInformationComplete::BYTE_LEN,
+        );
+        check_lengths::<EncryptionKeyRefreshComplete>(EncryptionKeyRefreshComplete::BYTE_LEN);
+    }
+    #[test]
+    fn test_events_bad_values() {
+        // Unknown status.
+        assert_eq!(
+            EncryptionChange::event_unpack_from(&[0xFF, 0x40, 0x00, 0x01]),
+            Err(PackError::bad_index(0))
+        );
+        // Connection handle above 0x0EFF.
+        assert!(EncryptionChange::event_unpack_from(&[0x00, 0x00, 0x0F, 0x01]).is_err());
+        // Reserved encryption enabled value.
+        assert_eq!(
+            EncryptionChange::event_unpack_from(&[0x00, 0x40, 0x00, 0x03]),
+            Err(PackError::bad_index(3))
+        );
+        assert_eq!(
+            DataBufferOverflow::event_unpack_from(&[0x02]),
+            Err(PackError::bad_index(0))
+        );
+        assert!(EncryptionKeyRefreshComplete::event_unpack_from(&[0xFF, 0x40, 0x00]).is_err());
+    }
+    #[test]
+    fn test_number_of_completed_packets() {
+        let event = NumberOfCompletedPackets::event_unpack_from(&[
+            0x02, 0x40, 0x00, 0x01, 0x00, 0x41, 0x00, 0x02, 0x00,
+        ])
+        .unwrap();
+        assert_eq!(event.completed.len(), 2);
+        assert_eq!(event.completed[1].handle, ConnectionHandle::new(0x0041));
+        assert_eq!(event.completed[1].num_completed_packets, 2);
+        let mut buf = [0_u8; 9];
+        event.event_pack_into(&mut buf).unwrap();
+        assert_eq!(NumberOfCompletedPackets::event_unpack_from(&buf), Ok(event));
+        assert_eq!(
+            NumberOfCompletedPackets::event_unpack_from(&[]),
+            Err(PackError::BadLength {
+                expected: 1,
+                got: 0
+            })
+        );
+        // Says 2 handles but only has 1.
+        assert!(
+            NumberOfCompletedPackets::event_unpack_from(&[0x02, 0x40, 0x00, 0x01, 0x00]).is_err()
+        );
+        assert!(
+            NumberOfCompletedPackets::event_unpack_from(&[0x01, 0x00, 0x0F, 0x01, 0x00]).is_err()
+        );
+        assert_eq!(
+            NumberOfCompletedPackets::event_unpack_from(&[0x00]),
+            Ok(NumberOfCompletedPackets::default())
+        );
+    }
+    #[test]
+    fn test_decoded_event() {
+        let change = EncryptionChange {
+            status: ErrorCode::Ok,
+            handle: ConnectionHandle::new(0x0040),
+            encryption_enabled: EncryptionEnabled::On,
+        };
+        let mut buf = [0_u8; EncryptionChange::BYTE_LEN];
+        change.event_pack_into(&mut buf).unwrap();
+        let packet = EventPacket::new(EventCode::EncryptionChange, &buf[..]);
+        match DecodedEvent::<Box<[u8]>>::decode(&packet).unwrap() {
+            DecodedEvent::EncryptionChange(decoded) => assert_eq!(decoded, change),
+            e => panic!("wrong event {:?}", e),
+        }
+        // Typed events with bad parameters are errors instead of `Raw`.
+        let packet = EventPacket::new(EventCode::EncryptionChange, &buf[..2]);
+        assert!(DecodedEvent::<Box<[u8]>>::decode(&packet).is_err());
+        // LE Meta events need at least the subevent code.
+        let packet = EventPacket::new(EventCode::LEMeta, &[][..]);
+        assert!(DecodedEvent::<Box<[u8]>>::decode(&packet).is_err());
+        let packet = EventPacket::new(EventCode::CommandComplete, &[0x01, 0x00, 0x00][..]);
+        let decoded = DecodedEvent::<Box<[u8]>>::decode(&packet).unwrap();
+        match decoded.clone() {
+            DecodedEvent::Raw(raw) => assert_eq!(raw.parameters(), &[0x01, 0x00, 0x00][..]),
+            e => panic!("wrong event {:?}", e),
+        }
+        assert_eq!(decoded.event_code(), EventCode::CommandComplete);
+    }
+}
@@ -0,0 +1,641 @@
+//! Informational Parameters (OGF 0x04). Fixed information about the controller (versions,
+//! supported commands and features, `BD_ADDR`, etc) and [`ControllerInfo`] for collecting all of
+//! it.
+use crate::hci::command::Command;
+use crate::hci::event::{CommandComplete, ReturnParameters};
+use crate::hci::le::features::{LEBufferSize, LEFeatures};
+use crate::hci::{ErrorCode, Opcode, OCF, OGF};
+use crate::{BTAddress, CompanyID, PackError, BT_ADDRESS_LEN};
+use core::convert::TryFrom;
+
+/// OCF of the Informational Parameters commands.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[repr(u16)]
+pub enum InformationalOpcode {
+    ReadLocalVersionInformation = 0x0001,
+    ReadLocalSupportedCommands = 0x0002,
+    ReadLocalSupportedFeatures = 0x0003,
+    ReadLocalExtendedFeatures = 0x0004,
+    ReadBufferSize = 0x0005,
+    ReadBDADDR = 0x0009,
+    ReadDataBlockSize = 0x000A,
+    ReadLocalSupportedCodecs = 0x000B,
+}
+impl From<InformationalOpcode> for u16 {
+    fn from(opcode: InformationalOpcode) -> Self {
+        opcode as u16
+    }
+}
+impl From<InformationalOpcode> for OCF {
+    fn from(opcode: InformationalOpcode) -> Self {
+        OCF::new(opcode.into())
+    }
+}
+impl From<InformationalOpcode> for Opcode {
+    fn from(opcode: InformationalOpcode) -> Self {
+        Opcode(OGF::InformationalParameters, opcode.into())
+    }
+}
+/// Read Local Version Information Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadLocalVersionInformation {}
+impl Command for ReadLocalVersionInformation {
+    type Return = CommandComplete<LocalVersionInformation>;
+
+    fn opcode() -> Opcode {
+        InformationalOpcode::ReadLocalVersionInformation.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadLocalVersionInformation {})
+    }
+}
+/// Version information of the local controller. Version numbers are listed in Bluetooth
+/// Assigned Numbers (for example, `0x09` is Bluetooth 5.0).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct LocalVersionInformation {
+    pub status: ErrorCode,
+    pub hci_version: u8,
+    pub hci_revision: u16,
+    pub lmp_version: u8,
+    pub manufacturer_name: CompanyID,
+    pub lmp_subversion: u16,
+}
+impl LocalVersionInformation {
+    pub const BYTE_LEN: usize = 1 + 1 + 2 + 1 + 2 + 2;
+}
+impl ReturnParameters for LocalVersionInformation {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        buf[1] = self.hci_version;
+        buf[2..4].copy_from_slice(&self.hci_revision.to_le_bytes());
+        buf[4] = self.lmp_version;
+        buf[5..7].copy_from_slice(&self.manufacturer_name.0.to_le_bytes());
+        buf[7..9].copy_from_slice(&self.lmp_subversion.to_le_bytes());
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(LocalVersionInformation {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            hci_version: buf[1],
+            hci_revision: u16::from_le_bytes([buf[2], buf[3]]),
+            lmp_version: buf[4],
+            manufacturer_name: CompanyID(u16::from_le_bytes([buf[5], buf[6]])),
+            lmp_subversion: u16::from_le_bytes([buf[7], buf[8]]),
+        })
+    }
+}
+/// Read Local Supported Commands Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadLocalSupportedCommands {}
+impl Command for ReadLocalSupportedCommands {
+    type Return = CommandComplete<SupportedCommandsReturn>;
+
+    fn opcode() -> Opcode {
+        InformationalOpcode::ReadLocalSupportedCommands.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadLocalSupportedCommands {})
+    }
+}
+/// 64 byte bitmap of the commands supported by the controller.
+/// ```
+/// use btle::hci::baseband::Reset;
+/// use btle::hci::command::Command;
+/// use btle::hci::info::SupportedCommands;
+/// let mut commands = SupportedCommands::default();
+/// assert_eq!(commands.is_supported(Reset::opcode()), Some(false));
+/// assert!(commands.set_supported(Reset::opcode(), true));
+/// assert_eq!(commands.0[5], 0x80);
+/// assert_eq!(commands.is_supported(Reset::opcode()), Some(true));
+/// ```
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SupportedCommands(pub [u8; SupportedCommands::BYTE_LEN]);
+impl SupportedCommands {
+    pub const BYTE_LEN: usize = 64;
+    pub const ZEROED: SupportedCommands = SupportedCommands([0_u8; Self::BYTE_LEN]);
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf.copy_from_slice(&self.0[..]);
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let mut out = Self::ZEROED;
+        out.0.copy_from_slice(buf);
+        Ok(out)
+    }
+    /// Returns the (octet, bit) of `opcode` in the bitmap (see the Supported Commands table in
+    /// the Core Spec Vol 4, Part E, 6.27) or `None` if `opcode` has no known bit (vendor specific
+    /// commands, etc).
+    pub fn bit_position(opcode: Opcode) -> Option<(usize, u8)> {
+        let Opcode(ogf, ocf) = opcode;
+        Some(match (ogf, u16::from(ocf)) {
+            (OGF::LinkControl, 0x0001) => (0, 0),
+            (OGF::LinkControl, 0x0002) => (0, 1),
+            (OGF::LinkControl, 0x0003) => (0, 2),
+            (OGF::LinkControl, 0x0004) => (0, 3),
+            (OGF::LinkControl, 0x0005) => (0, 4),
+            (OGF::LinkControl, 0x0006) => (0, 5),
+            (OGF::LinkControl, 0x0007) => (0, 6),
+            (OGF::LinkControl, 0x0008) => (0, 7),
+            (OGF::LinkControl, 0x0009) => (1, 0),
+            (OGF::LinkControl, 0x000A) => (1, 1),
+            (OGF::LinkControl, 0x000B) => (1, 2),
+            (OGF::LinkControl, 0x000C) => (1, 3),
+            (OGF::LinkControl, 0x000D) => (1, 4),
+            (OGF::LinkControl, 0x000E) => (1, 5),
+            (OGF::LinkControl, 0x000F) => (1, 6),
+            (OGF::LinkControl, 0x0011) => (1, 7),
+            (OGF::LinkControl, 0x0013) => (2, 0),
+            (OGF::LinkControl, 0x0015) => (2, 1),
+            (OGF::LinkControl, 0x0017) => (2, 2),
+            (OGF::LinkControl, 0x0019) => (2, 3),
+            (OGF::LinkControl, 0x001A) => (2, 4),
+            (OGF::LinkControl, 0x001B) => (2, 5),
+            (OGF::LinkControl, 0x001C) => (2, 6),
+            (OGF::LinkControl, 0x001D) => (2, 7),
+            (OGF::LinkControl, 0x001F) => (3, 0),
+            (OGF::LinkControl, 0x0020) => (3, 1),
+            (OGF::LinkPolicy, 0x0001) => (4, 1),
+            (OGF::LinkPolicy, 0x0003) => (4, 2),
+            (OGF::LinkPolicy, 0x0004) => (4, 3),
+            (OGF::LinkPolicy, 0x0005) => (4, 4),
+            (OGF::LinkPolicy, 0x0006) => (4, 5),
+            (OGF::LinkPolicy, 0x0007) => (4, 6),
+            (OGF::LinkPolicy, 0x0009) => (4, 7),
+            (OGF::LinkPolicy, 0x000B) => (5, 0),
+            (OGF::LinkPolicy, 0x000C) => (5, 1),
+            (OGF::LinkPolicy, 0x000D) => (5, 2),
+            (OGF::LinkPolicy, 0x000E) => (5, 3),
+            (OGF::LinkPolicy, 0x000F) => (5, 4),
+            (OGF::LinkPolicy, 0x0010) => (5, 5),
+            (OGF::HCIControlBaseband, 0x0001) => (5, 6),
+            (OGF::HCIControlBaseband, 0x0003) => (5, 7),
+            (OGF::HCIControlBaseband, 0x0005) => (6, 0),
+            (OGF::HCIControlBaseband, 0x0008) => (6, 1),
+            (OGF::HCIControlBaseband, 0x0009) => (6, 2),
+            (OGF::HCIControlBaseband, 0x000A) => (6, 3),
+            (OGF::HCIControlBaseband, 0x000B) => (6, 4),
+            (OGF::HCIControlBaseband, 0x000D) => (6, 5),
+            (OGF::HCIControlBaseband, 0x0011) => (6, 6),
+            (OGF::HCIControlBaseband, 0x0012) => (6, 7),
+            (OGF::HCIControlBaseband, 0x0013) => (7, 0),
+            (OGF::HCIControlBaseband, 0x0014) => (7, 1),
+            (OGF::HCIControlBaseband, 0x0015) => (7, 2),
+            (OGF::HCIControlBaseband, 0x0016) => (7, 3),
+            (OGF::HCIControlBaseband, 0x0017) => (7, 4),
+            (OGF::HCIControlBaseband, 0x0018) => (7, 5),
+            (OGF::HCIControlBaseband, 0x0019) => (7, 6),
+            (OGF::HCIControlBaseband, 0x001A) => (7, 7),
+            (OGF::InformationalParameters, 0x0001) => (14, 3),
+            (OGF::InformationalParameters, 0x0003) => (14, 5),
+            (OGF::InformationalParameters, 0x0004) => (14, 6),
+            (OGF::InformationalParameters, 0x0005) => (14, 7),
+            (OGF::InformationalParameters, 0x0009) => (15, 1),
+            (OGF::StatusParameters, 0x0001) => (15, 2),
+            (OGF::StatusParameters, 0x0002) => (15, 3),
+            (OGF::StatusParameters, 0x0003) => (15, 4),
+            (OGF::StatusParameters, 0x0005) => (15, 5),
+            (OGF::StatusParameters, 0x0006) => (15, 6),
+            (OGF::StatusParameters, 0x0007) => (15, 7),
+            (OGF::LEController, 0x0001) => (25, 0),
+            (OGF::LEController, 0x0002) => (25, 1),
+            (OGF::LEController, 0x0003) => (25, 2),
+            (OGF::LEController, 0x0005) => (25, 4),
+            (OGF::LEController, 0x0006) => (25, 5),
+            (OGF::LEController, 0x0007) => (25, 6),
+            (OGF::LEController, 0x0008) => (25, 7),
+            (OGF::LEController, 0x0009) => (26, 0),
+            (OGF::LEController, 0x000A) => (26, 1),
+            (OGF::LEController, 0x000B) => (26, 2),
+            (OGF::LEController, 0x000C) => (26, 3),
+            (OGF::LEController, 0x000D) => (26, 4),
+            (OGF::LEController, 0x000E) => (26, 5),
+            (OGF::LEController, 0x000F) => (26, 6),
+            (OGF::LEController, 0x0010) => (26, 7),
+            (OGF::LEController, 0x0011) => (27, 0),
+            (OGF::LEController, 0x0012) => (27, 1),
+            (OGF::LEController, 0x0013) => (27, 2),
+            (OGF::LEController, 0x0014) => (27, 3),
+            (OGF::LEController, 0x0015) => (27, 4),
+            (OGF::LEController, 0x0016) => (27, 5),
+            (OGF::LEController, 0x0017) => (27, 6),
+            (OGF::LEController, 0x0018) => (27, 7),
+            (OGF::LEController, 0x0019) => (28, 0),
+            (OGF::LEController, 0x001A) => (28, 1),
+            (OGF::LEController, 0x001B) => (28, 2),
+            (OGF::LEController, 0x001C) => (28, 3),
+            (OGF::LEController, 0x001D) => (28, 4),
+            (OGF::LEController, 0x001E) => (28, 5),
+            (OGF::LEController, 0x001F) => (28, 6),
+            (OGF::LEController, 0x0020) => (33, 4),
+            (OGF::LEController, 0x0021) => (33, 5),
+            (OGF::LEController, 0x0022) => (33, 6),
+            (OGF::LEController, 0x0023) => (33, 7),
+            (OGF::LEController, 0x0024) => (34, 0),
+            (OGF::LEController, 0x0025) => (34, 1),
+            (OGF::LEController, 0x0026) => (34, 2),
+            (OGF::LEController, 0x0027) => (34, 3),
+            (OGF::LEController, 0x0028) => (34, 4),
+            (OGF::LEController, 0x0029) => (34, 5),
+            (OGF::LEController, 0x002A) => (34, 6),
+            (OGF::LEController, 0x002B) => (34, 7),
+            (OGF::LEController, 0x002C) => (35, 0),
+            (OGF::LEController, 0x002D) => (35, 1),
+            (OGF::LEController, 0x002E) => (35, 2),
+            (OGF::LEController, 0x002F) => (35, 3),
+            (OGF::LEController, 0x0030) => (35, 4),
+            (OGF::LEController, 0x0031) => (35, 5),
+            (OGF::LEController, 0x0032) => (35, 6),
+            (OGF::LEController, 0x0033) => (35, 7),
+            (OGF::LEController, 0x0034) => (36, 0),
+            (OGF::LEController, 0x0035) => (36, 1),
+            (OGF::LEController, 0x0036) => (36, 2),
+            (OGF::LEController, 0x0037) => (36, 3),
+            (OGF::LEController, 0x0038) => (36, 4),
+            (OGF::LEController, 0x0039) => (36, 5),
+            (OGF::LEController, 0x003A) => (36, 6),
+            (OGF::LEController, 0x003B) => (36, 7),
+            (OGF::LEController, 0x003C) => (37, 0),
+            (OGF::LEController, 0x003D) => (37, 1),
+            (OGF::LEController, 0x003E) => (37, 2),
+            (OGF::LEController, 0x003F) => (37, 3),
+            (OGF::LEController, 0x0040) => (37, 4),
+            (OGF::LEController, 0x0041) => (37, 5),
+            (OGF::LEController, 0x0042) => (37, 6),
+            (OGF::LEController, 0x0043) => (37, 7),
+            (OGF::LEController, 0x0044) => (38, 0),
+            (OGF::LEController, 0x0045) => (38, 1),
+            (OGF::LEController, 0x0046) => (38, 2),
+            (OGF::LEController, 0x0047) => (38, 3),
+            (OGF::LEController, 0x0048) => (38, 4),
+            (OGF::LEController, 0x0049) => (38, 5),
+            (OGF::LEController, 0x004A) => (38, 6),
+            (OGF::LEController, 0x004B) => (38, 7),
+            (OGF::LEController, 0x004C) => (39, 0),
+            (OGF::LEController, 0x004D) => (39, 1),
+            (OGF::LEController, 0x004E) => (39, 2),
+            _ => return None,
+        })
+    }
+    /// Returns `Some(true)` if the controller reports `opcode` as supported, `Some(false)` if it
+    /// doesn't and `None` if `opcode` isn't in the bitmap.
+    pub fn is_supported(&self, opcode: Opcode) -> Option<bool> {
+        Self::bit_position(opcode).map(|(octet, bit)| self.0[octet] & (1 << bit) != 0)
+    }
+    /// Sets whether `opcode` is supported. Returns `false` if `opcode` isn't in the bitmap.
+    pub fn set_supported(&mut self, opcode: Opcode, supported: bool) -> bool {
+        match Self::bit_position(opcode) {
+            Some((octet, bit)) => {
+                if supported {
+                    self.0[octet] |= 1 << bit;
+                } else {
+                    self.0[octet] &= !(1 << bit);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+impl Default for SupportedCommands {
+    fn default() -> Self {
+        Self::ZEROED
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SupportedCommandsReturn {
+    pub status: ErrorCode,
+    pub supported_commands: SupportedCommands,
+}
+impl SupportedCommandsReturn {
+    pub const BYTE_LEN: usize = 1 + SupportedCommands::BYTE_LEN;
+}
+impl ReturnParameters for SupportedCommandsReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.supported_commands.pack_into(&mut buf[1..])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(SupportedCommandsReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            supported_commands: SupportedCommands::unpack_from(&buf[1..])?,
+        })
+    }
+}
+/// Read Local Supported Features Command. Reads the LMP features of the controller.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadLocalSupportedFeatures {}
+impl Command for ReadLocalSupportedFeatures {
+    type Return = CommandComplete<LocalSupportedFeaturesReturn>;
+
+    fn opcode() -> Opcode {
+        InformationalOpcode::ReadLocalSupportedFeatures.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadLocalSupportedFeatures {})
+    }
+}
+/// 64-bit LMP feature mask (page 0).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct LMPFeatures(pub u64);
+impl LMPFeatures {
+    pub const BYTE_LEN: usize = 8;
+    const BR_EDR_NOT_SUPPORTED_BIT: u64 = 37;
+    const LE_SUPPORTED_BIT: u64 = 38;
+    pub fn get_bit(self, bit: u64) -> bool {
+        self.0 & (1_u64 << bit) != 0
+    }
+    /// LE Supported (Controller).
+    pub fn le_supported(self) -> bool {
+        self.get_bit(Self::LE_SUPPORTED_BIT)
+    }
+    pub fn br_edr_not_supported(self) -> bool {
+        self.get_bit(Self::BR_EDR_NOT_SUPPORTED_BIT)
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct LocalSupportedFeaturesReturn {
+    pub status: ErrorCode,
+    pub features: LMPFeatures,
+}
+impl LocalSupportedFeaturesReturn {
+    pub const BYTE_LEN: usize = 1 + LMPFeatures::BYTE_LEN;
+}
+impl ReturnParameters for LocalSupportedFeaturesReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        buf[1..].copy_from_slice(&(self.features.0).to_le_bytes());
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let mut features = [0_u8; LMPFeatures::BYTE_LEN];
+        features.copy_from_slice(&buf[1..]);
+        Ok(LocalSupportedFeaturesReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            features: LMPFeatures(u64::from_le_bytes(features)),
+        })
+    }
+}
+/// Read BD_ADDR Command. Reads the public address of the controller.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadBDADDR {}
+impl Command for ReadBDADDR {
+    type Return = CommandComplete<BDADDRReturn>;
+
+    fn opcode() -> Opcode {
+        InformationalOpcode::ReadBDADDR.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadBDADDR {})
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct BDADDRReturn {
+    pub status: ErrorCode,
+    pub address: BTAddress,
+}
+impl BDADDRReturn {
+    pub const BYTE_LEN: usize = 1 + BT_ADDRESS_LEN;
+}
+impl ReturnParameters for BDADDRReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.address.pack_into(&mut buf[1..])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(BDADDRReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            address: BTAddress::unpack_from(&buf[1..])?,
+        })
+    }
+}
+/// Everything read from the controller by `Adapter::initialize`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ControllerInfo {
+    pub version: LocalVersionInformation,
+    pub supported_commands: SupportedCommands,
+    pub features: LMPFeatures,
+    /// Public address of the controller.
+    pub address: BTAddress,
+    pub le_buffer_size: LEBufferSize,
+    pub le_features: LEFeatures,
+}
+#[cfg(test)]
+mod tests {
+    use super::{
+        BDADDRReturn, LMPFeatures, LocalSupportedFeaturesReturn, LocalVersionInformation,
+        SupportedCommands, SupportedCommandsReturn,
+    };
+    use crate::hci::baseband::Reset;
+    use crate::hci::command::Command;
+    use crate::hci::event::ReturnParameters;
+    use crate::hci::le::random::Rand;
+    use crate::hci::{ErrorCode, Opcode, OCF, OCF_MAX, OGF};
+    use crate::{BTAddress, CompanyID, PackError};
+    use core::fmt::Debug;
+
+    /// Packs `ret`, checks it unpacks to the same value and that shorter and longer buffers are
+    /// rejected.
+    fn check_round_trip<R: ReturnParameters + PartialEq + Debug>(ret: &R) {
+        let len = ret.byte_len();
+        let mut buf = alloc::vec![0_u8; len + 1];
+        ret.pack_into(&mut buf[..len]).unwrap();
+        assert_eq!(&R::unpack_from(&buf[..len]).unwrap(), ret);
+        assert!(ret.pack_into(&mut buf[..len - 1]).is_err());
+        assert_eq!(
+            R::unpack_from(&buf[..len - 1]),
+            Err(PackError::BadLength {
+                expected: len,
+                got: len - 1
+            })
+        );
+        assert!(R::unpack_from(&buf[..]).is_err());
+        // Unknown status.
+        buf[0] = 0xFF;
+        assert_eq!(R::unpack_from(&buf[..len]), Err(PackError::bad_index(0)));
+    }
+    #[test]
+    fn test_local_version_information() {
+        let version = LocalVersionInformation {
+            status: ErrorCode::Ok,
+            hci_version: 0x09,
+            hci_revision: 0x1234,
+            lmp_version: 0x09,
+            manufacturer_name: CompanyID(0x0002),
+            lmp_subversion: 0xABCD,
+        };
+        let mut buf = [0_u8; LocalVersionInformation::BYTE_LEN];
+        version.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x00, 0x09, 0x34, 0x12, 0x09, 0x02, 0x00, 0xCD, 0xAB]);
+        check_round_trip(&version);
+    }
+    #[test]
+    fn test_supported_commands_return() {
+        let mut supported_commands = SupportedCommands::ZEROED;
+        supported_commands.0[5] = 0x80;
+        supported_commands.0[SupportedCommands::BYTE_LEN - 1] = 0x01;
+        check_round_trip(&SupportedCommandsReturn {
+            status: ErrorCode::Ok,
+            supported_commands,
+        });
+    }
+    #[test]
+    fn test_local_supported_features_return() {
+        let features = LMPFeatures((1 << 38) | (1 << 37));
+        assert!(features.le_supported());
+        assert!(features.br_edr_not_supported());
+        assert!(!LMPFeatures(1 << 38).br_edr_not_supported());
+        check_round_trip(&LocalSupportedFeaturesReturn {
+            status: ErrorCode::Ok,
+            features,
+        });
+    }
+    #[test]
+    fn test_bd_addr_return() {
+        check_round_trip(&BDADDRReturn {
+            status: ErrorCode::HardwareFailure,
+            address: BTAddress([0x01, 0x02, 0x03, 0x04, 0x05, 0x06]),
+        });
+    }
+    #[test]
+    fn test_supported_commands_bit_positions() {
+        let ogfs = [
+            OGF::LinkControl,
+            OGF::LinkPolicy,
+            OGF::HCIControlBaseband,
+            OGF::InformationalParameters,
+            OGF::StatusParameters,
+            OGF::LEController,
+            OGF::VendorSpecific,
+        ];
+        let mut seen = SupportedCommands::ZEROED;
+        for ogf in &ogfs {
+            for ocf in 0..=OCF_MAX {
+                let opcode = Opcode(*ogf, OCF::new(ocf));
+                if let Some((octet, bit)) = SupportedCommands::bit_position(opcode) {
+                    assert!(octet < SupportedCommands::BYTE_LEN && bit < 8);
+                    // Every bit belongs to one command only.
+                    assert_eq!(seen.is_supported(opcode), Some(false));
+                    assert!(seen.set_supported(opcode, true));
+                }
+            }
+        }
+        assert_eq!(
+            SupportedCommands::bit_position(Reset::opcode()),
+            Some((5, 7))
+        );
+        assert_eq!(
+            SupportedCommands::bit_position(Rand::opcode()),
+            Some((27, 7))
+        );
+        let vendor = Opcode(OGF::VendorSpecific, OCF::new(0x0001));
+        assert_eq!(SupportedCommands::bit_position(vendor), None);
+    }
+    #[test]
+    fn test_supported_commands_set_supported() {
+        let mut commands = SupportedCommands::ZEROED;
+        assert_eq!(commands.is_supported(Reset::opcode()), Some(false));
+        assert!(commands.set_supported(Reset::opcode(), true));
+        assert_eq!(commands.0[5], 0x80);
+        assert_eq!(commands.is_supported(Reset::opcode()), Some(true));
+        assert!(commands.set_supported(Reset::opcode(), false));
+        assert_eq!(commands, SupportedCommands::ZEROED);
+        let vendor = Opcode(OGF::VendorSpecific, OCF::new(0x0001));
+        assert!(!commands.set_supported(vendor, true));
+        assert_eq!(commands.is_supported(vendor), None);
+        assert!(SupportedCommands::unpack_from(&commands.0[1..]).is_err());
+    }
+}
@@ -1,4 +1,5 @@
-//! LE [`SetAdvertisingEnable`], [`SetAdvertisingData`] and other advertising types.
+//! LE [`SetAdvertisingEnable`], [`SetAdvertisingData`], [`SetScanResponseData`] and other
+//! advertising types.
 use crate::bytes::ToFromBytesEndian;
 use crate::hci::command::Command;
 use crate::hci::event::{CommandComplete, ReturnParameters, StatusReturn};
@@ -93,6 +94,66 @@ impl SetAdvertisingData {
         }
     }
 }
+const SCAN_RESPONSE_DATA_MAX_LEN: usize = 0x1F;
+/// Set the data sent in response to scan requests (0-31 bytes). Only used by scannable
+/// advertising types (`AdvInd` and `AdvScanInd`) when an active scanner sends a scan request.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct SetScanResponseData {
+    data: [u8; SCAN_RESPONSE_DATA_MAX_LEN],
+    len: u8,
+}
+
+impl Command for SetScanResponseData {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetScanResponseData.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::COMMAND_BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::COMMAND_BYTE_LEN, buf)?;
+        buf[0] = self.len;
+        let l = usize::from(self.len);
+        buf[1..][..l].copy_from_slice(&self.data[..l]);
+        // Zero the rest of the bytes in the scan response
+        buf[1..][l..].iter_mut().for_each(|i| *i = 0);
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::COMMAND_BYTE_LEN, buf)?;
+        let len = usize::from(buf[0]);
+        if len > SCAN_RESPONSE_DATA_MAX_LEN {
+            return Err(PackError::bad_index(0));
+        }
+        Ok(Self::new(&buf[1..][..len]))
+    }
+}
+impl SetScanResponseData {
+    const COMMAND_BYTE_LEN: usize = SCAN_RESPONSE_DATA_MAX_LEN + 1;
+    /// Creates a new `SetScanResponseData` command.
+    /// # Panics
+    /// Panics if `data.len() > 31`.
+    pub fn new(data: &[u8]) -> SetScanResponseData {
+        assert!(data.len() <= SCAN_RESPONSE_DATA_MAX_LEN);
+        let mut buf = [0_u8; SCAN_RESPONSE_DATA_MAX_LEN];
+        buf[..data.len()].copy_from_slice(data);
+        SetScanResponseData {
+            data: buf,
+            len: data.len().try_into().expect("data max len 0x1F"),
+        }
+    }
+    pub fn data(&self) -> &[u8] {
+        &self.data[..usize::from(self.len)]
+    }
+}
 #[derive(Copy, Clone, Debug)]
 pub struct SetAdvertisingParameters(pub AdvertisingParameters);
 impl Command for SetAdvertisingParameters {
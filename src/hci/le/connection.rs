@@ -0,0 +1,719 @@
+//! LE [`CreateConnection`], [`CreateConnectionCancel`], [`ConnectionComplete`] and
+//! [`EnhancedConnectionComplete`] for initiating connections (Central role). Also
+//! [`ConnectionUpdate`] and remote connection parameter request handling
+//! ([`RemoteConnectionParameterRequest`], [`RemoteConnectionParameterRequestReply`] and
+//! [`RemoteConnectionParameterRequestNegativeReply`]).
+use crate::bytes::ToFromBytesEndian;
+use crate::hci::command::{AsyncCommand, Command};
+use crate::hci::event::{
+    CommandComplete, CommandStatus, Event, EventCode, EventPacket, ReturnParameters, StatusReturn,
+};
+use crate::hci::le::data_length::{DataLength, DataLengthChange};
+use crate::hci::le::phy::{Phy, PhyUpdateComplete};
+use crate::hci::le::{LEControllerOpcode, MetaEvent, MetaEventCode};
+use crate::hci::{ConnectionHandle, ErrorCode, Opcode};
+use crate::le::connection::{
+    CELength, ConnectionInterval, ConnectionParameters, ConnectionUpdateParameters,
+    InitiatorFilterPolicy, MasterClockAccuracy, Role, SlaveLatency, SupervisionTimeout,
+};
+use crate::le::report::AddressType;
+use crate::le::scan::{OwnAddressType, ScanInterval, ScanWindow};
+use crate::{BTAddress, PackError, BT_ADDRESS_LEN};
+use core::convert::TryFrom;
+
+fn unpack_u16(buf: &[u8]) -> u16 {
+    u16::from_bytes_le(buf).expect("hardcoded length")
+}
+/// Unpacks a `T` (`ConnectionInterval`, `SlaveLatency`, etc) from `buf[index..index + 2]`.
+fn unpack_param<T: TryFrom<u16>>(buf: &[u8], index: usize) -> Result<T, PackError> {
+    let bytes = buf
+        .get(index..index + 2)
+        .ok_or_else(|| PackError::bad_index(index))?;
+    T::try_from(unpack_u16(bytes)).map_err(|_| PackError::bad_index(index))
+}
+fn pack_update_parameters(
+    parameters: &ConnectionUpdateParameters,
+    buf: &mut [u8],
+) -> Result<(), PackError> {
+    PackError::expect_length(ConnectionUpdateParameters::BYTE_LEN, buf)?;
+    if parameters.interval_min > parameters.interval_max
+        || parameters.min_ce_length > parameters.max_ce_length
+    {
+        return Err(PackError::InvalidFields);
+    }
+    buf[0..2].copy_from_slice(&u16::from(parameters.interval_min).to_bytes_le()[..]);
+    buf[2..4].copy_from_slice(&u16::from(parameters.interval_max).to_bytes_le()[..]);
+    buf[4..6].copy_from_slice(&u16::from(parameters.latency).to_bytes_le()[..]);
+    buf[6..8].copy_from_slice(&u16::from(parameters.supervision_timeout).to_bytes_le()[..]);
+    buf[8..10].copy_from_slice(&u16::from(parameters.min_ce_length).to_bytes_le()[..]);
+    buf[10..12].copy_from_slice(&u16::from(parameters.max_ce_length).to_bytes_le()[..]);
+    Ok(())
+}
+fn unpack_update_parameters(buf: &[u8]) -> Result<ConnectionUpdateParameters, PackError> {
+    PackError::expect_length(ConnectionUpdateParameters::BYTE_LEN, buf)?;
+    Ok(ConnectionUpdateParameters {
+        interval_min: unpack_param(buf, 0)?,
+        interval_max: unpack_param(buf, 2)?,
+        latency: unpack_param(buf, 4)?,
+        supervision_timeout: unpack_param(buf, 6)?,
+        min_ce_length: CELength(unpack_u16(&buf[8..10])),
+        max_ce_length: CELength(unpack_u16(&buf[10..12])),
+    })
+}
+/// LE Create Connection. Only returns a `CommandStatus`, the result of the connection attempt
+/// is reported with a [`ConnectionComplete`] (or [`EnhancedConnectionComplete`]) event.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct CreateConnection {
+    pub peer_address_type: AddressType,
+    pub peer_address: BTAddress,
+    pub parameters: ConnectionParameters,
+}
+impl CreateConnection {
+    pub const BYTE_LEN: usize =
+        2 + 2 + 1 + 1 + BT_ADDRESS_LEN + 1 + ConnectionUpdateParameters::BYTE_LEN;
+}
+impl Command for CreateConnection {
+    type Return = CommandStatus;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::CreateConnection.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let p = &self.parameters;
+        let window = u16::from(p.scan_window);
+        let interval = u16::from(p.scan_interval);
+        if window > interval {
+            return Err(PackError::InvalidFields);
+        }
+        buf[0..2].copy_from_slice(&interval.to_bytes_le()[..]);
+        buf[2..4].copy_from_slice(&window.to_bytes_le()[..]);
+        buf[4] = p.initiator_filter_policy.into();
+        buf[5] = self.peer_address_type.into();
+        self.peer_address.pack_into(&mut buf[6..12])?;
+        buf[12] = p.own_address_type.into();
+        pack_update_parameters(&p.connection, &mut buf[13..25])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(CreateConnection {
+            peer_address_type: AddressType::try_from(buf[5])
+                .map_err(|_| PackError::bad_index(5))?,
+            peer_address: BTAddress::unpack_from(&buf[6..12])?,
+            parameters: ConnectionParameters {
+                scan_interval: unpack_param::<ScanInterval>(buf, 0)?,
+                scan_window: unpack_param::<ScanWindow>(buf, 2)?,
+                initiator_filter_policy: InitiatorFilterPolicy::try_from(buf[4])
+                    .map_err(|_| PackError::bad_index(4))?,
+                own_address_type: OwnAddressType::try_from(buf[12])
+                    .map_err(|_| PackError::bad_index(12))?,
+                connection: unpack_update_parameters(&buf[13..25])?,
+            },
+        })
+    }
+}
+impl AsyncCommand for CreateConnection {
+    type Completion = AnyConnectionComplete;
+
+    /// Only connections established as the master with the requested peer (any peer when using
+    /// the White List) finish `CreateConnection`. Failures finish it too, except for directed
+    /// advertising timeouts which belong to the advertiser.
+    fn is_completion(&self, completion: &AnyConnectionComplete) -> bool {
+        match completion.status() {
+            ErrorCode::Ok => {}
+            ErrorCode::AdvertisingTimeout => return false,
+            _ => return true,
+        }
+        if completion.role() != Role::Master {
+            return false;
+        }
+        if self.parameters.initiator_filter_policy == InitiatorFilterPolicy::Whitelist {
+            return true;
+        }
+        let same_type =
+            completion.peer_address_type().is_random() == self.peer_address_type.is_random();
+        (same_type && completion.peer_address() == self.peer_address)
+            || completion.peer_resolvable_private_address() == Some(self.peer_address)
+    }
+}
+/// LE Create Connection Cancel. Cancels a pending [`CreateConnection`]. If the cancel succeeds,
+/// the controller sends a [`ConnectionComplete`] event with `ErrorCode::NoConnection`.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+pub struct CreateConnectionCancel {}
+impl Command for CreateConnectionCancel {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::CreateConnectionCancel.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(CreateConnectionCancel {})
+    }
+}
+/// Established LE Connection. Built from a successful [`ConnectionComplete`] or
+/// [`EnhancedConnectionComplete`] event.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct Connection {
+    pub handle: ConnectionHandle,
+    pub role: Role,
+    pub peer_address_type: AddressType,
+    pub peer_address: BTAddress,
+    pub interval: ConnectionInterval,
+    pub latency: SlaveLatency,
+    pub supervision_timeout: SupervisionTimeout,
+    pub master_clock_accuracy: MasterClockAccuracy,
+    /// PHY used to transmit. Connections start on the LE 1M PHY.
+    pub tx_phy: Phy,
+    /// PHY used to receive. Connections start on the LE 1M PHY.
+    pub rx_phy: Phy,
+    /// Max link layer packet lengths. Fragment ACL data to `data_length.tx.octets`.
+    pub data_length: DataLength,
+}
+impl Connection {
+    /// Updates `tx_phy` and `rx_phy` from a successful [`PhyUpdateComplete`] event of this
+    /// connection. Returns `true` if the event was applied.
+    pub fn apply_phy_update(&mut self, update: &PhyUpdateComplete) -> bool {
+        if update.handle != self.handle || update.status != ErrorCode::Ok {
+            return false;
+        }
+        self.tx_phy = update.tx_phy;
+        self.rx_phy = update.rx_phy;
+        true
+    }
+    /// Updates `data_length` from a [`DataLengthChange`] event of this connection. Returns
+    /// `true` if the event was applied.
+    pub fn apply_data_length_change(&mut self, change: &DataLengthChange) -> bool {
+        if change.handle != self.handle {
+            return false;
+        }
+        self.data_length = change.data_length;
+        true
+    }
+}
+/// LE Connection Complete Event. Sent to both the initiator and the advertiser when a new
+/// connection is created (or when creating a connection failed, see `status`).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct ConnectionComplete {
+    pub status: ErrorCode,
+    pub handle: ConnectionHandle,
+    pub role: Role,
+    pub peer_address_type: AddressType,
+    pub peer_address: BTAddress,
+    pub interval: ConnectionInterval,
+    pub latency: SlaveLatency,
+    pub supervision_timeout: SupervisionTimeout,
+    pub master_clock_accuracy: MasterClockAccuracy,
+}
+impl ConnectionComplete {
+    pub const BYTE_LEN: usize = 1 + 2 + 1 + 1 + BT_ADDRESS_LEN + 2 + 2 + 2 + 1;
+    /// Returns the [`Connection`] if `status == ErrorCode::Ok` or the `status` error otherwise.
+    pub fn connection(&self) -> Result<Connection, ErrorCode> {
+        self.status.error()?;
+        Ok(Connection {
+            handle: self.handle,
+            role: self.role,
+            peer_address_type: self.peer_address_type,
+            peer_address: self.peer_address,
+            interval: self.interval,
+            latency: self.latency,
+            supervision_timeout: self.supervision_timeout,
+            master_clock_accuracy: self.master_clock_accuracy,
+            tx_phy: Phy::LE1M,
+            rx_phy: Phy::LE1M,
+            data_length: DataLength::DEFAULT,
+        })
+    }
+}
+impl MetaEvent for ConnectionComplete {
+    const META_CODE: MetaEventCode = MetaEventCode::ConnectionComplete;
+
+    fn meta_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn meta_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(ConnectionComplete {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            handle: ConnectionHandle::unpack_from(&buf[1..3])?,
+            role: Role::try_from(buf[3]).map_err(|_| PackError::bad_index(3))?,
+            peer_address_type: AddressType::try_from(buf[4])
+                .map_err(|_| PackError::bad_index(4))?,
+            peer_address: BTAddress::unpack_from(&buf[5..11])?,
+            interval: unpack_param(buf, 11)?,
+            latency: unpack_param(buf, 13)?,
+            supervision_timeout: unpack_param(buf, 15)?,
+            master_clock_accuracy: MasterClockAccuracy::try_from(buf[17])
+                .map_err(|_| PackError::bad_index(17))?,
+        })
+    }
+
+    fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.handle.pack_into(&mut buf[1..3])?;
+        buf[3] = self.role.into();
+        buf[4] = self.peer_address_type.into();
+        self.peer_address.pack_into(&mut buf[5..11])?;
+        buf[11..13].copy_from_slice(&u16::from(self.interval).to_bytes_le()[..]);
+        buf[13..15].copy_from_slice(&u16::from(self.latency).to_bytes_le()[..]);
+        buf[15..17].copy_from_slice(&u16::from(self.supervision_timeout).to_bytes_le()[..]);
+        buf[17] = self.master_clock_accuracy.into();
+        Ok(())
+    }
+}
+/// LE Enhanced Connection Complete Event. Replaces [`ConnectionComplete`] when enabled in the
+/// `MetaEventMask`. Also contains the resolvable private addresses used for the connection
+/// (`BTAddress::ZEROED` if none were used).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct EnhancedConnectionComplete {
+    pub status: ErrorCode,
+    pub handle: ConnectionHandle,
+    pub role: Role,
+    pub peer_address_type: AddressType,
+    pub peer_address: BTAddress,
+    pub local_resolvable_private_address: BTAddress,
+    pub peer_resolvable_private_address: BTAddress,
+    pub interval: ConnectionInterval,
+    pub latency: SlaveLatency,
+    pub supervision_timeout: SupervisionTimeout,
+    pub master_clock_accuracy: MasterClockAccuracy,
+}
+impl EnhancedConnectionComplete {
+    pub const BYTE_LEN: usize = ConnectionComplete::BYTE_LEN + BT_ADDRESS_LEN * 2;
+    /// Returns the [`Connection`] if `status == ErrorCode::Ok` or the `status` error otherwise.
+    pub fn connection(&self) -> Result<Connection, ErrorCode> {
+        self.status.error()?;
+        Ok(Connection {
+            handle: self.handle,
+            role: self.role,
+            peer_address_type: self.peer_address_type,
+            peer_address: self.peer_address,
+            interval: self.interval,
+            latency: self.latency,
+            supervision_timeout: self.supervision_timeout,
+            master_clock_accuracy: self.master_clock_accuracy,
+            tx_phy: Phy::LE1M,
+            rx_phy: Phy::LE1M,
+            data_length: DataLength::DEFAULT,
+        })
+    }
+}
+impl MetaEvent for EnhancedConnectionComplete {
+    const META_CODE: MetaEventCode = MetaEventCode::EnhancedConnectionComplete;
+
+    fn meta_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn meta_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(EnhancedConnectionComplete {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            handle: ConnectionHandle::unpack_from(&buf[1..3])?,
+            role: Role::try_from(buf[3]).map_err(|_| PackError::bad_index(3))?,
+            peer_address_type: AddressType::try_from(buf[4])
+                .map_err(|_| PackError::bad_index(4))?,
+            peer_address: BTAddress::unpack_from(&buf[5..11])?,
+            local_resolvable_private_address: BTAddress::unpack_from(&buf[11..17])?,
+            peer_resolvable_private_address: BTAddress::unpack_from(&buf[17..23])?,
+            interval: unpack_param(buf, 23)?,
+            latency: unpack_param(buf, 25)?,
+            supervision_timeout: unpack_param(buf, 27)?,
+            master_clock_accuracy: MasterClockAccuracy::try_from(buf[29])
+                .map_err(|_| PackError::bad_index(29))?,
+        })
+    }
+
+    fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.handle.pack_into(&mut buf[1..3])?;
+        buf[3] = self.role.into();
+        buf[4] = self.peer_address_type.into();
+        self.peer_address.pack_into(&mut buf[5..11])?;
+        self.local_resolvable_private_address
+            .pack_into(&mut buf[11..17])?;
+        self.peer_resolvable_private_address
+            .pack_into(&mut buf[17..23])?;
+        buf[23..25].copy_from_slice(&u16::from(self.interval).to_bytes_le()[..]);
+        buf[25..27].copy_from_slice(&u16::from(self.latency).to_bytes_le()[..]);
+        buf[27..29].copy_from_slice(&u16::from(self.supervision_timeout).to_bytes_le()[..]);
+        buf[29] = self.master_clock_accuracy.into();
+        Ok(())
+    }
+}
+/// Either a [`ConnectionComplete`] or an [`EnhancedConnectionComplete`] event (depending on
+/// which one is enabled in the `MetaEventMask`). Completion event of [`CreateConnection`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub enum AnyConnectionComplete {
+    Legacy(ConnectionComplete),
+    Enhanced(EnhancedConnectionComplete),
+}
+impl AnyConnectionComplete {
+    pub fn status(&self) -> ErrorCode {
+        match self {
+            AnyConnectionComplete::Legacy(c) => c.status,
+            AnyConnectionComplete::Enhanced(c) => c.status,
+        }
+    }
+    pub fn role(&self) -> Role {
+        match self {
+            AnyConnectionComplete::Legacy(c) => c.role,
+            AnyConnectionComplete::Enhanced(c) => c.role,
+        }
+    }
+    pub fn peer_address_type(&self) -> AddressType {
+        match self {
+            AnyConnectionComplete::Legacy(c) => c.peer_address_type,
+            AnyConnectionComplete::Enhanced(c) => c.peer_address_type,
+        }
+    }
+    pub fn peer_address(&self) -> BTAddress {
+        match self {
+            AnyConnectionComplete::Legacy(c) => c.peer_address,
+            AnyConnectionComplete::Enhanced(c) => c.peer_address,
+        }
+    }
+    /// Resolvable private address used by the peer (only reported by
+    /// [`EnhancedConnectionComplete`]).
+    pub fn peer_resolvable_private_address(&self) -> Option<BTAddress> {
+        match self {
+            AnyConnectionComplete::Legacy(_) => None,
+            AnyConnectionComplete::Enhanced(c) => Some(c.peer_resolvable_private_address),
+        }
+    }
+    /// Returns the [`Connection`] if `status == ErrorCode::Ok` or the `status` error otherwise.
+    pub fn connection(&self) -> Result<Connection, ErrorCode> {
+        match self {
+            AnyConnectionComplete::Legacy(c) => c.connection(),
+            AnyConnectionComplete::Enhanced(c) => c.connection(),
+        }
+    }
+}
+impl Event for AnyConnectionComplete {
+    const EVENT_CODE: EventCode = EventCode::LEMeta;
+
+    fn matches_event_packet(packet: &EventPacket<&[u8]>) -> bool {
+        ConnectionComplete::matches_event_packet(packet)
+            || EnhancedConnectionComplete::matches_event_packet(packet)
+    }
+
+    fn event_byte_len(&self) -> usize {
+        match self {
+            AnyConnectionComplete::Legacy(c) => c.event_byte_len(),
+            AnyConnectionComplete::Enhanced(c) => c.event_byte_len(),
+        }
+    }
+
+    fn event_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        match buf.first().map(|code| MetaEventCode::try_from(*code)) {
+            Some(Ok(MetaEventCode::ConnectionComplete)) => Ok(AnyConnectionComplete::Legacy(
+                ConnectionComplete::event_unpack_from(buf)?,
+            )),
+            Some(Ok(MetaEventCode::EnhancedConnectionComplete)) => {
+                Ok(AnyConnectionComplete::Enhanced(
+                    EnhancedConnectionComplete::event_unpack_from(buf)?,
+                ))
+            }
+            Some(_) => Err(PackError::bad_index(0)),
+            None => Err(PackError::BadLength {
+                expected: 1,
+                got: 0,
+            }),
+        }
+    }
+
+    fn event_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        match self {
+            AnyConnectionComplete::Legacy(c) => c.event_pack_into(buf),
+            AnyConnectionComplete::Enhanced(c) => c.event_pack_into(buf),
+        }
+    }
+}
+/// LE Connection Update. Changes the connection parameters of an existing connection. Only
+/// returns a `CommandStatus`, the [`ConnectionUpdateComplete`] event is sent once the new
+/// parameters are applied.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct ConnectionUpdate {
+    pub handle: ConnectionHandle,
+    pub parameters: ConnectionUpdateParameters,
+}
+impl ConnectionUpdate {
+    pub const BYTE_LEN: usize = ConnectionHandle::BYTE_LEN + ConnectionUpdateParameters::BYTE_LEN;
+}
+impl Command for ConnectionUpdate {
+    type Return = CommandStatus;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ConnectionUpdate.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        self.handle.pack_into(&mut buf[..2])?;
+        pack_update_parameters(&self.parameters, &mut buf[2..])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(ConnectionUpdate {
+            handle: ConnectionHandle::unpack_from(&buf[..2])?,
+            parameters: unpack_update_parameters(&buf[2..])?,
+        })
+    }
+}
+impl AsyncCommand for ConnectionUpdate {
+    type Completion = ConnectionUpdateComplete;
+
+    fn is_completion(&self, completion: &Self::Completion) -> bool {
+        completion.handle == self.handle
+    }
+}
+/// LE Connection Update Complete Event. Sent when the connection parameters of a connection
+/// change (requested locally with [`ConnectionUpdate`] or by the remote device).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct ConnectionUpdateComplete {
+    pub status: ErrorCode,
+    pub handle: ConnectionHandle,
+    pub interval: ConnectionInterval,
+    pub latency: SlaveLatency,
+    pub supervision_timeout: SupervisionTimeout,
+}
+impl ConnectionUpdateComplete {
+    pub const BYTE_LEN: usize = 1 + ConnectionHandle::BYTE_LEN + 2 + 2 + 2;
+}
+impl MetaEvent for ConnectionUpdateComplete {
+    const META_CODE: MetaEventCode = MetaEventCode::ConnectionUpdateComplete;
+
+    fn meta_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn meta_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(ConnectionUpdateComplete {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            handle: ConnectionHandle::unpack_from(&buf[1..3])?,
+            interval: unpack_param(buf, 3)?,
+            latency: unpack_param(buf, 5)?,
+            supervision_timeout: unpack_param(buf, 7)?,
+        })
+    }
+
+    fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.handle.pack_into(&mut buf[1..3])?;
+        buf[3..5].copy_from_slice(&u16::from(self.interval).to_bytes_le()[..]);
+        buf[5..7].copy_from_slice(&u16::from(self.latency).to_bytes_le()[..]);
+        buf[7..9].copy_from_slice(&u16::from(self.supervision_timeout).to_bytes_le()[..]);
+        Ok(())
+    }
+}
+/// LE Remote Connection Parameter Request Event. Sent when the remote device wants to change
+/// the connection parameters. The host must answer with
+/// [`RemoteConnectionParameterRequestReply`] or
+/// [`RemoteConnectionParameterRequestNegativeReply`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct RemoteConnectionParameterRequest {
+    pub handle: ConnectionHandle,
+    pub interval_min: ConnectionInterval,
+    pub interval_max: ConnectionInterval,
+    pub max_latency: SlaveLatency,
+    pub supervision_timeout: SupervisionTimeout,
+}
+impl RemoteConnectionParameterRequest {
+    pub const BYTE_LEN: usize = ConnectionHandle::BYTE_LEN + 2 + 2 + 2 + 2;
+    /// Returns the requested parameters as `ConnectionUpdateParameters` (with CE lengths of 0)
+    /// that can be passed to [`RemoteConnectionParameterRequestReply`] to accept the request.
+    pub fn requested_parameters(&self) -> ConnectionUpdateParameters {
+        ConnectionUpdateParameters {
+            interval_min: self.interval_min,
+            interval_max: self.interval_max,
+            latency: self.max_latency,
+            supervision_timeout: self.supervision_timeout,
+            min_ce_length: CELength::ZEROED,
+            max_ce_length: CELength::ZEROED,
+        }
+    }
+}
+impl MetaEvent for RemoteConnectionParameterRequest {
+    const META_CODE: MetaEventCode = MetaEventCode::RemoteConnectionParametersRequest;
+
+    fn meta_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn meta_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(RemoteConnectionParameterRequest {
+            handle: ConnectionHandle::unpack_from(&buf[0..2])?,
+            interval_min: unpack_param(buf, 2)?,
+            interval_max: unpack_param(buf, 4)?,
+            max_latency: unpack_param(buf, 6)?,
+            supervision_timeout: unpack_param(buf, 8)?,
+        })
+    }
+
+    fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        self.handle.pack_into(&mut buf[0..2])?;
+        buf[2..4].copy_from_slice(&u16::from(self.interval_min).to_bytes_le()[..]);
+        buf[4..6].copy_from_slice(&u16::from(self.interval_max).to_bytes_le()[..]);
+        buf[6..8].copy_from_slice(&u16::from(self.max_latency).to_bytes_le()[..]);
+        buf[8..10].copy_from_slice(&u16::from(self.supervision_timeout).to_bytes_le()[..]);
+        Ok(())
+    }
+}
+/// Return parameters of commands that only return a status and a [`ConnectionHandle`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct ConnectionHandleReturn {
+    pub status: ErrorCode,
+    pub handle: ConnectionHandle,
+}
+impl ConnectionHandleReturn {
+    pub const BYTE_LEN: usize = 1 + ConnectionHandle::BYTE_LEN;
+}
+impl ReturnParameters for ConnectionHandleReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.handle.pack_into(&mut buf[1..3])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(ConnectionHandleReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            handle: ConnectionHandle::unpack_from(&buf[1..3])?,
+        })
+    }
+}
+/// Accept a [`RemoteConnectionParameterRequest`] with `parameters`.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct RemoteConnectionParameterRequestReply {
+    pub handle: ConnectionHandle,
+    pub parameters: ConnectionUpdateParameters,
+}
+impl RemoteConnectionParameterRequestReply {
+    pub const BYTE_LEN: usize = ConnectionHandle::BYTE_LEN + ConnectionUpdateParameters::BYTE_LEN;
+}
+impl Command for RemoteConnectionParameterRequestReply {
+    type Return = CommandComplete<ConnectionHandleReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::RemoteConnectionParameterRequestReply.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        self.handle.pack_into(&mut buf[..2])?;
+        pack_update_parameters(&self.parameters, &mut buf[2..])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(RemoteConnectionParameterRequestReply {
+            handle: ConnectionHandle::unpack_from(&buf[..2])?,
+            parameters: unpack_update_parameters(&buf[2..])?,
+        })
+    }
+}
+/// Reject a [`RemoteConnectionParameterRequest`]. `reason` is usually
+/// `ErrorCode::UnacceptableConnectionParameters`.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct RemoteConnectionParameterRequestNegativeReply {
+    pub handle: ConnectionHandle,
+    pub reason: ErrorCode,
+}
+impl RemoteConnectionParameterRequestNegativeReply {
+    pub const BYTE_LEN: usize = ConnectionHandle::BYTE_LEN + 1;
+}
+impl Command for RemoteConnectionParameterRequestNegativeReply {
+    type Return = CommandComplete<ConnectionHandleReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::RemoteConnectionParameterRequestNegativeReply.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        self.handle.pack_into(&mut buf[..2])?;
+        buf[2] = self.reason.into();
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(RemoteConnectionParameterRequestNegativeReply {
+            handle: ConnectionHandle::unpack_from(&buf[..2])?,
+            reason: ErrorCode::try_from(buf[2]).map_err(|_| PackError::bad_index(2))?,
+        })
+    }
+}
@@ -0,0 +1,446 @@
+//! LE Data Length Extension commands ([`SetDataLength`], [`ReadSuggestedDefaultDataLength`],
+//! [`WriteSuggestedDefaultDataLength`], [`ReadMaximumDataLength`]) and the
+//! [`DataLengthChange`] event. Lets connections use link layer packets with up to 251 bytes of
+//! payload (instead of 27).
+use crate::bytes::ToFromBytesEndian;
+use crate::hci::command::Command;
+use crate::hci::event::{CommandComplete, ReturnParameters, StatusReturn};
+use crate::hci::le::connection::ConnectionHandleReturn;
+use crate::hci::le::{LEControllerOpcode, MetaEvent, MetaEventCode};
+use crate::hci::{ConnectionHandle, ErrorCode, Opcode};
+use crate::PackError;
+use core::convert::TryFrom;
+
+/// Max payload octets and max time (in microseconds) of the link layer packets sent in one
+/// direction.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PacketLength {
+    pub octets: u16,
+    pub time: u16,
+}
+impl PacketLength {
+    pub const BYTE_LEN: usize = 4;
+    pub const MIN_OCTETS: u16 = 0x001B;
+    pub const MAX_OCTETS: u16 = 0x00FB;
+    pub const MIN_TIME: u16 = 0x0148;
+    pub const MAX_TIME: u16 = 0x4290;
+    /// 27 octets in 328 us (the length every connection starts with).
+    pub const DEFAULT: PacketLength = PacketLength {
+        octets: Self::MIN_OCTETS,
+        time: Self::MIN_TIME,
+    };
+    /// 251 octets in 2120 us (the longest packet on the LE 1M PHY).
+    pub const MAX_1M: PacketLength = PacketLength {
+        octets: Self::MAX_OCTETS,
+        time: 0x0848,
+    };
+    /// If `octets` and `time` are in the ranges allowed for the host to request.
+    pub fn is_valid(self) -> bool {
+        self.octets >= Self::MIN_OCTETS
+            && self.octets <= Self::MAX_OCTETS
+            && self.time >= Self::MIN_TIME
+            && self.time <= Self::MAX_TIME
+    }
+    pub fn pack_into(self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0..2].copy_from_slice(&self.octets.to_bytes_le());
+        buf[2..4].copy_from_slice(&self.time.to_bytes_le());
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(PacketLength {
+            octets: u16::from_le_bytes([buf[0], buf[1]]),
+            time: u16::from_le_bytes([buf[2], buf[3]]),
+        })
+    }
+}
+impl Default for PacketLength {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+/// Max link layer packet lengths of a connection (or supported by the controller) in both
+/// directions.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct DataLength {
+    pub tx: PacketLength,
+    pub rx: PacketLength,
+}
+impl DataLength {
+    pub const BYTE_LEN: usize = PacketLength::BYTE_LEN * 2;
+    pub const DEFAULT: DataLength = DataLength {
+        tx: PacketLength::DEFAULT,
+        rx: PacketLength::DEFAULT,
+    };
+    pub fn pack_into(self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        self.tx.pack_into(&mut buf[..PacketLength::BYTE_LEN])?;
+        self.rx.pack_into(&mut buf[PacketLength::BYTE_LEN..])
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(DataLength {
+            tx: PacketLength::unpack_from(&buf[..PacketLength::BYTE_LEN])?,
+            rx: PacketLength::unpack_from(&buf[PacketLength::BYTE_LEN..])?,
+        })
+    }
+}
+/// LE Set Data Length Command. Suggests the max TX packet length of a connection. The
+/// controller sends [`DataLengthChange`] if the lengths of the connection change.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SetDataLength {
+    pub handle: ConnectionHandle,
+    pub tx: PacketLength,
+}
+impl SetDataLength {
+    pub const BYTE_LEN: usize = ConnectionHandle::BYTE_LEN + PacketLength::BYTE_LEN;
+}
+impl Command for SetDataLength {
+    type Return = CommandComplete<ConnectionHandleReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetDataLength.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        if !self.tx.is_valid() {
+            return Err(PackError::InvalidFields);
+        }
+        self.handle.pack_into(&mut buf[..2])?;
+        self.tx.pack_into(&mut buf[2..])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(SetDataLength {
+            handle: ConnectionHandle::unpack_from(&buf[..2])?,
+            tx: PacketLength::unpack_from(&buf[2..])?,
+        })
+    }
+}
+/// LE Read Suggested Default Data Length Command. Reads the max TX packet length used for new
+/// connections.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadSuggestedDefaultDataLength {}
+impl Command for ReadSuggestedDefaultDataLength {
+    type Return = CommandComplete<SuggestedDefaultDataLengthReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ReadSuggestedDefaultDataLength.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadSuggestedDefaultDataLength {})
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SuggestedDefaultDataLengthReturn {
+    pub status: ErrorCode,
+    pub tx: PacketLength,
+}
+impl SuggestedDefaultDataLengthReturn {
+    pub const BYTE_LEN: usize = 1 + PacketLength::BYTE_LEN;
+}
+impl ReturnParameters for SuggestedDefaultDataLengthReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.tx.pack_into(&mut buf[1..])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(SuggestedDefaultDataLengthReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            tx: PacketLength::unpack_from(&buf[1..])?,
+        })
+    }
+}
+/// LE Write Suggested Default Data Length Command. Sets the max TX packet length used for new
+/// connections.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct WriteSuggestedDefaultDataLength {
+    pub tx: PacketLength,
+}
+impl Command for WriteSuggestedDefaultDataLength {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::WriteSuggestedDefaultDataLength.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        PacketLength::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        if !self.tx.is_valid() {
+            return Err(PackError::InvalidFields);
+        }
+        self.tx.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(WriteSuggestedDefaultDataLength {
+            tx: PacketLength::unpack_from(buf)?,
+        })
+    }
+}
+/// LE Read Maximum Data Length Command. Reads the longest packets the controller supports.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadMaximumDataLength {}
+impl Command for ReadMaximumDataLength {
+    type Return = CommandComplete<MaximumDataLengthReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ReadMaximumDataLength.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadMaximumDataLength {})
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct MaximumDataLengthReturn {
+    pub status: ErrorCode,
+    pub supported: DataLength,
+}
+impl MaximumDataLengthReturn {
+    pub const BYTE_LEN: usize = 1 + DataLength::BYTE_LEN;
+}
+impl ReturnParameters for MaximumDataLengthReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.supported.pack_into(&mut buf[1..])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(MaximumDataLengthReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            supported: DataLength::unpack_from(&buf[1..])?,
+        })
+    }
+}
+/// LE Data Length Change Event. Sent when the max packet lengths of a connection change. See
+/// [`Connection::apply_data_length_change`](crate::hci::le::connection::Connection::apply_data_length_change).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct DataLengthChange {
+    pub handle: ConnectionHandle,
+    pub data_length: DataLength,
+}
+impl DataLengthChange {
+    pub const BYTE_LEN: usize = ConnectionHandle::BYTE_LEN + DataLength::BYTE_LEN;
+}
+impl MetaEvent for DataLengthChange {
+    const META_CODE: MetaEventCode = MetaEventCode::DataLengthChange;
+
+    fn meta_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn meta_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(DataLengthChange {
+            handle: ConnectionHandle::unpack_from(&buf[..2])?,
+            data_length: DataLength::unpack_from(&buf[2..])?,
+        })
+    }
+
+    fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        self.handle.pack_into(&mut buf[..2])?;
+        self.data_length.pack_into(&mut buf[2..])
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{
+        DataLength, DataLengthChange, MaximumDataLengthReturn, PacketLength, SetDataLength,
+        SuggestedDefaultDataLengthReturn, WriteSuggestedDefaultDataLength,
+    };
+    use crate::hci::command::Command;
+    use crate::hci::event::ReturnParameters;
+    use crate::hci::le::MetaEvent;
+    use crate::hci::{ConnectionHandle, ErrorCode};
+    use crate::PackError;
+
+    #[test]
+    fn test_packet_length_range() {
+        assert!(PacketLength::DEFAULT.is_valid());
+        assert!(PacketLength::MAX_1M.is_valid());
+        let invalid = [
+            PacketLength {
+                octets: PacketLength::MIN_OCTETS - 1,
+                time: PacketLength::MIN_TIME,
+            },
+            PacketLength {
+                octets: PacketLength::MAX_OCTETS + 1,
+                time: PacketLength::MIN_TIME,
+            },
+            PacketLength {
+                octets: PacketLength::MIN_OCTETS,
+                time: PacketLength::MIN_TIME - 1,
+            },
+            PacketLength {
+                octets: PacketLength::MIN_OCTETS,
+                time: PacketLength::MAX_TIME + 1,
+            },
+        ];
+        let mut buf = [0_u8; SetDataLength::BYTE_LEN];
+        for &tx in &invalid {
+            assert!(!tx.is_valid());
+            let command = SetDataLength {
+                handle: ConnectionHandle::new(0x0001),
+                tx,
+            };
+            assert_eq!(command.pack_into(&mut buf), Err(PackError::InvalidFields));
+            assert_eq!(
+                WriteSuggestedDefaultDataLength { tx }.pack_into(&mut buf[2..]),
+                Err(PackError::InvalidFields)
+            );
+        }
+    }
+    #[test]
+    fn test_set_data_length_pack_unpack() {
+        let command = SetDataLength {
+            handle: ConnectionHandle::new(0x0002),
+            tx: PacketLength::MAX_1M,
+        };
+        let mut buf = [0_u8; SetDataLength::BYTE_LEN];
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x02, 0x00, 0xFB, 0x00, 0x48, 0x08]);
+        assert_eq!(SetDataLength::unpack_from(&buf), Ok(command));
+        assert_eq!(
+            SetDataLength::unpack_from(&buf[..5]),
+            Err(PackError::BadLength {
+                expected: SetDataLength::BYTE_LEN,
+                got: 5
+            })
+        );
+        assert!(command.pack_into(&mut buf[..5]).is_err());
+
+        let write = WriteSuggestedDefaultDataLength {
+            tx: PacketLength::DEFAULT,
+        };
+        let mut buf = [0_u8; PacketLength::BYTE_LEN];
+        write.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x1B, 0x00, 0x48, 0x01]);
+        assert_eq!(
+            WriteSuggestedDefaultDataLength::unpack_from(&buf),
+            Ok(write)
+        );
+        assert!(WriteSuggestedDefaultDataLength::unpack_from(&buf[..3]).is_err());
+    }
+    #[test]
+    fn test_data_length_returns() {
+        let suggested = SuggestedDefaultDataLengthReturn {
+            status: ErrorCode::Ok,
+            tx: PacketLength::MAX_1M,
+        };
+        let mut buf = [0_u8; SuggestedDefaultDataLengthReturn::BYTE_LEN];
+        suggested.pack_into(&mut buf).unwrap();
+        assert_eq!(
+            SuggestedDefaultDataLengthReturn::unpack_from(&buf),
+            Ok(suggested)
+        );
+        assert!(SuggestedDefaultDataLengthReturn::unpack_from(&buf[..4]).is_err());
+
+        let maximum = MaximumDataLengthReturn {
+            status: ErrorCode::Ok,
+            supported: DataLength {
+                tx: PacketLength::MAX_1M,
+                rx: PacketLength {
+                    octets: PacketLength::MAX_OCTETS,
+                    time: PacketLength::MAX_TIME,
+                },
+            },
+        };
+        let mut buf = [0_u8; MaximumDataLengthReturn::BYTE_LEN];
+        maximum.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x00, 0xFB, 0x00, 0x48, 0x08, 0xFB, 0x00, 0x90, 0x42]);
+        assert_eq!(MaximumDataLengthReturn::unpack_from(&buf), Ok(maximum));
+        assert!(MaximumDataLengthReturn::unpack_from(&buf[..8]).is_err());
+        buf[0] = 0xFF;
+        assert_eq!(
+            MaximumDataLengthReturn::unpack_from(&buf),
+            Err(PackError::bad_index(0))
+        );
+    }
+    #[test]
+    fn test_data_length_change_pack_unpack() {
+        let event = DataLengthChange {
+            handle: ConnectionHandle::new(0x0EFF),
+            data_length: DataLength {
+                tx: PacketLength::MAX_1M,
+                rx: PacketLength::DEFAULT,
+            },
+        };
+        let mut buf = [0_u8; DataLengthChange::BYTE_LEN];
+        event.meta_pack_into(&mut buf).unwrap();
+        assert_eq!(DataLengthChange::meta_unpack_from(&buf), Ok(event));
+        assert_eq!(
+            DataLengthChange::meta_unpack_from(&buf[..9]),
+            Err(PackError::BadLength {
+                expected: DataLengthChange::BYTE_LEN,
+                got: 9
+            })
+        );
+        buf[1] = 0x0F;
+        assert!(DataLengthChange::meta_unpack_from(&buf).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//! LE [`Encrypt`] command and return parameters. Lets the host use the controller's AES-128
+//! (see [`crate::crypto::CryptoProvider`]).
+use crate::crypto::{AES_BLOCK_LEN, AES_KEY_LEN};
+use crate::hci::command::Command;
+use crate::hci::event::{CommandComplete, ReturnParameters};
+use crate::hci::le::LEControllerOpcode;
+use crate::hci::{ErrorCode, Opcode};
+use crate::PackError;
+use core::convert::TryFrom;
+
+/// LE Encrypt Command. AES-128 encrypts `plaintext` with `key`. `key`, `plaintext` and the
+/// returned encrypted data are least significant octet first (HCI byte order, the reverse of
+/// FIPS-197 and [`crate::crypto::e`]).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Encrypt {
+    pub key: [u8; AES_KEY_LEN],
+    pub plaintext: [u8; AES_BLOCK_LEN],
+}
+impl Encrypt {
+    pub const BYTE_LEN: usize = AES_KEY_LEN + AES_BLOCK_LEN;
+}
+impl Command for Encrypt {
+    type Return = CommandComplete<EncryptReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::Encrypt.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[..AES_KEY_LEN].copy_from_slice(&self.key[..]);
+        buf[AES_KEY_LEN..].copy_from_slice(&self.plaintext[..]);
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let mut key = [0_u8; AES_KEY_LEN];
+        key.copy_from_slice(&buf[..AES_KEY_LEN]);
+        let mut plaintext = [0_u8; AES_BLOCK_LEN];
+        plaintext.copy_from_slice(&buf[AES_KEY_LEN..]);
+        Ok(Encrypt { key, plaintext })
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct EncryptReturn {
+    pub status: ErrorCode,
+    /// Least significant octet first.
+    pub encrypted: [u8; AES_BLOCK_LEN],
+}
+impl EncryptReturn {
+    pub const BYTE_LEN: usize = 1 + AES_BLOCK_LEN;
+}
+impl ReturnParameters for EncryptReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        buf[1..].copy_from_slice(&self.encrypted[..]);
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let mut encrypted = [0_u8; AES_BLOCK_LEN];
+        encrypted.copy_from_slice(&buf[1..]);
+        Ok(EncryptReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            encrypted,
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{Encrypt, EncryptReturn};
+    use crate::crypto::{AES_BLOCK_LEN, AES_KEY_LEN};
+    use crate::hci::command::Command;
+    use crate::hci::event::ReturnParameters;
+    use crate::hci::ErrorCode;
+    use crate::PackError;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn test_encrypt_pack_unpack() {
+        let mut command = Encrypt::default();
+        for (i, b) in command.key.iter_mut().enumerate() {
+            *b = u8::try_from(i).unwrap();
+        }
+        command.plaintext = [0xFF; AES_BLOCK_LEN];
+        let mut buf = [0_u8; Encrypt::BYTE_LEN];
+        assert_eq!(command.byte_len(), 32);
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(buf[..AES_KEY_LEN], command.key);
+        assert_eq!(buf[AES_KEY_LEN..], command.plaintext);
+        assert_eq!(Encrypt::unpack_from(&buf), Ok(command));
+        assert_eq!(
+            Encrypt::unpack_from(&buf[..31]),
+            Err(PackError::BadLength {
+                expected: Encrypt::BYTE_LEN,
+                got: 31
+            })
+        );
+        assert!(command.pack_into(&mut buf[..31]).is_err());
+    }
+    #[test]
+    fn test_encrypt_return_pack_unpack() {
+        let ret = EncryptReturn {
+            status: ErrorCode::Ok,
+            encrypted: [0x5A; AES_BLOCK_LEN],
+        };
+        let mut buf = [0_u8; EncryptReturn::BYTE_LEN];
+        ret.pack_into(&mut buf).unwrap();
+        assert_eq!(buf[0], 0x00);
+        assert_eq!(EncryptReturn::unpack_from(&buf), Ok(ret));
+        assert!(EncryptReturn::unpack_from(&buf[..16]).is_err());
+        buf[0] = 0xFF;
+        assert_eq!(
+            EncryptReturn::unpack_from(&buf),
+            Err(PackError::bad_index(0))
+        );
+    }
+}
@@ -0,0 +1,1115 @@
+//! LE Extended Advertising commands ([`SetExtendedAdvertisingParameters`],
+//! [`SetExtendedAdvertisingData`], [`SetExtendedAdvertisingEnable`], etc) and the
+//! [`AdvertisingSetTerminated`] event. Unlike legacy advertising, the controller can run
+//! multiple advertising sets (each with its own [`AdvertisingHandle`]) with up to
+//! [`MAX_EXTENDED_ADVERTISING_DATA_LEN`] bytes of data.
+use crate::bytes::ToFromBytesEndian;
+use crate::hci::command::Command;
+use crate::hci::event::{CommandComplete, ReturnParameters, StatusReturn};
+use crate::hci::le::advertise::{TxPowerLevel, TxPowerLevelReturn};
+use crate::hci::le::phy::Phy;
+use crate::hci::le::{LEControllerOpcode, MetaEvent, MetaEventCode};
+use crate::hci::{ConnectionHandle, ErrorCode, Opcode};
+use crate::le::advertiser::{
+    AdvertisingInterval, ChannelMap, FilterPolicy, OwnAddressType, PeerAddressType,
+};
+use crate::ConversionError;
+use crate::{BTAddress, PackError, BT_ADDRESS_LEN};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// Max length of the advertising (or scan response) data of an advertising set.
+pub const MAX_EXTENDED_ADVERTISING_DATA_LEN: usize = 1650;
+/// Max length of the data in one [`SetExtendedAdvertisingData`] or
+/// [`SetExtendedScanResponseData`] command.
+pub const MAX_ADVERTISING_DATA_FRAGMENT_LEN: usize = 251;
+
+/// Identifies an advertising set (0x00-0xEF).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct AdvertisingHandle(u8);
+impl AdvertisingHandle {
+    pub const BYTE_LEN: usize = 1;
+    pub const MAX_HANDLE_U8: u8 = 0xEF;
+    /// Creates a new `AdvertisingHandle`.
+    /// # Panics
+    /// Panics if `handle > AdvertisingHandle::MAX_HANDLE_U8` (0xEF).
+    pub fn new(handle: u8) -> AdvertisingHandle {
+        assert!(
+            handle <= Self::MAX_HANDLE_U8,
+            "invalid advertising handle '{}'",
+            handle
+        );
+        AdvertisingHandle(handle)
+    }
+}
+impl From<AdvertisingHandle> for u8 {
+    fn from(h: AdvertisingHandle) -> Self {
+        h.0
+    }
+}
+impl TryFrom<u8> for AdvertisingHandle {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value <= Self::MAX_HANDLE_U8 {
+            Ok(AdvertisingHandle(value))
+        } else {
+            Err(ConversionError(()))
+        }
+    }
+}
+/// Bit positions of the flags in [`AdvertisingEventProperties`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum AdvertisingEventPropertyFlags {
+    Connectable = 0,
+    Scannable = 1,
+    Directed = 2,
+    HighDutyCycleDirected = 3,
+    /// Use legacy advertising PDUs. Data is then limited to 31 bytes.
+    Legacy = 4,
+    /// Omit the advertiser's address from all PDUs.
+    Anonymous = 5,
+    /// Include the TxPower in the extended header of the advertising PDU.
+    IncludeTxPower = 6,
+}
+impl From<AdvertisingEventPropertyFlags> for u16 {
+    fn from(f: AdvertisingEventPropertyFlags) -> Self {
+        f as u16
+    }
+}
+/// Type of advertising events of an advertising set.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct AdvertisingEventProperties(pub u16);
+impl AdvertisingEventProperties {
+    pub const BYTE_LEN: usize = 2;
+    pub const ZEROED: AdvertisingEventProperties = AdvertisingEventProperties(0);
+    /// Legacy `ADV_IND` (connectable and scannable undirected).
+    pub const LEGACY_ADV_IND: AdvertisingEventProperties = AdvertisingEventProperties(0x0013);
+    /// Legacy `ADV_DIRECT_IND` (low duty cycle).
+    pub const LEGACY_ADV_DIRECT_IND_LOW_DUTY_CYCLE: AdvertisingEventProperties =
+        AdvertisingEventProperties(0x0015);
+    /// Legacy `ADV_DIRECT_IND` (high duty cycle).
+    pub const LEGACY_ADV_DIRECT_IND_HIGH_DUTY_CYCLE: AdvertisingEventProperties =
+        AdvertisingEventProperties(0x001D);
+    /// Legacy `ADV_SCAN_IND` (scannable undirected).
+    pub const LEGACY_ADV_SCAN_IND: AdvertisingEventProperties = AdvertisingEventProperties(0x0012);
+    /// Legacy `ADV_NONCONN_IND` (non-connectable and non-scannable undirected).
+    pub const LEGACY_ADV_NONCONN_IND: AdvertisingEventProperties =
+        AdvertisingEventProperties(0x0010);
+    pub const DEFAULT: AdvertisingEventProperties = Self::LEGACY_ADV_IND;
+    pub fn enable_flag(&mut self, flag: AdvertisingEventPropertyFlags) {
+        self.0 |= 1 << u16::from(flag);
+    }
+    pub fn disable_flag(&mut self, flag: AdvertisingEventPropertyFlags) {
+        self.0 &= !(1 << u16::from(flag));
+    }
+    pub fn get_flag(self, flag: AdvertisingEventPropertyFlags) -> bool {
+        self.0 & (1 << u16::from(flag)) != 0
+    }
+    pub fn is_legacy(self) -> bool {
+        self.get_flag(AdvertisingEventPropertyFlags::Legacy)
+    }
+}
+impl Default for AdvertisingEventProperties {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+/// Extended advertising interval (in units of 0.625 ms). Range 0x000020 to 0xFFFFFF (20 ms to
+/// 10,485.759375 s).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ExtendedAdvertisingInterval(u32);
+impl ExtendedAdvertisingInterval {
+    pub const BYTE_LEN: usize = 3;
+    pub const MIN_U32: u32 = 0x0000_0020;
+    pub const MIN: ExtendedAdvertisingInterval = ExtendedAdvertisingInterval(Self::MIN_U32);
+    pub const MAX_U32: u32 = 0x00FF_FFFF;
+    pub const MAX: ExtendedAdvertisingInterval = ExtendedAdvertisingInterval(Self::MAX_U32);
+    pub const DEFAULT: ExtendedAdvertisingInterval =
+        ExtendedAdvertisingInterval(AdvertisingInterval::DEFAULT_U16 as u32);
+    /// Creates a new `ExtendedAdvertisingInterval`.
+    /// # Panics
+    /// Panics if `interval < ExtendedAdvertisingInterval::MIN_U32 ||
+    /// interval > ExtendedAdvertisingInterval::MAX_U32`.
+    pub fn new(interval: u32) -> ExtendedAdvertisingInterval {
+        assert!(
+            interval >= Self::MIN_U32 && interval <= Self::MAX_U32,
+            "invalid extended advertising interval '{}'",
+            interval
+        );
+        ExtendedAdvertisingInterval(interval)
+    }
+    pub fn as_microseconds(self) -> u64 {
+        u64::from(self.0) * 625
+    }
+    pub fn pack_into(self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf.copy_from_slice(&self.0.to_le_bytes()[..Self::BYTE_LEN]);
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Self::try_from(u32::from_le_bytes([buf[0], buf[1], buf[2], 0]))
+            .map_err(|_| PackError::bad_index(0))
+    }
+}
+impl Default for ExtendedAdvertisingInterval {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+impl From<AdvertisingInterval> for ExtendedAdvertisingInterval {
+    fn from(interval: AdvertisingInterval) -> Self {
+        ExtendedAdvertisingInterval(u32::from(u16::from(interval)))
+    }
+}
+impl From<ExtendedAdvertisingInterval> for u32 {
+    fn from(interval: ExtendedAdvertisingInterval) -> Self {
+        interval.0
+    }
+}
+impl TryFrom<u32> for ExtendedAdvertisingInterval {
+    type Error = ConversionError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        if value >= Self::MIN_U32 && value <= Self::MAX_U32 {
+            Ok(ExtendedAdvertisingInterval(value))
+        } else {
+            Err(ConversionError(()))
+        }
+    }
+}
+/// Parameters of an advertising set. See [`SetExtendedAdvertisingParameters`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ExtendedAdvertisingParameters {
+    pub properties: AdvertisingEventProperties,
+    pub interval_min: ExtendedAdvertisingInterval,
+    pub interval_max: ExtendedAdvertisingInterval,
+    pub channel_map: ChannelMap,
+    pub own_address_type: OwnAddressType,
+    pub peer_address_type: PeerAddressType,
+    pub peer_address: BTAddress,
+    pub filter_policy: FilterPolicy,
+    /// Max TX power the controller should use. `None` lets the controller choose.
+    pub tx_power: Option<TxPowerLevel>,
+    /// PHY of the primary advertising channels. Must be `Phy::LE1M` or `Phy::LECoded`.
+    pub primary_phy: Phy,
+    /// Max advertising events on the primary channels that can be skipped before sending an
+    /// `AUX_ADV_IND`.
+    pub secondary_max_skip: u8,
+    pub secondary_phy: Phy,
+    /// Advertising SID (0x00-0x0F) used in the `ADI` field.
+    pub sid: u8,
+    pub scan_request_notification_enable: bool,
+}
+impl ExtendedAdvertisingParameters {
+    /// properties (2) + interval_min (3) + interval_max (3) + channel_map (1) +
+    /// own_address_type (1) + peer_address_type (1) + peer_address (6) + filter_policy (1) +
+    /// tx_power (1) + primary_phy (1) + secondary_max_skip (1) + secondary_phy (1) + sid (1) +
+    /// scan_request_notification_enable (1)
+    pub const BYTE_LEN: usize = AdvertisingEventProperties::BYTE_LEN
+        + ExtendedAdvertisingInterval::BYTE_LEN * 2
+        + 1
+        + 1
+        + 1
+        + BT_ADDRESS_LEN
+        + 1
+        + 1
+        + 1
+        + 1
+        + 1
+        + 1
+        + 1;
+    pub const MAX_SID: u8 = 0x0F;
+    /// `tx_power` value meaning 'no preference'.
+    pub const TX_POWER_NO_PREFERENCE: u8 = 0x7F;
+    pub const DEFAULT: ExtendedAdvertisingParameters = ExtendedAdvertisingParameters {
+        properties: AdvertisingEventProperties::DEFAULT,
+        interval_min: ExtendedAdvertisingInterval::DEFAULT,
+        interval_max: ExtendedAdvertisingInterval::DEFAULT,
+        channel_map: ChannelMap::DEFAULT,
+        own_address_type: OwnAddressType::DEFAULT,
+        peer_address_type: PeerAddressType::DEFAULT,
+        peer_address: BTAddress::ZEROED,
+        filter_policy: FilterPolicy::DEFAULT,
+        tx_power: None,
+        primary_phy: Phy::LE1M,
+        secondary_max_skip: 0,
+        secondary_phy: Phy::LE1M,
+        sid: 0,
+        scan_request_notification_enable: false,
+    };
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0..2].copy_from_slice(&self.properties.0.to_bytes_le());
+        self.interval_min.pack_into(&mut buf[2..5])?;
+        self.interval_max.pack_into(&mut buf[5..8])?;
+        buf[8] = self.channel_map.into();
+        buf[9] = self.own_address_type.into();
+        buf[10] = self.peer_address_type.into();
+        self.peer_address
+            .pack_into(&mut buf[11..11 + BT_ADDRESS_LEN])?;
+        let buf = &mut buf[11 + BT_ADDRESS_LEN..];
+        buf[0] = self.filter_policy.into();
+        buf[1] = self.tx_power.map_or(Self::TX_POWER_NO_PREFERENCE, u8::from);
+        buf[2] = self.primary_phy.into();
+        buf[3] = self.secondary_max_skip;
+        buf[4] = self.secondary_phy.into();
+        buf[5] = self.sid;
+        buf[6] = self.scan_request_notification_enable.into();
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let rest = &buf[11 + BT_ADDRESS_LEN..];
+        let rest_index = |i: usize| PackError::bad_index(11 + BT_ADDRESS_LEN + i);
+        Ok(ExtendedAdvertisingParameters {
+            properties: AdvertisingEventProperties(u16::from_le_bytes([buf[0], buf[1]])),
+            interval_min: ExtendedAdvertisingInterval::unpack_from(&buf[2..5])?,
+            interval_max: ExtendedAdvertisingInterval::unpack_from(&buf[5..8])?,
+            channel_map: ChannelMap::try_from(buf[8]).map_err(|_| PackError::bad_index(8))?,
+            own_address_type: OwnAddressType::try_from(buf[9])
+                .map_err(|_| PackError::bad_index(9))?,
+            peer_address_type: PeerAddressType::try_from(buf[10])
+                .map_err(|_| PackError::bad_index(10))?,
+            peer_address: BTAddress::unpack_from(&buf[11..11 + BT_ADDRESS_LEN])?,
+            filter_policy: FilterPolicy::try_from(rest[0]).map_err(|_| rest_index(0))?,
+            tx_power: if rest[1] == Self::TX_POWER_NO_PREFERENCE {
+                None
+            } else {
+                Some(TxPowerLevel::try_from(rest[1]).map_err(|_| rest_index(1))?)
+            },
+            primary_phy: Phy::try_from(rest[2]).map_err(|_| rest_index(2))?,
+            secondary_max_skip: rest[3],
+            secondary_phy: Phy::try_from(rest[4]).map_err(|_| rest_index(4))?,
+            sid: if rest[5] <= Self::MAX_SID {
+                rest[5]
+            } else {
+                return Err(rest_index(5));
+            },
+            scan_request_notification_enable: match rest[6] {
+                0 => false,
+                1 => true,
+                _ => return Err(rest_index(6)),
+            },
+        })
+    }
+}
+impl Default for ExtendedAdvertisingParameters {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+/// LE Set Extended Advertising Parameters Command. Creates the advertising set `handle` (or
+/// changes its parameters while it's disabled). Returns the TX power selected by the
+/// controller.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SetExtendedAdvertisingParameters {
+    pub handle: AdvertisingHandle,
+    pub parameters: ExtendedAdvertisingParameters,
+}
+impl SetExtendedAdvertisingParameters {
+    pub const BYTE_LEN: usize =
+        AdvertisingHandle::BYTE_LEN + ExtendedAdvertisingParameters::BYTE_LEN;
+}
+impl Command for SetExtendedAdvertisingParameters {
+    type Return = CommandComplete<TxPowerLevelReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetExtendedAdvertisingParameters.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.handle.into();
+        self.parameters.pack_into(&mut buf[1..])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(SetExtendedAdvertisingParameters {
+            handle: AdvertisingHandle::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            parameters: ExtendedAdvertisingParameters::unpack_from(&buf[1..])?,
+        })
+    }
+}
+/// LE Set Advertising Set Random Address Command. Sets the random address used by advertising
+/// set `handle` (when its `own_address_type` uses a random address).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SetAdvertisingSetRandomAddress {
+    pub handle: AdvertisingHandle,
+    pub address: BTAddress,
+}
+impl SetAdvertisingSetRandomAddress {
+    pub const BYTE_LEN: usize = AdvertisingHandle::BYTE_LEN + BT_ADDRESS_LEN;
+}
+impl Command for SetAdvertisingSetRandomAddress {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetAdvertisingSetRandomAddress.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.handle.into();
+        self.address.pack_into(&mut buf[1..])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(SetAdvertisingSetRandomAddress {
+            handle: AdvertisingHandle::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            address: BTAddress::unpack_from(&buf[1..])?,
+        })
+    }
+}
+/// Which part of the advertising data an [`AdvertisingDataFragment`] is.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum FragmentOperation {
+    Intermediate = 0x00,
+    First = 0x01,
+    Last = 0x02,
+    /// The fragment is all of the data.
+    Complete = 0x03,
+    /// Keep the current data but change the Advertising DID.
+    Unchanged = 0x04,
+}
+impl From<FragmentOperation> for u8 {
+    fn from(o: FragmentOperation) -> Self {
+        o as u8
+    }
+}
+impl TryFrom<u8> for FragmentOperation {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(FragmentOperation::Intermediate),
+            0x01 => Ok(FragmentOperation::First),
+            0x02 => Ok(FragmentOperation::Last),
+            0x03 => Ok(FragmentOperation::Complete),
+            0x04 => Ok(FragmentOperation::Unchanged),
+            _ => Err(ConversionError(())),
+        }
+    }
+}
+/// If the controller may fragment the data over multiple PDUs when advertising.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum FragmentPreference {
+    MayFragment = 0x00,
+    /// The controller should not fragment (or should minimize fragmenting) the data.
+    ShouldNotFragment = 0x01,
+}
+impl FragmentPreference {
+    pub const DEFAULT: FragmentPreference = FragmentPreference::MayFragment;
+}
+impl Default for FragmentPreference {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+impl From<FragmentPreference> for u8 {
+    fn from(p: FragmentPreference) -> Self {
+        p as u8
+    }
+}
+impl TryFrom<u8> for FragmentPreference {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(FragmentPreference::MayFragment),
+            0x01 => Ok(FragmentPreference::ShouldNotFragment),
+            _ => Err(ConversionError(())),
+        }
+    }
+}
+/// Up to [`MAX_ADVERTISING_DATA_FRAGMENT_LEN`] bytes of advertising or scan response data of an
+/// advertising set. Use [`AdvertisingDataFragment::fragments`] to split longer data.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct AdvertisingDataFragment {
+    pub handle: AdvertisingHandle,
+    pub operation: FragmentOperation,
+    pub preference: FragmentPreference,
+    data: [u8; MAX_ADVERTISING_DATA_FRAGMENT_LEN],
+    len: u8,
+}
+impl AdvertisingDataFragment {
+    /// handle (1) + operation (1) + preference (1) + data length (1)
+    pub const HEADER_LEN: usize = 4;
+    /// Creates a new `AdvertisingDataFragment`.
+    /// # Panics
+    /// Panics if `data.len() > MAX_ADVERTISING_DATA_FRAGMENT_LEN` (251).
+    pub fn new(
+        handle: AdvertisingHandle,
+        operation: FragmentOperation,
+        preference: FragmentPreference,
+        data: &[u8],
+    ) -> AdvertisingDataFragment {
+        assert!(
+            data.len() <= MAX_ADVERTISING_DATA_FRAGMENT_LEN,
+            "advertising data fragment too long"
+        );
+        let mut buf = [0_u8; MAX_ADVERTISING_DATA_FRAGMENT_LEN];
+        buf[..data.len()].copy_from_slice(data);
+        AdvertisingDataFragment {
+            handle,
+            operation,
+            preference,
+            data: buf,
+            len: u8::try_from(data.len()).unwrap_or(u8::MAX),
+        }
+    }
+    /// Splits `data` into the fragments needed to set it as the data of advertising set
+    /// `handle`. Empty `data` gives one empty `FragmentOperation::Complete` fragment (clearing
+    /// the data).
+    /// # Panics
+    /// Panics if `data.len() > MAX_EXTENDED_ADVERTISING_DATA_LEN` (1650).
+    /// ```
+    /// use btle::hci::le::extended_advertise::{
+    ///     AdvertisingDataFragment, AdvertisingHandle, FragmentOperation, FragmentPreference,
+    /// };
+    /// let data = [0xAA_u8; 300];
+    /// let fragments: Vec<_> = AdvertisingDataFragment::fragments(
+    ///     AdvertisingHandle::new(1),
+    ///     FragmentPreference::MayFragment,
+    ///     &data[..],
+    /// )
+    /// .collect();
+    /// assert_eq!(fragments.len(), 2);
+    /// assert_eq!(fragments[0].operation, FragmentOperation::First);
+    /// assert_eq!(fragments[0].data().len(), 251);
+    /// assert_eq!(fragments[1].operation, FragmentOperation::Last);
+    /// assert_eq!(fragments[1].data().len(), 49);
+    /// ```
+    pub fn fragments(
+        handle: AdvertisingHandle,
+        preference: FragmentPreference,
+        data: &[u8],
+    ) -> impl Iterator<Item = AdvertisingDataFragment> + '_ {
+        assert!(
+            data.len() <= MAX_EXTENDED_ADVERTISING_DATA_LEN,
+            "extended advertising data too long"
+        );
+        // `usize::div_ceil` needs Rust 1.73.
+        #[allow(clippy::manual_div_ceil)]
+        let count = core::cmp::max(
+            1,
+            (data.len() + MAX_ADVERTISING_DATA_FRAGMENT_LEN - 1)
+                / MAX_ADVERTISING_DATA_FRAGMENT_LEN,
+        );
+        (0..count).map(move |i| {
+            let start = i * MAX_ADVERTISING_DATA_FRAGMENT_LEN;
+            let end = core::cmp::min(start + MAX_ADVERTISING_DATA_FRAGMENT_LEN, data.len());
+            let operation = match (i == 0, i + 1 == count) {
+                (true, true) => FragmentOperation::Complete,
+                (true, false) => FragmentOperation::First,
+                (false, true) => FragmentOperation::Last,
+                (false, false) => FragmentOperation::Intermediate,
+            };
+            AdvertisingDataFragment::new(handle, operation, preference, &data[start..end])
+        })
+    }
+    pub fn data(&self) -> &[u8] {
+        &self.data[..usize::from(self.len)]
+    }
+    pub fn byte_len(&self) -> usize {
+        Self::HEADER_LEN + usize::from(self.len)
+    }
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(self.byte_len(), buf)?;
+        buf[0] = self.handle.into();
+        buf[1] = self.operation.into();
+        buf[2] = self.preference.into();
+        buf[3] = self.len;
+        buf[Self::HEADER_LEN..].copy_from_slice(self.data());
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        if buf.len() < Self::HEADER_LEN {
+            return Err(PackError::BadLength {
+                expected: Self::HEADER_LEN,
+                got: buf.len(),
+            });
+        }
+        let len = usize::from(buf[3]);
+        if len > MAX_ADVERTISING_DATA_FRAGMENT_LEN {
+            return Err(PackError::bad_index(3));
+        }
+        PackError::expect_length(Self::HEADER_LEN + len, buf)?;
+        Ok(AdvertisingDataFragment::new(
+            AdvertisingHandle::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            FragmentOperation::try_from(buf[1]).map_err(|_| PackError::bad_index(1))?,
+            FragmentPreference::try_from(buf[2]).map_err(|_| PackError::bad_index(2))?,
+            &buf[Self::HEADER_LEN..],
+        ))
+    }
+}
+/// LE Set Extended Advertising Data Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SetExtendedAdvertisingData(pub AdvertisingDataFragment);
+impl Command for SetExtendedAdvertisingData {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetExtendedAdvertisingData.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        self.0.byte_len()
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(SetExtendedAdvertisingData(
+            AdvertisingDataFragment::unpack_from(buf)?,
+        ))
+    }
+}
+/// LE Set Extended Scan Response Data Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SetExtendedScanResponseData(pub AdvertisingDataFragment);
+impl Command for SetExtendedScanResponseData {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetExtendedScanResponseData.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        self.0.byte_len()
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(SetExtendedScanResponseData(
+            AdvertisingDataFragment::unpack_from(buf)?,
+        ))
+    }
+}
+/// Enable parameters of one advertising set. See [`SetExtendedAdvertisingEnable`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct AdvertisingSetEnable {
+    pub handle: AdvertisingHandle,
+    /// How long to advertise for (in units of 10 ms). 0 advertises until disabled.
+    pub duration: u16,
+    /// Max number of extended advertising events to send. 0 means no limit.
+    pub max_extended_advertising_events: u8,
+}
+impl AdvertisingSetEnable {
+    pub const BYTE_LEN: usize = AdvertisingHandle::BYTE_LEN + 2 + 1;
+    /// Advertise with `handle` until disabled.
+    pub fn new(handle: AdvertisingHandle) -> AdvertisingSetEnable {
+        AdvertisingSetEnable {
+            handle,
+            duration: 0,
+            max_extended_advertising_events: 0,
+        }
+    }
+}
+/// LE Set Extended Advertising Enable Command. Enables or disables the advertising `sets`.
+/// Disabling with no `sets` disables every advertising set.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SetExtendedAdvertisingEnable {
+    pub is_enabled: bool,
+    pub sets: Vec<AdvertisingSetEnable>,
+}
+impl Command for SetExtendedAdvertisingEnable {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetExtendedAdvertisingEnable.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        2 + self.sets.len() * AdvertisingSetEnable::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(self.byte_len(), buf)?;
+        buf[0] = self.is_enabled.into();
+        buf[1] = u8::try_from(self.sets.len()).map_err(|_| PackError::InvalidFields)?;
+        for (set, buf) in self
+            .sets
+            .iter()
+            .zip(buf[2..].chunks_exact_mut(AdvertisingSetEnable::BYTE_LEN))
+        {
+            buf[0] = set.handle.into();
+            buf[1..3].copy_from_slice(&set.duration.to_bytes_le());
+            buf[3] = set.max_extended_advertising_events;
+        }
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        if buf.len() < 2 {
+            return Err(PackError::BadLength {
+                expected: 2,
+                got: buf.len(),
+            });
+        }
+        let is_enabled = match buf[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(PackError::bad_index(0)),
+        };
+        let count = usize::from(buf[1]);
+        PackError::expect_length(2 + count * AdvertisingSetEnable::BYTE_LEN, buf)?;
+        let sets = buf[2..]
+            .chunks_exact(AdvertisingSetEnable::BYTE_LEN)
+            .enumerate()
+            .map(|(i, set)| {
+                Ok(AdvertisingSetEnable {
+                    handle: AdvertisingHandle::try_from(set[0]).map_err(|_| {
+                        PackError::bad_index(2 + i * AdvertisingSetEnable::BYTE_LEN)
+                    })?,
+                    duration: u16::from_le_bytes([set[1], set[2]]),
+                    max_extended_advertising_events: set[3],
+                })
+            })
+            .collect::<Result<Vec<_>, PackError>>()?;
+        Ok(SetExtendedAdvertisingEnable { is_enabled, sets })
+    }
+}
+/// LE Remove Advertising Set Command. The set must be disabled first.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct RemoveAdvertisingSet {
+    pub handle: AdvertisingHandle,
+}
+impl Command for RemoveAdvertisingSet {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::RemoveAdvertisingSet.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        AdvertisingHandle::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(AdvertisingHandle::BYTE_LEN, buf)?;
+        buf[0] = self.handle.into();
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(AdvertisingHandle::BYTE_LEN, buf)?;
+        Ok(RemoveAdvertisingSet {
+            handle: AdvertisingHandle::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+        })
+    }
+}
+/// LE Clear Advertising Sets Command. Removes every advertising set.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ClearAdvertisingSets {}
+impl Command for ClearAdvertisingSets {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ClearAdvertisingSets.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ClearAdvertisingSets {})
+    }
+}
+/// LE Read Maximum Advertising Data Length Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadMaximumAdvertisingDataLength {}
+impl Command for ReadMaximumAdvertisingDataLength {
+    type Return = CommandComplete<MaximumAdvertisingDataLengthReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ReadMaximumAdvertisingDataLength.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadMaximumAdvertisingDataLength {})
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct MaximumAdvertisingDataLengthReturn {
+    pub status: ErrorCode,
+    /// Max length of the advertising (or scan response) data of an advertising set.
+    pub max_len: u16,
+}
+impl MaximumAdvertisingDataLengthReturn {
+    pub const BYTE_LEN: usize = 3;
+}
+impl ReturnParameters for MaximumAdvertisingDataLengthReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        buf[1..3].copy_from_slice(&self.max_len.to_bytes_le());
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(MaximumAdvertisingDataLengthReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            max_len: u16::from_le_bytes([buf[1], buf[2]]),
+        })
+    }
+}
+/// LE Read Number of Supported Advertising Sets Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadNumberOfSupportedAdvertisingSets {}
+impl Command for ReadNumberOfSupportedAdvertisingSets {
+    type Return = CommandComplete<NumberOfSupportedAdvertisingSetsReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ReadNumberOfSupportedAdvertisingSets.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadNumberOfSupportedAdvertisingSets {})
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct NumberOfSupportedAdvertisingSetsReturn {
+    pub status: ErrorCode,
+    pub num_sets: u8,
+}
+impl NumberOfSupportedAdvertisingSetsReturn {
+    pub const BYTE_LEN: usize = 2;
+}
+impl ReturnParameters for NumberOfSupportedAdvertisingSetsReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        buf[1] = self.num_sets;
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(NumberOfSupportedAdvertisingSetsReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            num_sets: buf[1],
+        })
+    }
+}
+/// LE Advertising Set Terminated Event. Sent when an advertising set stops advertising because
+/// a connection was created, its duration ran out or it sent its max number of extended
+/// advertising events.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct AdvertisingSetTerminated {
+    /// `ErrorCode::Ok` if a connection was created, `ErrorCode::AdvertisingTimeout` or
+    /// `ErrorCode::LimitReached` if the duration or max events ran out.
+    pub status: ErrorCode,
+    pub handle: AdvertisingHandle,
+    /// Connection created by the advertising set. Only valid if `status` is `ErrorCode::Ok`.
+    pub connection_handle: Option<ConnectionHandle>,
+    pub num_completed_extended_advertising_events: u8,
+}
+impl AdvertisingSetTerminated {
+    pub const BYTE_LEN: usize = 1 + AdvertisingHandle::BYTE_LEN + ConnectionHandle::BYTE_LEN + 1;
+}
+impl MetaEvent for AdvertisingSetTerminated {
+    const META_CODE: MetaEventCode = MetaEventCode::AdvertisingSetTerminated;
+
+    fn meta_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn meta_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let status = ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?;
+        Ok(AdvertisingSetTerminated {
+            status,
+            handle: AdvertisingHandle::try_from(buf[1]).map_err(|_| PackError::bad_index(1))?,
+            connection_handle: if status == ErrorCode::Ok {
+                Some(ConnectionHandle::unpack_from(&buf[2..4])?)
+            } else {
+                None
+            },
+            num_completed_extended_advertising_events: buf[4],
+        })
+    }
+
+    fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        buf[1] = self.handle.into();
+        match self.connection_handle {
+            Some(handle) => handle.pack_into(&mut buf[2..4])?,
+            None => buf[2..4].copy_from_slice(&[0, 0]),
+        }
+        buf[4] = self.num_completed_extended_advertising_events;
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{
+        AdvertisingDataFragment, AdvertisingEventProperties, AdvertisingHandle,
+        AdvertisingSetEnable, AdvertisingSetTerminated, ExtendedAdvertisingInterval,
+        ExtendedAdvertisingParameters, FragmentOperation, FragmentPreference,
+        SetExtendedAdvertisingEnable, MAX_ADVERTISING_DATA_FRAGMENT_LEN,
+        MAX_EXTENDED_ADVERTISING_DATA_LEN,
+    };
+    use crate::hci::command::Command;
+    use crate::hci::le::advertise::TxPowerLevel;
+    use crate::hci::le::phy::Phy;
+    use crate::hci::le::MetaEvent;
+    use crate::hci::{ConnectionHandle, ErrorCode};
+    use crate::le::advertiser::{OwnAddressType, PeerAddressType};
+    use crate::{BTAddress, PackError};
+    use alloc::vec::Vec;
+    use core::convert::TryFrom;
+
+    fn parameters() -> ExtendedAdvertisingParameters {
+        ExtendedAdvertisingParameters {
+            properties: AdvertisingEventProperties(0x0001),
+            interval_min: ExtendedAdvertisingInterval::new(0x0000_0100),
+            interval_max: ExtendedAdvertisingInterval::new(0x0012_3456),
+            own_address_type: OwnAddressType::RandomDevice,
+            peer_address_type: PeerAddressType::Random,
+            peer_address: BTAddress([0x01, 0x02, 0x03, 0x04, 0x05, 0x06]),
+            tx_power: Some(TxPowerLevel::new(-4)),
+            primary_phy: Phy::LECoded,
+            secondary_max_skip: 2,
+            secondary_phy: Phy::LE2M,
+            sid: ExtendedAdvertisingParameters::MAX_SID,
+            scan_request_notification_enable: true,
+            ..ExtendedAdvertisingParameters::DEFAULT
+        }
+    }
+    #[test]
+    fn test_extended_advertising_parameters_pack_unpack() {
+        let parameters = parameters();
+        let mut buf = [0_u8; ExtendedAdvertisingParameters::BYTE_LEN];
+        parameters.pack_into(&mut buf).unwrap();
+        assert_eq!(&buf[2..8], &[0x00, 0x01, 0x00, 0x56, 0x34, 0x12]);
+        assert_eq!(
+            ExtendedAdvertisingParameters::unpack_from(&buf),
+            Ok(parameters)
+        );
+        assert!(ExtendedAdvertisingParameters::unpack_from(&buf[1..]).is_err());
+        // No TX power preference.
+        let parameters = ExtendedAdvertisingParameters::DEFAULT;
+        parameters.pack_into(&mut buf).unwrap();
+        assert_eq!(
+            buf[18],
+            ExtendedAdvertisingParameters::TX_POWER_NO_PREFERENCE
+        );
+        assert_eq!(
+            ExtendedAdvertisingParameters::unpack_from(&buf),
+            Ok(parameters)
+        );
+    }
+    #[test]
+    fn test_extended_advertising_parameters_bad_fields() {
+        let mut buf = [0_u8; ExtendedAdvertisingParameters::BYTE_LEN];
+        parameters().pack_into(&mut buf).unwrap();
+        let check = |index: usize, value: u8| {
+            let mut bad = buf;
+            bad[index] = value;
+            assert_eq!(
+                ExtendedAdvertisingParameters::unpack_from(&bad),
+                Err(PackError::bad_index(index))
+            );
+        };
+        // Primary PHY, SID > 0x0F and scan request notification enable.
+        check(19, 0x00);
+        check(22, ExtendedAdvertisingParameters::MAX_SID + 1);
+        check(23, 0x02);
+        // Interval below `ExtendedAdvertisingInterval::MIN_U32`.
+        let mut bad = buf;
+        bad[2..5].copy_from_slice(&[0x1F, 0x00, 0x00]);
+        assert!(ExtendedAdvertisingParameters::unpack_from(&bad).is_err());
+        assert!(ExtendedAdvertisingInterval::try_from(0x0100_0000).is_err());
+        assert!(AdvertisingHandle::try_from(AdvertisingHandle::MAX_HANDLE_U8 + 1).is_err());
+    }
+    #[test]
+    fn test_advertising_data_fragments() {
+        let handle = AdvertisingHandle::new(1);
+        let preference = FragmentPreference::ShouldNotFragment;
+        let operations = |len: usize| {
+            let data = alloc::vec![0xAA_u8; len];
+            AdvertisingDataFragment::fragments(handle, preference, &data)
+                .map(|fragment| (fragment.operation, fragment.data().len()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(operations(0), [(FragmentOperation::Complete, 0)]);
+        assert_eq!(
+            operations(MAX_ADVERTISING_DATA_FRAGMENT_LEN),
+            [(
+                FragmentOperation::Complete,
+                MAX_ADVERTISING_DATA_FRAGMENT_LEN
+            )]
+        );
+        assert_eq!(
+            operations(MAX_ADVERTISING_DATA_FRAGMENT_LEN + 1),
+            [
+                (FragmentOperation::First, MAX_ADVERTISING_DATA_FRAGMENT_LEN),
+                (FragmentOperation::Last, 1)
+            ]
+        );
+        let fragments = operations(MAX_EXTENDED_ADVERTISING_DATA_LEN);
+        assert_eq!(fragments.len(), 7);
+        assert_eq!(fragments[1].0, FragmentOperation::Intermediate);
+        assert_eq!(fragments.iter().map(|f| f.1).sum::<usize>(), 1650);
+    }
+    #[test]
+    fn test_advertising_data_fragment_pack_unpack() {
+        let fragment = AdvertisingDataFragment::new(
+            AdvertisingHandle::new(2),
+            FragmentOperation::Last,
+            FragmentPreference::MayFragment,
+            &[0x02, 0x01, 0x06],
+        );
+        let mut buf = [0_u8; AdvertisingDataFragment::HEADER_LEN + 3];
+        fragment.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x02, 0x02, 0x00, 0x03, 0x02, 0x01, 0x06]);
+        assert_eq!(AdvertisingDataFragment::unpack_from(&buf), Ok(fragment));
+        // The data length doesn't match the buffer.
+        assert!(AdvertisingDataFragment::unpack_from(&buf[..6]).is_err());
+        assert!(AdvertisingDataFragment::unpack_from(&buf[..3]).is_err());
+        let mut long = [0_u8; AdvertisingDataFragment::HEADER_LEN + 252];
+        long[3] = 252;
+        assert_eq!(
+            AdvertisingDataFragment::unpack_from(&long),
+            Err(PackError::bad_index(3))
+        );
+    }
+    #[test]
+    fn test_set_extended_advertising_enable_pack_unpack() {
+        let enable = SetExtendedAdvertisingEnable {
+            is_enabled: true,
+            sets: alloc::vec![
+                AdvertisingSetEnable::new(AdvertisingHandle::new(0)),
+                AdvertisingSetEnable {
+                    handle: AdvertisingHandle::new(1),
+                    duration: 0x0102,
+                    max_extended_advertising_events: 3,
+                },
+            ],
+        };
+        let mut buf = alloc::vec![0_u8; enable.byte_len()];
+        enable.pack_into(&mut buf).unwrap();
+        assert_eq!(
+            buf,
+            [0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02, 0x01, 0x03]
+        );
+        assert_eq!(SetExtendedAdvertisingEnable::unpack_from(&buf), Ok(enable));
+        // Set count doesn't match the buffer.
+        assert!(SetExtendedAdvertisingEnable::unpack_from(&buf[..9]).is_err());
+        assert!(SetExtendedAdvertisingEnable::unpack_from(&[0x02, 0x00]).is_err());
+        assert!(SetExtendedAdvertisingEnable::unpack_from(&[0x00]).is_err());
+    }
+    #[test]
+    fn test_advertising_set_terminated() {
+        let terminated = AdvertisingSetTerminated {
+            status: ErrorCode::Ok,
+            handle: AdvertisingHandle::new(3),
+            connection_handle: Some(ConnectionHandle::new(0x0040)),
+            num_completed_extended_advertising_events: 5,
+        };
+        let mut buf = [0_u8; AdvertisingSetTerminated::BYTE_LEN];
+        terminated.meta_pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x00, 0x03, 0x40, 0x00, 0x05]);
+        assert_eq!(
+            AdvertisingSetTerminated::meta_unpack_from(&buf),
+            Ok(terminated)
+        );
+        // The connection handle is ignored if no connection was created.
+        buf[0] = ErrorCode::AdvertisingTimeout.into();
+        let terminated = AdvertisingSetTerminated::meta_unpack_from(&buf).unwrap();
+        assert_eq!(terminated.connection_handle, None);
+        assert!(AdvertisingSetTerminated::meta_unpack_from(&buf[..4]).is_err());
+        buf[1] = 0xF0;
+        assert_eq!(
+            AdvertisingSetTerminated::meta_unpack_from(&buf),
+            Err(PackError::bad_index(1))
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! LE Extended Scanning commands ([`SetExtendedScanParameters`], [`SetExtendedScanEnable`]) and
+//! the [`ScanTimeout`] event. Extended scanning receives advertisements on the Coded PHY and
+//! extended advertisements (reported with
+//! [`ExtendedAdvertisingReport`](crate::hci::le::report::ExtendedAdvertisingReport)).
+use crate::bytes::ToFromBytesEndian;
+use crate::hci::command::Command;
+use crate::hci::event::{CommandComplete, StatusReturn};
+use crate::hci::le::{LEControllerOpcode, MetaEvent, MetaEventCode};
+use crate::hci::Opcode;
+use crate::le::scan::{OwnAddressType, ScanInterval, ScanType, ScanWindow, ScanningFilterPolicy};
+use crate::ConversionError;
+use crate::PackError;
+use core::convert::TryFrom;
+
+/// Scan parameters used on one PHY. See [`ExtendedScanParameters`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct ScanPhyParameters {
+    pub scan_type: ScanType,
+    pub scan_interval: ScanInterval,
+    pub scan_window: ScanWindow,
+}
+impl ScanPhyParameters {
+    pub const BYTE_LEN: usize = 1 + 2 + 2;
+    pub const DEFAULT: ScanPhyParameters = ScanPhyParameters {
+        scan_type: ScanType::Passive,
+        scan_interval: ScanInterval::DEFAULT,
+        scan_window: ScanWindow::DEFAULT,
+    };
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let window = u16::from(self.scan_window);
+        let interval = u16::from(self.scan_interval);
+        if window > interval {
+            // The scan window should always be less than or equal to the scan interval.
+            return Err(PackError::InvalidFields);
+        }
+        buf[0] = self.scan_type.into();
+        buf[1..3].copy_from_slice(&interval.to_bytes_le());
+        buf[3..5].copy_from_slice(&window.to_bytes_le());
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(ScanPhyParameters {
+            scan_type: ScanType::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            scan_interval: ScanInterval::try_from(u16::from_le_bytes([buf[1], buf[2]]))
+                .map_err(|_| PackError::bad_index(1))?,
+            scan_window: ScanWindow::try_from(u16::from_le_bytes([buf[3], buf[4]]))
+                .map_err(|_| PackError::bad_index(3))?,
+        })
+    }
+}
+impl Default for ScanPhyParameters {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+/// Extended scan parameters. Scanning happens on every PHY that has parameters (at least one
+/// is required).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct ExtendedScanParameters {
+    pub own_address_type: OwnAddressType,
+    pub scanning_filter_policy: ScanningFilterPolicy,
+    /// Scan parameters on the LE 1M PHY (`None` to not scan on it).
+    pub le_1m: Option<ScanPhyParameters>,
+    /// Scan parameters on the LE Coded PHY (`None` to not scan on it).
+    pub le_coded: Option<ScanPhyParameters>,
+}
+impl ExtendedScanParameters {
+    const LE_1M_BIT: u8 = 1 << 0;
+    const LE_CODED_BIT: u8 = 1 << 2;
+    pub const DEFAULT: ExtendedScanParameters = ExtendedScanParameters {
+        own_address_type: OwnAddressType::Public,
+        scanning_filter_policy: ScanningFilterPolicy::All,
+        le_1m: Some(ScanPhyParameters::DEFAULT),
+        le_coded: None,
+    };
+    fn phys(&self) -> impl Iterator<Item = &ScanPhyParameters> {
+        self.le_1m.iter().chain(self.le_coded.iter())
+    }
+    pub fn byte_len(&self) -> usize {
+        3 + self.phys().count() * ScanPhyParameters::BYTE_LEN
+    }
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(self.byte_len(), buf)?;
+        if self.le_1m.is_none() && self.le_coded.is_none() {
+            return Err(PackError::InvalidFields);
+        }
+        buf[0] = self.own_address_type.into();
+        buf[1] = self.scanning_filter_policy.into();
+        buf[2] = self.le_1m.map_or(0, |_| Self::LE_1M_BIT)
+            | self.le_coded.map_or(0, |_| Self::LE_CODED_BIT);
+        for (parameters, buf) in self
+            .phys()
+            .zip(buf[3..].chunks_exact_mut(ScanPhyParameters::BYTE_LEN))
+        {
+            parameters.pack_into(buf)?;
+        }
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        if buf.len() < 3 {
+            return Err(PackError::BadLength {
+                expected: 3,
+                got: buf.len(),
+            });
+        }
+        let phys = buf[2];
+        if phys & !(Self::LE_1M_BIT | Self::LE_CODED_BIT) != 0 {
+            return Err(PackError::bad_index(2));
+        }
+        let mut chunks = buf[3..].chunks(ScanPhyParameters::BYTE_LEN);
+        let mut next_phy = |bit: u8| -> Result<Option<ScanPhyParameters>, PackError> {
+            if phys & bit == 0 {
+                return Ok(None);
+            }
+            let chunk = chunks.next().ok_or(PackError::BadLength {
+                expected: ScanPhyParameters::BYTE_LEN,
+                got: 0,
+            })?;
+            ScanPhyParameters::unpack_from(chunk).map(Some)
+        };
+        let out = ExtendedScanParameters {
+            own_address_type: OwnAddressType::try_from(buf[0])
+                .map_err(|_| PackError::bad_index(0))?,
+            scanning_filter_policy: ScanningFilterPolicy::try_from(buf[1])
+                .map_err(|_| PackError::bad_index(1))?,
+            le_1m: next_phy(Self::LE_1M_BIT)?,
+            le_coded: next_phy(Self::LE_CODED_BIT)?,
+        };
+        PackError::expect_length(out.byte_len(), buf)?;
+        Ok(out)
+    }
+}
+impl Default for ExtendedScanParameters {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+/// LE Set Extended Scan Parameters Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+pub struct SetExtendedScanParameters(pub ExtendedScanParameters);
+impl Command for SetExtendedScanParameters {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetExtendedScanParameters.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        self.0.byte_len()
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(SetExtendedScanParameters(
+            ExtendedScanParameters::unpack_from(buf)?,
+        ))
+    }
+}
+/// Duplicate advertisement filtering done by the controller while extended scanning.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub enum FilterDuplicates {
+    Disabled = 0x00,
+    Enabled = 0x01,
+    /// Filter duplicates but forget the seen advertisements every scan period.
+    ResetEachPeriod = 0x02,
+}
+impl From<FilterDuplicates> for u8 {
+    fn from(f: FilterDuplicates) -> Self {
+        f as u8
+    }
+}
+impl TryFrom<u8> for FilterDuplicates {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(FilterDuplicates::Disabled),
+            0x01 => Ok(FilterDuplicates::Enabled),
+            0x02 => Ok(FilterDuplicates::ResetEachPeriod),
+            _ => Err(ConversionError(())),
+        }
+    }
+}
+/// LE Set Extended Scan Enable Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct SetExtendedScanEnable {
+    pub is_enabled: bool,
+    pub filter_duplicates: FilterDuplicates,
+    /// How long to scan for (in units of 10 ms). 0 scans until disabled. The controller sends
+    /// [`ScanTimeout`] once the duration is over (and `period` is 0).
+    pub duration: u16,
+    /// Time from the start of one scan `duration` to the next (in units of 1.28 s). 0 scans only
+    /// once.
+    pub period: u16,
+}
+impl SetExtendedScanEnable {
+    pub const BYTE_LEN: usize = 1 + 1 + 2 + 2;
+}
+impl Command for SetExtendedScanEnable {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetExtendedScanEnable.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.is_enabled.into();
+        buf[1] = self.filter_duplicates.into();
+        buf[2..4].copy_from_slice(&self.duration.to_bytes_le());
+        buf[4..6].copy_from_slice(&self.period.to_bytes_le());
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let is_enabled = match buf[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(PackError::bad_index(0)),
+        };
+        Ok(SetExtendedScanEnable {
+            is_enabled,
+            filter_duplicates: FilterDuplicates::try_from(buf[1])
+                .map_err(|_| PackError::bad_index(1))?,
+            duration: u16::from_le_bytes([buf[2], buf[3]]),
+            period: u16::from_le_bytes([buf[4], buf[5]]),
+        })
+    }
+}
+/// LE Scan Timeout Event. Sent when the scan `duration` of [`SetExtendedScanEnable`] is over.
+/// Scanning is then disabled.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+pub struct ScanTimeout {}
+impl MetaEvent for ScanTimeout {
+    const META_CODE: MetaEventCode = MetaEventCode::ScanTimeout;
+
+    fn meta_byte_len(&self) -> usize {
+        0
+    }
+
+    fn meta_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ScanTimeout {})
+    }
+
+    fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+}
@@ -0,0 +1,488 @@
+//! LE controller information commands ([`ReadBufferSize`], [`ReadLocalSupportedFeatures`],
+//! [`ReadSupportedStates`]).
+use crate::hci::command::Command;
+use crate::hci::event::{CommandComplete, ReturnParameters};
+use crate::hci::le::LEControllerOpcode;
+use crate::hci::{ErrorCode, Opcode};
+use crate::PackError;
+use core::convert::TryFrom;
+
+/// LE Read Buffer Size Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadBufferSize {}
+impl Command for ReadBufferSize {
+    type Return = CommandComplete<LEBufferSizeReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ReadBufferSize.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadBufferSize {})
+    }
+}
+/// Size and count of the controller's LE ACL data buffers. If both are 0, the controller
+/// shares its BR/EDR ACL buffers with LE.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct LEBufferSize {
+    /// Max length (in bytes) of the data portion of each LE ACL data packet.
+    pub le_acl_data_packet_length: u16,
+    pub total_num_le_acl_data_packets: u8,
+}
+impl LEBufferSize {
+    pub const BYTE_LEN: usize = 3;
+    pub fn is_shared_with_br_edr(self) -> bool {
+        self.le_acl_data_packet_length == 0 && self.total_num_le_acl_data_packets == 0
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct LEBufferSizeReturn {
+    pub status: ErrorCode,
+    pub buffer_size: LEBufferSize,
+}
+impl LEBufferSizeReturn {
+    pub const BYTE_LEN: usize = 1 + LEBufferSize::BYTE_LEN;
+}
+impl ReturnParameters for LEBufferSizeReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        buf[1..3].copy_from_slice(&self.buffer_size.le_acl_data_packet_length.to_le_bytes());
+        buf[3] = self.buffer_size.total_num_le_acl_data_packets;
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(LEBufferSizeReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            buffer_size: LEBufferSize {
+                le_acl_data_packet_length: u16::from_le_bytes([buf[1], buf[2]]),
+                total_num_le_acl_data_packets: buf[3],
+            },
+        })
+    }
+}
+/// LE Read Local Supported Features Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadLocalSupportedFeatures {}
+impl Command for ReadLocalSupportedFeatures {
+    type Return = CommandComplete<LEFeaturesReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ReadLocalSupportedFeatures.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadLocalSupportedFeatures {})
+    }
+}
+/// Bit positions of the LE features in [`LEFeatures`] (Core Spec Vol 6, Part B, 4.6).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[repr(u8)]
+pub enum LEFeatureFlags {
+    LEEncryption = 0,
+    ConnectionParametersRequestProcedure = 1,
+    ExtendedRejectIndication = 2,
+    PeripheralInitiatedFeaturesExchange = 3,
+    LEPing = 4,
+    LEDataPacketLengthExtension = 5,
+    LLPrivacy = 6,
+    ExtendedScannerFilterPolicies = 7,
+    LE2MPHY = 8,
+    StableModulationIndexTransmitter = 9,
+    StableModulationIndexReceiver = 10,
+    LECodedPHY = 11,
+    LEExtendedAdvertising = 12,
+    LEPeriodicAdvertising = 13,
+    ChannelSelectionAlgorithm2 = 14,
+    LEPowerClass1 = 15,
+    MinimumNumberOfUsedChannelsProcedure = 16,
+    ConnectionCTERequest = 17,
+    ConnectionCTEResponse = 18,
+    ConnectionlessCTETransmitter = 19,
+    ConnectionlessCTEReceiver = 20,
+    AntennaSwitchingDuringCTETransmission = 21,
+    AntennaSwitchingDuringCTEReception = 22,
+    ReceivingConstantToneExtensions = 23,
+    PeriodicAdvertisingSyncTransferSender = 24,
+    PeriodicAdvertisingSyncTransferRecipient = 25,
+    SleepClockAccuracyUpdates = 26,
+    RemotePublicKeyValidation = 27,
+    ConnectedIsochronousStreamCentral = 28,
+    ConnectedIsochronousStreamPeripheral = 29,
+    IsochronousBroadcaster = 30,
+    SynchronizedReceiver = 31,
+    ConnectedIsochronousStreamHostSupport = 32,
+    LEPowerControlRequest = 33,
+    LEPowerChangeIndication = 34,
+    LEPathLossMonitoring = 35,
+    PeriodicAdvertisingADISupport = 36,
+    ConnectionSubrating = 37,
+    ConnectionSubratingHostSupport = 38,
+    ChannelClassification = 39,
+}
+impl From<LEFeatureFlags> for u8 {
+    fn from(f: LEFeatureFlags) -> Self {
+        f as u8
+    }
+}
+impl From<LEFeatureFlags> for u64 {
+    fn from(f: LEFeatureFlags) -> Self {
+        f as u64
+    }
+}
+/// 64-bit LE feature mask of the controller. Bits are indexed by [`LEFeatureFlags`].
+/// ```
+/// use btle::hci::le::features::{LEFeatureFlags, LEFeatures};
+/// let mut features = LEFeatures::ZEROED;
+/// features.enable_feature(LEFeatureFlags::LE2MPHY);
+/// assert_eq!(features.0, 0x100);
+/// assert!(features.get_feature(LEFeatureFlags::LE2MPHY));
+/// assert!(!features.supports_extended_advertising());
+/// ```
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct LEFeatures(pub u64);
+impl LEFeatures {
+    pub const BYTE_LEN: usize = 8;
+    pub const ZEROED: LEFeatures = LEFeatures(0);
+    pub fn enable_feature(&mut self, flag: LEFeatureFlags) {
+        self.0 |= 1 << u64::from(flag);
+    }
+    pub fn disable_feature(&mut self, flag: LEFeatureFlags) {
+        self.0 &= !(1 << u64::from(flag));
+    }
+    pub fn get_feature(self, flag: LEFeatureFlags) -> bool {
+        self.0 & (1 << u64::from(flag)) != 0
+    }
+    pub fn supports_encryption(self) -> bool {
+        self.get_feature(LEFeatureFlags::LEEncryption)
+    }
+    /// Extended advertising (and extended scanning/initiating) commands.
+    pub fn supports_extended_advertising(self) -> bool {
+        self.get_feature(LEFeatureFlags::LEExtendedAdvertising)
+    }
+    pub fn supports_periodic_advertising(self) -> bool {
+        self.get_feature(LEFeatureFlags::LEPeriodicAdvertising)
+    }
+    pub fn supports_2m_phy(self) -> bool {
+        self.get_feature(LEFeatureFlags::LE2MPHY)
+    }
+    pub fn supports_coded_phy(self) -> bool {
+        self.get_feature(LEFeatureFlags::LECodedPHY)
+    }
+    pub fn supports_data_length_extension(self) -> bool {
+        self.get_feature(LEFeatureFlags::LEDataPacketLengthExtension)
+    }
+    pub fn supports_privacy(self) -> bool {
+        self.get_feature(LEFeatureFlags::LLPrivacy)
+    }
+    /// Any of the isochronous channel roles (connected or broadcast).
+    pub fn supports_iso(self) -> bool {
+        self.get_feature(LEFeatureFlags::ConnectedIsochronousStreamCentral)
+            || self.get_feature(LEFeatureFlags::ConnectedIsochronousStreamPeripheral)
+            || self.get_feature(LEFeatureFlags::IsochronousBroadcaster)
+            || self.get_feature(LEFeatureFlags::SynchronizedReceiver)
+    }
+    pub fn pack_into(self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf.copy_from_slice(&self.0.to_le_bytes());
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let mut features = [0_u8; Self::BYTE_LEN];
+        features.copy_from_slice(buf);
+        Ok(LEFeatures(u64::from_le_bytes(features)))
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct LEFeaturesReturn {
+    pub status: ErrorCode,
+    pub features: LEFeatures,
+}
+impl LEFeaturesReturn {
+    pub const BYTE_LEN: usize = 1 + LEFeatures::BYTE_LEN;
+}
+impl ReturnParameters for LEFeaturesReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.features.pack_into(&mut buf[1..])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(LEFeaturesReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            features: LEFeatures::unpack_from(&buf[1..])?,
+        })
+    }
+}
+/// LE Read Supported States Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadSupportedStates {}
+impl Command for ReadSupportedStates {
+    type Return = CommandComplete<SupportedStatesReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ReadSupportedState.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadSupportedStates {})
+    }
+}
+/// Link Layer state (or role) used in [`SupportedStates`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum LEState {
+    NonConnectableAdvertising,
+    ScannableAdvertising,
+    ConnectableAdvertising,
+    HighDutyCycleDirectedAdvertising,
+    LowDutyCycleDirectedAdvertising,
+    PassiveScanning,
+    ActiveScanning,
+    Initiating,
+    /// Connection State in the Central (Master) role.
+    Central,
+    /// Connection State in the Peripheral (Slave) role.
+    Peripheral,
+}
+/// States (or combination of two states) indexed by their bit in [`SupportedStates`]. The
+/// Central role is implied by the Initiating state (bit 6).
+const STATE_BITS: [(LEState, Option<LEState>); SupportedStates::STATE_COUNT] = {
+    use LEState::{
+        ActiveScanning, Central, ConnectableAdvertising, HighDutyCycleDirectedAdvertising,
+        Initiating, LowDutyCycleDirectedAdvertising, NonConnectableAdvertising, PassiveScanning,
+        Peripheral, ScannableAdvertising,
+    };
+    [
+        (NonConnectableAdvertising, None),
+        (ScannableAdvertising, None),
+        (ConnectableAdvertising, None),
+        (HighDutyCycleDirectedAdvertising, None),
+        (PassiveScanning, None),
+        (ActiveScanning, None),
+        (Initiating, None),
+        (Peripheral, None),
+        (NonConnectableAdvertising, Some(PassiveScanning)),
+        (ScannableAdvertising, Some(PassiveScanning)),
+        (ConnectableAdvertising, Some(PassiveScanning)),
+        (HighDutyCycleDirectedAdvertising, Some(PassiveScanning)),
+        (NonConnectableAdvertising, Some(ActiveScanning)),
+        (ScannableAdvertising, Some(ActiveScanning)),
+        (ConnectableAdvertising, Some(ActiveScanning)),
+        (HighDutyCycleDirectedAdvertising, Some(ActiveScanning)),
+        (NonConnectableAdvertising, Some(Initiating)),
+        (ScannableAdvertising, Some(Initiating)),
+        (NonConnectableAdvertising, Some(Central)),
+        (ScannableAdvertising, Some(Central)),
+        (NonConnectableAdvertising, Some(Peripheral)),
+        (ScannableAdvertising, Some(Peripheral)),
+        (PassiveScanning, Some(Initiating)),
+        (ActiveScanning, Some(Initiating)),
+        (PassiveScanning, Some(Central)),
+        (ActiveScanning, Some(Central)),
+        (PassiveScanning, Some(Peripheral)),
+        (ActiveScanning, Some(Peripheral)),
+        (Initiating, Some(Central)),
+        (LowDutyCycleDirectedAdvertising, None),
+        (LowDutyCycleDirectedAdvertising, Some(PassiveScanning)),
+        (LowDutyCycleDirectedAdvertising, Some(ActiveScanning)),
+        (ConnectableAdvertising, Some(Initiating)),
+        (HighDutyCycleDirectedAdvertising, Some(Initiating)),
+        (LowDutyCycleDirectedAdvertising, Some(Initiating)),
+        (ConnectableAdvertising, Some(Central)),
+        (HighDutyCycleDirectedAdvertising, Some(Central)),
+        (LowDutyCycleDirectedAdvertising, Some(Central)),
+        (ConnectableAdvertising, Some(Peripheral)),
+        (HighDutyCycleDirectedAdvertising, Some(Peripheral)),
+        (LowDutyCycleDirectedAdvertising, Some(Peripheral)),
+        (Initiating, Some(Peripheral)),
+    ]
+};
+/// 64-bit mask of the Link Layer states and state combinations supported by the controller.
+/// ```
+/// use btle::hci::le::features::{LEState, SupportedStates};
+/// let states = SupportedStates(0x0000_0000_0001_0001);
+/// assert!(states.supports(LEState::NonConnectableAdvertising));
+/// assert!(states.supports_combination(LEState::Initiating, LEState::NonConnectableAdvertising));
+/// assert!(!states.supports(LEState::Initiating));
+/// assert_eq!(states.iter().count(), 2);
+/// ```
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct SupportedStates(pub u64);
+impl SupportedStates {
+    pub const BYTE_LEN: usize = 8;
+    /// Number of defined state bits. The rest are reserved.
+    pub const STATE_COUNT: usize = 42;
+    fn bit_position(a: LEState, b: Option<LEState>) -> Option<usize> {
+        let key = match (a, b) {
+            // The Central role on its own is entered from the Initiating state.
+            (LEState::Central, None) => (LEState::Initiating, None),
+            key => key,
+        };
+        STATE_BITS
+            .iter()
+            .position(|&states| states == key || b.map_or(false, |b| states == (b, Some(a))))
+    }
+    fn get_bit(self, bit: usize) -> bool {
+        self.0 & (1 << bit) != 0
+    }
+    /// Returns if the controller supports `state` on its own.
+    pub fn supports(self, state: LEState) -> bool {
+        Self::bit_position(state, None).map_or(false, |bit| self.get_bit(bit))
+    }
+    /// Returns if the controller supports being in states `a` and `b` at the same time (in any
+    /// order).
+    pub fn supports_combination(self, a: LEState, b: LEState) -> bool {
+        Self::bit_position(a, Some(b)).map_or(false, |bit| self.get_bit(bit))
+    }
+    /// Iterates over every supported state (`(state, None)`) and state combination
+    /// (`(state, Some(other_state))`).
+    pub fn iter(self) -> impl Iterator<Item = (LEState, Option<LEState>)> {
+        STATE_BITS
+            .iter()
+            .enumerate()
+            .filter(move |&(bit, _)| self.get_bit(bit))
+            .map(|(_, &states)| states)
+    }
+    pub fn pack_into(self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf.copy_from_slice(&self.0.to_le_bytes());
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let mut states = [0_u8; Self::BYTE_LEN];
+        states.copy_from_slice(buf);
+        Ok(SupportedStates(u64::from_le_bytes(states)))
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SupportedStatesReturn {
+    pub status: ErrorCode,
+    pub states: SupportedStates,
+}
+impl SupportedStatesReturn {
+    pub const BYTE_LEN: usize = 1 + SupportedStates::BYTE_LEN;
+}
+impl ReturnParameters for SupportedStatesReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.states.pack_into(&mut buf[1..])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(SupportedStatesReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            states: SupportedStates::unpack_from(&buf[1..])?,
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{LEBufferSize, LEBufferSizeReturn, LEFeatures, LEFeaturesReturn};
+    use crate::hci::event::ReturnParameters;
+    use crate::hci::ErrorCode;
+    use crate::PackError;
+
+    #[test]
+    fn test_le_buffer_size_return() {
+        let ret = LEBufferSizeReturn {
+            status: ErrorCode::Ok,
+            buffer_size: LEBufferSize {
+                le_acl_data_packet_length: 251,
+                total_num_le_acl_data_packets: 4,
+            },
+        };
+        let mut buf = [0_u8; LEBufferSizeReturn::BYTE_LEN];
+        ret.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x00, 0xFB, 0x00, 0x04]);
+        assert_eq!(LEBufferSizeReturn::unpack_from(&buf), Ok(ret));
+        assert!(!ret.buffer_size.is_shared_with_br_edr());
+        assert!(LEBufferSize::default().is_shared_with_br_edr());
+        assert!(LEBufferSizeReturn::unpack_from(&buf[..3]).is_err());
+        assert!(LEBufferSizeReturn::unpack_from(&[0x00, 0xFB, 0x00, 0x04, 0x00]).is_err());
+        assert_eq!(
+            LEBufferSizeReturn::unpack_from(&[0xFF, 0xFB, 0x00, 0x04]),
+            Err(PackError::bad_index(0))
+        );
+    }
+    #[test]
+    fn test_le_features_return() {
+        let ret = LEFeaturesReturn {
+            status: ErrorCode::Ok,
+            features: LEFeatures(0x0123_4567_89AB_CDEF),
+        };
+        let mut buf = [0_u8; LEFeaturesReturn::BYTE_LEN];
+        ret.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x00, 0xEF, 0xCD, 0xAB, 0x89, 0x67, 0x45, 0x23, 0x01]);
+        assert_eq!(LEFeaturesReturn::unpack_from(&buf), Ok(ret));
+        assert!(LEFeaturesReturn::unpack_from(&buf[..LEFeaturesReturn::BYTE_LEN - 1]).is_err());
+        assert!(LEFeatures::unpack_from(&buf[..]).is_err());
+        assert!(ret
+            .pack_into(&mut [0_u8; LEFeaturesReturn::BYTE_LEN + 1])
+            .is_err());
+    }
+}
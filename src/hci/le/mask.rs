@@ -11,7 +11,7 @@ use core::convert::TryFrom;
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 pub struct MetaEventMask(u64);
 impl MetaEventMask {
-    const DEFAULT: MetaEventMask = MetaEventMask(0x1F);
+    pub const DEFAULT: MetaEventMask = MetaEventMask(0x1F);
     const ZEROED: MetaEventMask = MetaEventMask(0);
     const BYTE_LEN: usize = 8;
     pub const fn zeroed() -> MetaEventMask {
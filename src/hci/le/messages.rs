@@ -5,14 +5,94 @@ pub mod commands {
     pub use super::advertise::SetAdvertisingData;
     pub use super::advertise::SetAdvertisingEnable;
     pub use super::advertise::SetAdvertisingParameters;
+    pub use super::advertise::SetScanResponseData;
+
+    pub use super::extended_advertise::ClearAdvertisingSets;
+    pub use super::extended_advertise::ReadMaximumAdvertisingDataLength;
+    pub use super::extended_advertise::ReadNumberOfSupportedAdvertisingSets;
+    pub use super::extended_advertise::RemoveAdvertisingSet;
+    pub use super::extended_advertise::SetAdvertisingSetRandomAddress;
+    pub use super::extended_advertise::SetExtendedAdvertisingData;
+    pub use super::extended_advertise::SetExtendedAdvertisingEnable;
+    pub use super::extended_advertise::SetExtendedAdvertisingParameters;
+    pub use super::extended_advertise::SetExtendedScanResponseData;
+
+    pub use super::extended_scan::SetExtendedScanEnable;
+    pub use super::extended_scan::SetExtendedScanParameters;
+
+    pub use super::periodic_advertise::SetPeriodicAdvertisingData;
+    pub use super::periodic_advertise::SetPeriodicAdvertisingEnable;
+    pub use super::periodic_advertise::SetPeriodicAdvertisingParameters;
+
+    pub use super::periodic_sync::AddDeviceToPeriodicAdvertiserList;
+    pub use super::periodic_sync::ClearPeriodicAdvertiserList;
+    pub use super::periodic_sync::PeriodicAdvertisingCreateSync;
+    pub use super::periodic_sync::PeriodicAdvertisingCreateSyncCancel;
+    pub use super::periodic_sync::PeriodicAdvertisingTerminateSync;
+    pub use super::periodic_sync::ReadPeriodicAdvertiserListSize;
+    pub use super::periodic_sync::RemoveDeviceFromPeriodicAdvertiserList;
+
+    pub use super::data_length::ReadMaximumDataLength;
+    pub use super::data_length::ReadSuggestedDefaultDataLength;
+    pub use super::data_length::SetDataLength;
+    pub use super::data_length::WriteSuggestedDefaultDataLength;
+
+    pub use super::privacy::AddDeviceToResolvingList;
+    pub use super::privacy::ClearResolvingList;
+    pub use super::privacy::ReadLocalResolvableAddress;
+    pub use super::privacy::ReadPeerResolvableAddress;
+    pub use super::privacy::ReadResolvingListSize;
+    pub use super::privacy::RemoveDeviceFromResolvingList;
+    pub use super::privacy::SetAddressResolutionEnable;
+    pub use super::privacy::SetResolvablePrivateAddressTimeout;
+
+    pub use super::phy::ReadPhy;
+    pub use super::phy::SetDefaultPhy;
+    pub use super::phy::SetPhy;
 
     pub use super::scan::SetScanEnable;
     pub use super::scan::SetScanParameters;
 
     pub use super::mask::SetMetaEventMask;
 
+    pub use super::features::ReadBufferSize;
+    pub use super::features::ReadLocalSupportedFeatures;
+    pub use super::features::ReadSupportedStates;
+
+    pub use super::encrypt::Encrypt;
     pub use super::random::Rand;
+    pub use super::random::SetRandomAddress;
+
+    pub use super::whitelist::AddDeviceToWhitelist;
+    pub use super::whitelist::ClearWhitelist;
+    pub use super::whitelist::ReadWhitelistSize;
+    pub use super::whitelist::RemoveDeviceFromWhitelist;
+
+    pub use super::connection::ConnectionUpdate;
+    pub use super::connection::CreateConnection;
+    pub use super::connection::CreateConnectionCancel;
+    pub use super::connection::RemoteConnectionParameterRequestNegativeReply;
+    pub use super::connection::RemoteConnectionParameterRequestReply;
 }
 pub mod events {
     pub use super::report::AdvertisingReport;
+    pub use super::report::DirectedAdvertisingReport;
+
+    pub use super::extended_advertise::AdvertisingSetTerminated;
+
+    pub use super::extended_scan::ScanTimeout;
+    pub use super::report::ExtendedAdvertisingReport;
+
+    pub use super::periodic_sync::PeriodicAdvertisingReport;
+    pub use super::periodic_sync::PeriodicAdvertisingSyncEstablished;
+    pub use super::periodic_sync::PeriodicAdvertisingSyncLost;
+
+    pub use super::connection::ConnectionComplete;
+    pub use super::connection::ConnectionUpdateComplete;
+    pub use super::connection::EnhancedConnectionComplete;
+    pub use super::connection::RemoteConnectionParameterRequest;
+
+    pub use super::data_length::DataLengthChange;
+
+    pub use super::phy::PhyUpdateComplete;
 }
@@ -1,11 +1,22 @@
 //! HCI LE Layer. Handles everything from advertising, scanning, LE links, etc.
 pub mod advertise;
+pub mod connection;
+pub mod data_length;
+pub mod encrypt;
+pub mod extended_advertise;
+pub mod extended_scan;
+pub mod features;
 pub mod mask;
 pub mod messages;
+pub mod periodic_advertise;
+pub mod periodic_sync;
 pub mod report;
 pub use messages::*;
+pub mod phy;
+pub mod privacy;
 pub mod random;
 pub mod scan;
+pub mod whitelist;
 use crate::bytes::Storage;
 use crate::hci::event::{Event, EventCode, EventPacket};
 use crate::hci::{Opcode, OCF, OGF};
@@ -47,6 +58,44 @@ pub enum LEControllerOpcode {
     ReceiverTest = 0x001D,
     TransmitterTest = 0x001E,
     TestEnd = 0x001F,
+    RemoteConnectionParameterRequestReply = 0x0020,
+    RemoteConnectionParameterRequestNegativeReply = 0x0021,
+    SetDataLength = 0x0022,
+    ReadSuggestedDefaultDataLength = 0x0023,
+    WriteSuggestedDefaultDataLength = 0x0024,
+    AddDeviceToResolvingList = 0x0027,
+    RemoveDeviceFromResolvingList = 0x0028,
+    ClearResolvingList = 0x0029,
+    ReadResolvingListSize = 0x002A,
+    ReadPeerResolvableAddress = 0x002B,
+    ReadLocalResolvableAddress = 0x002C,
+    SetAddressResolutionEnable = 0x002D,
+    SetResolvablePrivateAddressTimeout = 0x002E,
+    ReadMaximumDataLength = 0x002F,
+    ReadPhy = 0x0030,
+    SetDefaultPhy = 0x0031,
+    SetPhy = 0x0032,
+    SetAdvertisingSetRandomAddress = 0x0035,
+    SetExtendedAdvertisingParameters = 0x0036,
+    SetExtendedAdvertisingData = 0x0037,
+    SetExtendedScanResponseData = 0x0038,
+    SetExtendedAdvertisingEnable = 0x0039,
+    ReadMaximumAdvertisingDataLength = 0x003A,
+    ReadNumberOfSupportedAdvertisingSets = 0x003B,
+    RemoveAdvertisingSet = 0x003C,
+    ClearAdvertisingSets = 0x003D,
+    SetPeriodicAdvertisingParameters = 0x003E,
+    SetPeriodicAdvertisingData = 0x003F,
+    SetPeriodicAdvertisingEnable = 0x0040,
+    SetExtendedScanParameters = 0x0041,
+    SetExtendedScanEnable = 0x0042,
+    PeriodicAdvertisingCreateSync = 0x0044,
+    PeriodicAdvertisingCreateSyncCancel = 0x0045,
+    PeriodicAdvertisingTerminateSync = 0x0046,
+    AddDeviceToPeriodicAdvertiserList = 0x0047,
+    RemoveDeviceFromPeriodicAdvertiserList = 0x0048,
+    ClearPeriodicAdvertiserList = 0x0049,
+    ReadPeriodicAdvertiserListSize = 0x004A,
 }
 impl TryFrom<OCF> for LEControllerOpcode {
     type Error = ConversionError;
@@ -83,6 +132,44 @@ impl TryFrom<OCF> for LEControllerOpcode {
             0x001D => Ok(LEControllerOpcode::ReceiverTest),
             0x001E => Ok(LEControllerOpcode::TransmitterTest),
             0x001F => Ok(LEControllerOpcode::TestEnd),
+            0x0020 => Ok(LEControllerOpcode::RemoteConnectionParameterRequestReply),
+            0x0021 => Ok(LEControllerOpcode::RemoteConnectionParameterRequestNegativeReply),
+            0x0022 => Ok(LEControllerOpcode::SetDataLength),
+            0x0023 => Ok(LEControllerOpcode::ReadSuggestedDefaultDataLength),
+            0x0024 => Ok(LEControllerOpcode::WriteSuggestedDefaultDataLength),
+            0x0027 => Ok(LEControllerOpcode::AddDeviceToResolvingList),
+            0x0028 => Ok(LEControllerOpcode::RemoveDeviceFromResolvingList),
+            0x0029 => Ok(LEControllerOpcode::ClearResolvingList),
+            0x002A => Ok(LEControllerOpcode::ReadResolvingListSize),
+            0x002B => Ok(LEControllerOpcode::ReadPeerResolvableAddress),
+            0x002C => Ok(LEControllerOpcode::ReadLocalResolvableAddress),
+            0x002D => Ok(LEControllerOpcode::SetAddressResolutionEnable),
+            0x002E => Ok(LEControllerOpcode::SetResolvablePrivateAddressTimeout),
+            0x002F => Ok(LEControllerOpcode::ReadMaximumDataLength),
+            0x0030 => Ok(LEControllerOpcode::ReadPhy),
+            0x0031 => Ok(LEControllerOpcode::SetDefaultPhy),
+            0x0032 => Ok(LEControllerOpcode::SetPhy),
+            0x0035 => Ok(LEControllerOpcode::SetAdvertisingSetRandomAddress),
+            0x0036 => Ok(LEControllerOpcode::SetExtendedAdvertisingParameters),
+            0x0037 => Ok(LEControllerOpcode::SetExtendedAdvertisingData),
+            0x0038 => Ok(LEControllerOpcode::SetExtendedScanResponseData),
+            0x0039 => Ok(LEControllerOpcode::SetExtendedAdvertisingEnable),
+            0x003A => Ok(LEControllerOpcode::ReadMaximumAdvertisingDataLength),
+            0x003B => Ok(LEControllerOpcode::ReadNumberOfSupportedAdvertisingSets),
+            0x003C => Ok(LEControllerOpcode::RemoveAdvertisingSet),
+            0x003D => Ok(LEControllerOpcode::ClearAdvertisingSets),
+            0x003E => Ok(LEControllerOpcode::SetPeriodicAdvertisingParameters),
+            0x003F => Ok(LEControllerOpcode::SetPeriodicAdvertisingData),
+            0x0040 => Ok(LEControllerOpcode::SetPeriodicAdvertisingEnable),
+            0x0041 => Ok(LEControllerOpcode::SetExtendedScanParameters),
+            0x0042 => Ok(LEControllerOpcode::SetExtendedScanEnable),
+            0x0044 => Ok(LEControllerOpcode::PeriodicAdvertisingCreateSync),
+            0x0045 => Ok(LEControllerOpcode::PeriodicAdvertisingCreateSyncCancel),
+            0x0046 => Ok(LEControllerOpcode::PeriodicAdvertisingTerminateSync),
+            0x0047 => Ok(LEControllerOpcode::AddDeviceToPeriodicAdvertiserList),
+            0x0048 => Ok(LEControllerOpcode::RemoveDeviceFromPeriodicAdvertiserList),
+            0x0049 => Ok(LEControllerOpcode::ClearPeriodicAdvertiserList),
+            0x004A => Ok(LEControllerOpcode::ReadPeriodicAdvertiserListSize),
             _ => Err(ConversionError(())),
         }
     }
@@ -214,6 +301,7 @@ pub trait MetaEvent {
     fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError>;
 }
 
+#[derive(Clone)]
 pub struct RawMetaEvent<Buf> {
     pub code: MetaEventCode,
     pub parameters: Buf,
@@ -232,6 +320,14 @@ impl<Buf: AsRef<[u8]>> RawMetaEvent<Buf> {
         }
     }
 }
+impl<Buf: AsRef<[u8]>> core::fmt::Debug for RawMetaEvent<Buf> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
+        f.debug_struct("RawMetaEvent")
+            .field("code", &self.code)
+            .field("parameters", &self.parameters.as_ref())
+            .finish()
+    }
+}
 impl<'a> TryFrom<EventPacket<&'a [u8]>> for RawMetaEvent<&'a [u8]> {
     type Error = PackError;
 
@@ -254,6 +350,11 @@ impl<'a> TryFrom<EventPacket<&'a [u8]>> for RawMetaEvent<&'a [u8]> {
 impl<M: MetaEvent> Event for M {
     const EVENT_CODE: EventCode = EventCode::LEMeta;
 
+    fn matches_event_packet(packet: &EventPacket<&[u8]>) -> bool {
+        packet.event_code == EventCode::LEMeta
+            && packet.parameters.first() == Some(&u8::from(Self::META_CODE))
+    }
+
     fn event_byte_len(&self) -> usize {
         MetaEvent::meta_byte_len(self) + 1
     }
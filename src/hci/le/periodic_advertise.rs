@@ -0,0 +1,533 @@
+//! LE Periodic Advertising commands ([`SetPeriodicAdvertisingParameters`],
+//! [`SetPeriodicAdvertisingData`] and [`SetPeriodicAdvertisingEnable`]). Periodic advertising
+//! runs on top of an extended advertising set (see
+//! [`extended_advertise`](crate::hci::le::extended_advertise)) that is neither connectable nor
+//! scannable.
+use crate::bytes::ToFromBytesEndian;
+use crate::hci::command::Command;
+use crate::hci::event::{CommandComplete, StatusReturn};
+use crate::hci::le::extended_advertise::{
+    AdvertisingHandle, FragmentOperation, MAX_EXTENDED_ADVERTISING_DATA_LEN,
+};
+use crate::hci::le::LEControllerOpcode;
+use crate::hci::Opcode;
+use crate::ConversionError;
+use crate::PackError;
+use core::convert::TryFrom;
+
+/// Max length of the data in one [`SetPeriodicAdvertisingData`] command.
+pub const MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN: usize = 252;
+
+/// Periodic advertising interval (in units of 1.25 ms). Range 0x0006 to 0xFFFF (7.5 ms to
+/// 81.91875 s).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PeriodicAdvertisingInterval(u16);
+impl PeriodicAdvertisingInterval {
+    pub const BYTE_LEN: usize = 2;
+    pub const MIN_U16: u16 = 0x0006;
+    pub const MAX_U16: u16 = 0xFFFF;
+    pub const MIN: PeriodicAdvertisingInterval = PeriodicAdvertisingInterval(Self::MIN_U16);
+    pub const MAX: PeriodicAdvertisingInterval = PeriodicAdvertisingInterval(Self::MAX_U16);
+    /// 100 ms.
+    pub const DEFAULT: PeriodicAdvertisingInterval = PeriodicAdvertisingInterval(80);
+    /// Creates a new `PeriodicAdvertisingInterval`.
+    /// # Panics
+    /// Panics if `interval < PeriodicAdvertisingInterval::MIN_U16`.
+    pub fn new(interval: u16) -> PeriodicAdvertisingInterval {
+        assert!(
+            interval >= Self::MIN_U16,
+            "invalid periodic advertising interval '{}'",
+            interval
+        );
+        PeriodicAdvertisingInterval(interval)
+    }
+    pub fn as_microseconds(self) -> u32 {
+        u32::from(self.0) * 1250
+    }
+}
+impl Default for PeriodicAdvertisingInterval {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+impl From<PeriodicAdvertisingInterval> for u16 {
+    fn from(i: PeriodicAdvertisingInterval) -> Self {
+        i.0
+    }
+}
+impl TryFrom<u16> for PeriodicAdvertisingInterval {
+    type Error = ConversionError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        if value >= Self::MIN_U16 {
+            Ok(PeriodicAdvertisingInterval(value))
+        } else {
+            Err(ConversionError(()))
+        }
+    }
+}
+/// Periodic advertising parameters of an advertising set.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PeriodicAdvertisingParameters {
+    pub interval_min: PeriodicAdvertisingInterval,
+    pub interval_max: PeriodicAdvertisingInterval,
+    /// Include the TX power in the periodic advertising PDUs.
+    pub include_tx_power: bool,
+}
+impl PeriodicAdvertisingParameters {
+    pub const BYTE_LEN: usize = PeriodicAdvertisingInterval::BYTE_LEN * 2 + 2;
+    const INCLUDE_TX_POWER_BIT: u16 = 1 << 6;
+    pub const DEFAULT: PeriodicAdvertisingParameters = PeriodicAdvertisingParameters {
+        interval_min: PeriodicAdvertisingInterval::DEFAULT,
+        interval_max: PeriodicAdvertisingInterval::DEFAULT,
+        include_tx_power: false,
+    };
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        if self.interval_min > self.interval_max {
+            return Err(PackError::InvalidFields);
+        }
+        let properties = if self.include_tx_power {
+            Self::INCLUDE_TX_POWER_BIT
+        } else {
+            0
+        };
+        buf[0..2].copy_from_slice(&u16::from(self.interval_min).to_bytes_le());
+        buf[2..4].copy_from_slice(&u16::from(self.interval_max).to_bytes_le());
+        buf[4..6].copy_from_slice(&properties.to_bytes_le());
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let properties = u16::from_le_bytes([buf[4], buf[5]]);
+        if properties & !Self::INCLUDE_TX_POWER_BIT != 0 {
+            return Err(PackError::bad_index(4));
+        }
+        Ok(PeriodicAdvertisingParameters {
+            interval_min: PeriodicAdvertisingInterval::try_from(u16::from_le_bytes([
+                buf[0], buf[1],
+            ]))
+            .map_err(|_| PackError::bad_index(0))?,
+            interval_max: PeriodicAdvertisingInterval::try_from(u16::from_le_bytes([
+                buf[2], buf[3],
+            ]))
+            .map_err(|_| PackError::bad_index(2))?,
+            include_tx_power: properties & Self::INCLUDE_TX_POWER_BIT != 0,
+        })
+    }
+}
+impl Default for PeriodicAdvertisingParameters {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+/// LE Set Periodic Advertising Parameters Command. The periodic advertising of `handle` must be
+/// disabled.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SetPeriodicAdvertisingParameters {
+    pub handle: AdvertisingHandle,
+    pub parameters: PeriodicAdvertisingParameters,
+}
+impl SetPeriodicAdvertisingParameters {
+    pub const BYTE_LEN: usize =
+        AdvertisingHandle::BYTE_LEN + PeriodicAdvertisingParameters::BYTE_LEN;
+}
+impl Command for SetPeriodicAdvertisingParameters {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetPeriodicAdvertisingParameters.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.handle.into();
+        self.parameters.pack_into(&mut buf[1..])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(SetPeriodicAdvertisingParameters {
+            handle: AdvertisingHandle::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            parameters: PeriodicAdvertisingParameters::unpack_from(&buf[1..]).map_err(
+                |e| match e {
+                    PackError::BadBytes { index: Some(i) } => PackError::bad_index(i + 1),
+                    e => e,
+                },
+            )?,
+        })
+    }
+}
+/// Up to [`MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN`] bytes of periodic advertising data of
+/// an advertising set. Use [`PeriodicAdvertisingDataFragment::fragments`] to split longer data.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PeriodicAdvertisingDataFragment {
+    pub handle: AdvertisingHandle,
+    pub operation: FragmentOperation,
+    data: [u8; MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN],
+    len: u8,
+}
+impl PeriodicAdvertisingDataFragment {
+    /// handle (1) + operation (1) + data length (1)
+    pub const HEADER_LEN: usize = 3;
+    /// Creates a new `PeriodicAdvertisingDataFragment`.
+    /// # Panics
+    /// Panics if `data.len() > MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN` (252).
+    pub fn new(
+        handle: AdvertisingHandle,
+        operation: FragmentOperation,
+        data: &[u8],
+    ) -> PeriodicAdvertisingDataFragment {
+        assert!(
+            data.len() <= MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN,
+            "periodic advertising data fragment too long"
+        );
+        let mut buf = [0_u8; MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN];
+        buf[..data.len()].copy_from_slice(data);
+        PeriodicAdvertisingDataFragment {
+            handle,
+            operation,
+            data: buf,
+            len: u8::try_from(data.len()).unwrap_or(u8::MAX),
+        }
+    }
+    /// Splits `data` into the fragments needed to set it as the periodic advertising data of
+    /// advertising set `handle`. Empty `data` gives one empty `FragmentOperation::Complete`
+    /// fragment.
+    /// # Panics
+    /// Panics if `data.len() > MAX_EXTENDED_ADVERTISING_DATA_LEN` (1650).
+    /// ```
+    /// use btle::hci::le::extended_advertise::{AdvertisingHandle, FragmentOperation};
+    /// use btle::hci::le::periodic_advertise::PeriodicAdvertisingDataFragment;
+    /// let data = [0x55_u8; 600];
+    /// let fragments: Vec<_> =
+    ///     PeriodicAdvertisingDataFragment::fragments(AdvertisingHandle::new(0), &data[..])
+    ///         .collect();
+    /// assert_eq!(fragments.len(), 3);
+    /// assert_eq!(fragments[0].operation, FragmentOperation::First);
+    /// assert_eq!(fragments[1].operation, FragmentOperation::Intermediate);
+    /// assert_eq!(fragments[2].operation, FragmentOperation::Last);
+    /// assert_eq!(fragments[2].data().len(), 600 - 2 * 252);
+    /// ```
+    pub fn fragments(
+        handle: AdvertisingHandle,
+        data: &[u8],
+    ) -> impl Iterator<Item = PeriodicAdvertisingDataFragment> + '_ {
+        assert!(
+            data.len() <= MAX_EXTENDED_ADVERTISING_DATA_LEN,
+            "periodic advertising data too long"
+        );
+        // `usize::div_ceil` needs Rust 1.73.
+        #[allow(clippy::manual_div_ceil)]
+        let count = core::cmp::max(
+            1,
+            (data.len() + MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN - 1)
+                / MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN,
+        );
+        (0..count).map(move |i| {
+            let start = i * MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN;
+            let end = core::cmp::min(
+                start + MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN,
+                data.len(),
+            );
+            let operation = match (i == 0, i + 1 == count) {
+                (true, true) => FragmentOperation::Complete,
+                (true, false) => FragmentOperation::First,
+                (false, true) => FragmentOperation::Last,
+                (false, false) => FragmentOperation::Intermediate,
+            };
+            PeriodicAdvertisingDataFragment::new(handle, operation, &data[start..end])
+        })
+    }
+    pub fn data(&self) -> &[u8] {
+        &self.data[..usize::from(self.len)]
+    }
+    pub fn byte_len(&self) -> usize {
+        Self::HEADER_LEN + usize::from(self.len)
+    }
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(self.byte_len(), buf)?;
+        buf[0] = self.handle.into();
+        buf[1] = self.operation.into();
+        buf[2] = self.len;
+        buf[Self::HEADER_LEN..].copy_from_slice(self.data());
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        if buf.len() < Self::HEADER_LEN {
+            return Err(PackError::BadLength {
+                expected: Self::HEADER_LEN,
+                got: buf.len(),
+            });
+        }
+        let len = usize::from(buf[2]);
+        if len > MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN {
+            return Err(PackError::bad_index(2));
+        }
+        PackError::expect_length(Self::HEADER_LEN + len, buf)?;
+        Ok(PeriodicAdvertisingDataFragment::new(
+            AdvertisingHandle::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            FragmentOperation::try_from(buf[1]).map_err(|_| PackError::bad_index(1))?,
+            &buf[Self::HEADER_LEN..],
+        ))
+    }
+}
+/// LE Set Periodic Advertising Data Command. While periodic advertising is enabled, the data
+/// can only be replaced with a single `FragmentOperation::Complete` fragment.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SetPeriodicAdvertisingData(pub PeriodicAdvertisingDataFragment);
+impl Command for SetPeriodicAdvertisingData {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetPeriodicAdvertisingData.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        self.0.byte_len()
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(SetPeriodicAdvertisingData(
+            PeriodicAdvertisingDataFragment::unpack_from(buf)?,
+        ))
+    }
+}
+/// LE Set Periodic Advertising Enable Command. Periodic advertising only starts being sent once
+/// the advertising set itself is also enabled.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SetPeriodicAdvertisingEnable {
+    pub is_enabled: bool,
+    /// Include the ADI (Advertising Data Info) field in the periodic advertising PDUs.
+    pub include_adi: bool,
+    pub handle: AdvertisingHandle,
+}
+impl SetPeriodicAdvertisingEnable {
+    pub const BYTE_LEN: usize = 1 + AdvertisingHandle::BYTE_LEN;
+    const ENABLE_BIT: u8 = 1 << 0;
+    const INCLUDE_ADI_BIT: u8 = 1 << 1;
+}
+impl Command for SetPeriodicAdvertisingEnable {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetPeriodicAdvertisingEnable.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = if self.is_enabled { Self::ENABLE_BIT } else { 0 }
+            | if self.include_adi {
+                Self::INCLUDE_ADI_BIT
+            } else {
+                0
+            };
+        buf[1] = self.handle.into();
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        if buf[0] & !(Self::ENABLE_BIT | Self::INCLUDE_ADI_BIT) != 0 {
+            return Err(PackError::bad_index(0));
+        }
+        Ok(SetPeriodicAdvertisingEnable {
+            is_enabled: buf[0] & Self::ENABLE_BIT != 0,
+            include_adi: buf[0] & Self::INCLUDE_ADI_BIT != 0,
+            handle: AdvertisingHandle::try_from(buf[1]).map_err(|_| PackError::bad_index(1))?,
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{
+        PeriodicAdvertisingDataFragment, PeriodicAdvertisingInterval,
+        PeriodicAdvertisingParameters, SetPeriodicAdvertisingData, SetPeriodicAdvertisingEnable,
+        SetPeriodicAdvertisingParameters, MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN,
+    };
+    use crate::hci::command::Command;
+    use crate::hci::le::extended_advertise::{
+        AdvertisingHandle, FragmentOperation, MAX_EXTENDED_ADVERTISING_DATA_LEN,
+    };
+    use crate::PackError;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn test_set_parameters_pack_unpack() {
+        let command = SetPeriodicAdvertisingParameters {
+            handle: AdvertisingHandle::new(0x02),
+            parameters: PeriodicAdvertisingParameters {
+                interval_min: PeriodicAdvertisingInterval::new(0x0010),
+                interval_max: PeriodicAdvertisingInterval::new(0x0100),
+                include_tx_power: true,
+            },
+        };
+        let mut buf = [0_u8; SetPeriodicAdvertisingParameters::BYTE_LEN];
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x02, 0x10, 0x00, 0x00, 0x01, 0x40, 0x00]);
+        assert_eq!(
+            SetPeriodicAdvertisingParameters::unpack_from(&buf),
+            Ok(command)
+        );
+        assert_eq!(
+            SetPeriodicAdvertisingParameters::unpack_from(&buf[..6]),
+            Err(PackError::BadLength {
+                expected: SetPeriodicAdvertisingParameters::BYTE_LEN,
+                got: 6,
+            })
+        );
+        assert!(command.pack_into(&mut buf[..6]).is_err());
+    }
+    #[test]
+    fn test_set_parameters_out_of_range() {
+        let mut command = SetPeriodicAdvertisingParameters {
+            handle: AdvertisingHandle::new(0),
+            parameters: PeriodicAdvertisingParameters::DEFAULT,
+        };
+        let mut buf = [0_u8; SetPeriodicAdvertisingParameters::BYTE_LEN];
+        command.pack_into(&mut buf).unwrap();
+        let good = buf;
+
+        buf[0] = AdvertisingHandle::MAX_HANDLE_U8 + 1;
+        assert_eq!(
+            SetPeriodicAdvertisingParameters::unpack_from(&buf),
+            Err(PackError::bad_index(0))
+        );
+        buf = good;
+        buf[1..3].copy_from_slice(&(PeriodicAdvertisingInterval::MIN_U16 - 1).to_le_bytes());
+        assert_eq!(
+            SetPeriodicAdvertisingParameters::unpack_from(&buf),
+            Err(PackError::bad_index(1))
+        );
+        buf = good;
+        // Only bit 6 (include TX power) of the properties is defined.
+        buf[5] = 0x01;
+        assert_eq!(
+            SetPeriodicAdvertisingParameters::unpack_from(&buf),
+            Err(PackError::bad_index(5))
+        );
+
+        assert!(
+            PeriodicAdvertisingInterval::try_from(PeriodicAdvertisingInterval::MIN_U16 - 1)
+                .is_err()
+        );
+        command.parameters.interval_min = PeriodicAdvertisingInterval::new(0x0200);
+        command.parameters.interval_max = PeriodicAdvertisingInterval::new(0x0100);
+        assert_eq!(command.pack_into(&mut buf), Err(PackError::InvalidFields));
+    }
+    #[test]
+    fn test_data_fragment_pack_unpack() {
+        let fragment = PeriodicAdvertisingDataFragment::new(
+            AdvertisingHandle::new(0x01),
+            FragmentOperation::Complete,
+            &[0x02, 0x01, 0x06],
+        );
+        let command = SetPeriodicAdvertisingData(fragment);
+        let mut buf = vec![0_u8; command.byte_len()];
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x01, 0x03, 0x03, 0x02, 0x01, 0x06]);
+        assert_eq!(SetPeriodicAdvertisingData::unpack_from(&buf), Ok(command));
+
+        assert_eq!(
+            SetPeriodicAdvertisingData::unpack_from(&buf[..5]),
+            Err(PackError::BadLength {
+                expected: 6,
+                got: 5
+            })
+        );
+        assert_eq!(
+            SetPeriodicAdvertisingData::unpack_from(&buf[..2]),
+            Err(PackError::BadLength {
+                expected: PeriodicAdvertisingDataFragment::HEADER_LEN,
+                got: 2
+            })
+        );
+        buf[1] = 0x05;
+        assert_eq!(
+            SetPeriodicAdvertisingData::unpack_from(&buf),
+            Err(PackError::bad_index(1))
+        );
+
+        let mut long = vec![0_u8; PeriodicAdvertisingDataFragment::HEADER_LEN + 253];
+        long[2] = 253;
+        assert_eq!(
+            PeriodicAdvertisingDataFragment::unpack_from(&long),
+            Err(PackError::bad_index(2))
+        );
+    }
+    #[test]
+    fn test_data_fragments() {
+        let handle = AdvertisingHandle::new(0x03);
+        let empty: Vec<_> = PeriodicAdvertisingDataFragment::fragments(handle, &[]).collect();
+        assert_eq!(empty.len(), 1);
+        assert_eq!(empty[0].operation, FragmentOperation::Complete);
+        assert!(empty[0].data().is_empty());
+
+        let exact = [0xAA_u8; MAX_PERIODIC_ADVERTISING_DATA_FRAGMENT_LEN];
+        let fragments: Vec<_> =
+            PeriodicAdvertisingDataFragment::fragments(handle, &exact).collect();
+        assert_eq!(fragments.len(), 1);
+        assert_eq!(fragments[0].operation, FragmentOperation::Complete);
+
+        let data: Vec<u8> = (0..MAX_EXTENDED_ADVERTISING_DATA_LEN)
+            .map(|i| i.to_le_bytes()[0])
+            .collect();
+        let fragments: Vec<_> = PeriodicAdvertisingDataFragment::fragments(handle, &data).collect();
+        assert_eq!(fragments.len(), 7);
+        assert_eq!(fragments[0].operation, FragmentOperation::First);
+        assert_eq!(fragments[6].operation, FragmentOperation::Last);
+        let joined: Vec<u8> = fragments
+            .iter()
+            .flat_map(|f| f.data().iter().copied())
+            .collect();
+        assert_eq!(joined, data);
+    }
+    #[test]
+    fn test_set_enable_pack_unpack() {
+        let command = SetPeriodicAdvertisingEnable {
+            is_enabled: true,
+            include_adi: true,
+            handle: AdvertisingHandle::new(0xEF),
+        };
+        let mut buf = [0_u8; SetPeriodicAdvertisingEnable::BYTE_LEN];
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x03, 0xEF]);
+        assert_eq!(SetPeriodicAdvertisingEnable::unpack_from(&buf), Ok(command));
+        assert!(SetPeriodicAdvertisingEnable::unpack_from(&buf[..1]).is_err());
+
+        buf[0] = 0x04;
+        assert_eq!(
+            SetPeriodicAdvertisingEnable::unpack_from(&buf),
+            Err(PackError::bad_index(0))
+        );
+        buf[0] = 0x01;
+        buf[1] = 0xF0;
+        assert_eq!(
+            SetPeriodicAdvertisingEnable::unpack_from(&buf),
+            Err(PackError::bad_index(1))
+        );
+    }
+}
@@ -0,0 +1,904 @@
+//! LE Periodic Advertising synchronization commands ([`PeriodicAdvertisingCreateSync`],
+//! [`PeriodicAdvertisingTerminateSync`], the Periodic Advertiser List, etc) and the
+//! [`PeriodicAdvertisingSyncEstablished`], [`PeriodicAdvertisingReport`] and
+//! [`PeriodicAdvertisingSyncLost`] events.
+use crate::bytes::ToFromBytesEndian;
+use crate::hci::command::{AsyncCommand, Command};
+use crate::hci::event::{CommandComplete, CommandStatus, ReturnParameters, StatusReturn};
+use crate::hci::le::advertise::TxPowerLevel;
+use crate::hci::le::phy::Phy;
+use crate::hci::le::report::DataStatus;
+use crate::hci::le::{LEControllerOpcode, MetaEvent, MetaEventCode};
+use crate::hci::{ErrorCode, Opcode};
+use crate::le::advertiser::PeerAddressType;
+use crate::le::report::AddressType;
+use crate::ConversionError;
+use crate::{BTAddress, PackError, BT_ADDRESS_LEN, RSSI};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// Identifies a periodic advertising train the controller is synchronized to (0x0000-0x0EFF).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct SyncHandle(u16);
+impl SyncHandle {
+    pub const BYTE_LEN: usize = 2;
+    pub const MAX_HANDLE_U16: u16 = 0x0EFF;
+    /// Creates a new `SyncHandle`.
+    /// # Panics
+    /// Panics if `handle > SyncHandle::MAX_HANDLE_U16` (0x0EFF).
+    pub fn new(handle: u16) -> SyncHandle {
+        assert!(
+            handle <= Self::MAX_HANDLE_U16,
+            "invalid sync handle '{}'",
+            handle
+        );
+        SyncHandle(handle)
+    }
+    pub fn pack_into(self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf.copy_from_slice(&self.0.to_bytes_le());
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<SyncHandle, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        SyncHandle::try_from(u16::from_le_bytes([buf[0], buf[1]]))
+            .map_err(|_| PackError::bad_index(0))
+    }
+}
+impl From<SyncHandle> for u16 {
+    fn from(h: SyncHandle) -> Self {
+        h.0
+    }
+}
+impl TryFrom<u16> for SyncHandle {
+    type Error = ConversionError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        if value <= Self::MAX_HANDLE_U16 {
+            Ok(SyncHandle(value))
+        } else {
+            Err(ConversionError(()))
+        }
+    }
+}
+/// Periodic advertising sync timeout (in units of 10 ms). Range 0x000A to 0x4000 (100 ms to
+/// 163.84 s).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SyncTimeout(u16);
+impl SyncTimeout {
+    pub const BYTE_LEN: usize = 2;
+    pub const MIN_U16: u16 = 0x000A;
+    pub const MAX_U16: u16 = 0x4000;
+    pub const MIN: SyncTimeout = SyncTimeout(Self::MIN_U16);
+    pub const MAX: SyncTimeout = SyncTimeout(Self::MAX_U16);
+    /// 10 seconds.
+    pub const DEFAULT: SyncTimeout = SyncTimeout(1000);
+    /// Creates a new `SyncTimeout`.
+    /// # Panics
+    /// Panics if `timeout < SyncTimeout::MIN_U16 || timeout > SyncTimeout::MAX_U16`.
+    pub fn new(timeout: u16) -> SyncTimeout {
+        assert!(
+            timeout >= Self::MIN_U16 && timeout <= Self::MAX_U16,
+            "invalid sync timeout '{}'",
+            timeout
+        );
+        SyncTimeout(timeout)
+    }
+    pub fn as_millis(self) -> u32 {
+        u32::from(self.0) * 10
+    }
+}
+impl Default for SyncTimeout {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+impl From<SyncTimeout> for u16 {
+    fn from(t: SyncTimeout) -> Self {
+        t.0
+    }
+}
+impl TryFrom<u16> for SyncTimeout {
+    type Error = ConversionError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        if value >= Self::MIN_U16 && value <= Self::MAX_U16 {
+            Ok(SyncTimeout(value))
+        } else {
+            Err(ConversionError(()))
+        }
+    }
+}
+/// LE Periodic Advertising Create Sync Command. Synchronizes to the periodic advertising of
+/// `address` (or of any device in the Periodic Advertiser List). Finishes with a
+/// [`PeriodicAdvertisingSyncEstablished`] event. Cancel it with
+/// [`PeriodicAdvertisingCreateSyncCancel`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PeriodicAdvertisingCreateSync {
+    /// Sync to any device in the Periodic Advertiser List (`sid`, `address_type` and `address`
+    /// are then ignored).
+    pub use_periodic_advertiser_list: bool,
+    /// Don't send [`PeriodicAdvertisingReport`]s once synchronized.
+    pub reporting_initially_disabled: bool,
+    /// Advertising SID (0x00-0x0F).
+    pub sid: u8,
+    pub address_type: PeerAddressType,
+    pub address: BTAddress,
+    /// Number of periodic advertising packets that can be skipped after a successful receive.
+    pub skip: u16,
+    pub sync_timeout: SyncTimeout,
+    /// Constant Tone Extension types not to sync to (bitmask). 0 syncs to any.
+    pub sync_cte_type: u8,
+}
+impl PeriodicAdvertisingCreateSync {
+    pub const BYTE_LEN: usize = 1 + 1 + 1 + BT_ADDRESS_LEN + 2 + SyncTimeout::BYTE_LEN + 1;
+    pub const MAX_SID: u8 = 0x0F;
+    /// Sync to `address` (with advertising set `sid`) with the default parameters.
+    pub fn new(address_type: PeerAddressType, address: BTAddress, sid: u8) -> Self {
+        PeriodicAdvertisingCreateSync {
+            use_periodic_advertiser_list: false,
+            reporting_initially_disabled: false,
+            sid,
+            address_type,
+            address,
+            skip: 0,
+            sync_timeout: SyncTimeout::DEFAULT,
+            sync_cte_type: 0,
+        }
+    }
+}
+impl Command for PeriodicAdvertisingCreateSync {
+    type Return = CommandStatus;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::PeriodicAdvertisingCreateSync.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        if self.sid > Self::MAX_SID {
+            return Err(PackError::InvalidFields);
+        }
+        buf[0] = u8::from(self.use_periodic_advertiser_list)
+            | (u8::from(self.reporting_initially_disabled) << 1);
+        buf[1] = self.sid;
+        buf[2] = self.address_type.into();
+        self.address.pack_into(&mut buf[3..3 + BT_ADDRESS_LEN])?;
+        let buf = &mut buf[3 + BT_ADDRESS_LEN..];
+        buf[0..2].copy_from_slice(&self.skip.to_bytes_le());
+        buf[2..4].copy_from_slice(&u16::from(self.sync_timeout).to_bytes_le());
+        buf[4] = self.sync_cte_type;
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        if buf[0] & !0b11 != 0 {
+            return Err(PackError::bad_index(0));
+        }
+        if buf[1] > Self::MAX_SID {
+            return Err(PackError::bad_index(1));
+        }
+        let rest = &buf[3 + BT_ADDRESS_LEN..];
+        Ok(PeriodicAdvertisingCreateSync {
+            use_periodic_advertiser_list: buf[0] & 0b01 != 0,
+            reporting_initially_disabled: buf[0] & 0b10 != 0,
+            sid: buf[1],
+            address_type: PeerAddressType::try_from(buf[2]).map_err(|_| PackError::bad_index(2))?,
+            address: BTAddress::unpack_from(&buf[3..3 + BT_ADDRESS_LEN])?,
+            skip: u16::from_le_bytes([rest[0], rest[1]]),
+            sync_timeout: SyncTimeout::try_from(u16::from_le_bytes([rest[2], rest[3]]))
+                .map_err(|_| PackError::bad_index(5 + BT_ADDRESS_LEN))?,
+            sync_cte_type: rest[4],
+        })
+    }
+}
+impl AsyncCommand for PeriodicAdvertisingCreateSync {
+    type Completion = PeriodicAdvertisingSyncEstablished;
+
+    /// Only syncs to the requested advertising set (any set in the Periodic Advertiser List when
+    /// using it) finish `PeriodicAdvertisingCreateSync`. Failures finish it too.
+    fn is_completion(&self, completion: &PeriodicAdvertisingSyncEstablished) -> bool {
+        if completion.status != ErrorCode::Ok || self.use_periodic_advertiser_list {
+            return true;
+        }
+        let same_type =
+            completion.address_type.is_random() == (self.address_type == PeerAddressType::Random);
+        completion.sid == self.sid && same_type && completion.address == self.address
+    }
+}
+/// LE Periodic Advertising Create Sync Cancel Command. The pending
+/// [`PeriodicAdvertisingCreateSync`] then finishes with
+/// `ErrorCode::OperationCancelledByHost`.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct PeriodicAdvertisingCreateSyncCancel {}
+impl Command for PeriodicAdvertisingCreateSyncCancel {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::PeriodicAdvertisingCreateSyncCancel.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(PeriodicAdvertisingCreateSyncCancel {})
+    }
+}
+/// LE Periodic Advertising Terminate Sync Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PeriodicAdvertisingTerminateSync {
+    pub sync_handle: SyncHandle,
+}
+impl Command for PeriodicAdvertisingTerminateSync {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::PeriodicAdvertisingTerminateSync.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        SyncHandle::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.sync_handle.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(PeriodicAdvertisingTerminateSync {
+            sync_handle: SyncHandle::unpack_from(buf)?,
+        })
+    }
+}
+/// Entry of the controller's Periodic Advertiser List.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PeriodicAdvertiserListEntry {
+    pub address_type: PeerAddressType,
+    pub address: BTAddress,
+    /// Advertising SID (0x00-0x0F).
+    pub sid: u8,
+}
+impl PeriodicAdvertiserListEntry {
+    pub const BYTE_LEN: usize = 1 + BT_ADDRESS_LEN + 1;
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        if self.sid > PeriodicAdvertisingCreateSync::MAX_SID {
+            return Err(PackError::InvalidFields);
+        }
+        buf[0] = self.address_type.into();
+        self.address.pack_into(&mut buf[1..1 + BT_ADDRESS_LEN])?;
+        buf[1 + BT_ADDRESS_LEN] = self.sid;
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let sid = buf[1 + BT_ADDRESS_LEN];
+        if sid > PeriodicAdvertisingCreateSync::MAX_SID {
+            return Err(PackError::bad_index(1 + BT_ADDRESS_LEN));
+        }
+        Ok(PeriodicAdvertiserListEntry {
+            address_type: PeerAddressType::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            address: BTAddress::unpack_from(&buf[1..1 + BT_ADDRESS_LEN])?,
+            sid,
+        })
+    }
+}
+/// LE Add Device To Periodic Advertiser List Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct AddDeviceToPeriodicAdvertiserList(pub PeriodicAdvertiserListEntry);
+impl Command for AddDeviceToPeriodicAdvertiserList {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::AddDeviceToPeriodicAdvertiserList.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        PeriodicAdvertiserListEntry::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(AddDeviceToPeriodicAdvertiserList(
+            PeriodicAdvertiserListEntry::unpack_from(buf)?,
+        ))
+    }
+}
+/// LE Remove Device From Periodic Advertiser List Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct RemoveDeviceFromPeriodicAdvertiserList(pub PeriodicAdvertiserListEntry);
+impl Command for RemoveDeviceFromPeriodicAdvertiserList {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::RemoveDeviceFromPeriodicAdvertiserList.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        PeriodicAdvertiserListEntry::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(RemoveDeviceFromPeriodicAdvertiserList(
+            PeriodicAdvertiserListEntry::unpack_from(buf)?,
+        ))
+    }
+}
+/// LE Clear Periodic Advertiser List Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ClearPeriodicAdvertiserList {}
+impl Command for ClearPeriodicAdvertiserList {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ClearPeriodicAdvertiserList.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ClearPeriodicAdvertiserList {})
+    }
+}
+/// LE Read Periodic Advertiser List Size Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadPeriodicAdvertiserListSize {}
+impl Command for ReadPeriodicAdvertiserListSize {
+    type Return = CommandComplete<PeriodicAdvertiserListSizeReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ReadPeriodicAdvertiserListSize.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadPeriodicAdvertiserListSize {})
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PeriodicAdvertiserListSizeReturn {
+    pub status: ErrorCode,
+    pub size: u8,
+}
+impl PeriodicAdvertiserListSizeReturn {
+    pub const BYTE_LEN: usize = 2;
+}
+impl ReturnParameters for PeriodicAdvertiserListSizeReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        buf[1] = self.size;
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(PeriodicAdvertiserListSizeReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            size: buf[1],
+        })
+    }
+}
+/// Sleep clock accuracy of the periodic advertiser.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum ClockAccuracy {
+    Ppm500 = 0x00,
+    Ppm250 = 0x01,
+    Ppm150 = 0x02,
+    Ppm100 = 0x03,
+    Ppm75 = 0x04,
+    Ppm50 = 0x05,
+    Ppm30 = 0x06,
+    Ppm20 = 0x07,
+}
+impl ClockAccuracy {
+    pub fn as_ppm(self) -> u16 {
+        match self {
+            ClockAccuracy::Ppm500 => 500,
+            ClockAccuracy::Ppm250 => 250,
+            ClockAccuracy::Ppm150 => 150,
+            ClockAccuracy::Ppm100 => 100,
+            ClockAccuracy::Ppm75 => 75,
+            ClockAccuracy::Ppm50 => 50,
+            ClockAccuracy::Ppm30 => 30,
+            ClockAccuracy::Ppm20 => 20,
+        }
+    }
+}
+impl From<ClockAccuracy> for u8 {
+    fn from(c: ClockAccuracy) -> Self {
+        c as u8
+    }
+}
+impl TryFrom<u8> for ClockAccuracy {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(ClockAccuracy::Ppm500),
+            0x01 => Ok(ClockAccuracy::Ppm250),
+            0x02 => Ok(ClockAccuracy::Ppm150),
+            0x03 => Ok(ClockAccuracy::Ppm100),
+            0x04 => Ok(ClockAccuracy::Ppm75),
+            0x05 => Ok(ClockAccuracy::Ppm50),
+            0x06 => Ok(ClockAccuracy::Ppm30),
+            0x07 => Ok(ClockAccuracy::Ppm20),
+            _ => Err(ConversionError(())),
+        }
+    }
+}
+/// LE Periodic Advertising Sync Established Event. Finishes a
+/// [`PeriodicAdvertisingCreateSync`]. The other fields are only meaningful if `status` is
+/// `ErrorCode::Ok`.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PeriodicAdvertisingSyncEstablished {
+    pub status: ErrorCode,
+    pub sync_handle: SyncHandle,
+    pub sid: u8,
+    pub address_type: AddressType,
+    pub address: BTAddress,
+    pub phy: Phy,
+    /// Periodic advertising interval (in units of 1.25 ms).
+    pub periodic_advertising_interval: u16,
+    pub clock_accuracy: ClockAccuracy,
+}
+impl PeriodicAdvertisingSyncEstablished {
+    pub const BYTE_LEN: usize = 1 + SyncHandle::BYTE_LEN + 1 + 1 + BT_ADDRESS_LEN + 1 + 2 + 1;
+}
+impl MetaEvent for PeriodicAdvertisingSyncEstablished {
+    const META_CODE: MetaEventCode = MetaEventCode::PeriodicAdvertisingSyncEstablished;
+
+    fn meta_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn meta_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let status = ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?;
+        let rest = &buf[5 + BT_ADDRESS_LEN..];
+        let phy_index = 5 + BT_ADDRESS_LEN;
+        let accuracy_index = phy_index + 3;
+        if status != ErrorCode::Ok {
+            // The controller doesn't have to fill in the fields of a failed sync.
+            return Ok(PeriodicAdvertisingSyncEstablished {
+                status,
+                sync_handle: SyncHandle::default(),
+                sid: buf[3],
+                address_type: AddressType::try_from(buf[4]).unwrap_or(AddressType::PublicDevice),
+                address: BTAddress::unpack_from(&buf[5..5 + BT_ADDRESS_LEN])?,
+                phy: Phy::try_from(rest[0]).unwrap_or(Phy::DEFAULT),
+                periodic_advertising_interval: u16::from_le_bytes([rest[1], rest[2]]),
+                clock_accuracy: ClockAccuracy::try_from(rest[3]).unwrap_or(ClockAccuracy::Ppm500),
+            });
+        }
+        Ok(PeriodicAdvertisingSyncEstablished {
+            status,
+            sync_handle: SyncHandle::unpack_from(&buf[1..3])?,
+            sid: buf[3],
+            address_type: AddressType::try_from(buf[4]).map_err(|_| PackError::bad_index(4))?,
+            address: BTAddress::unpack_from(&buf[5..5 + BT_ADDRESS_LEN])?,
+            phy: Phy::try_from(rest[0]).map_err(|_| PackError::bad_index(phy_index))?,
+            periodic_advertising_interval: u16::from_le_bytes([rest[1], rest[2]]),
+            clock_accuracy: ClockAccuracy::try_from(rest[3])
+                .map_err(|_| PackError::bad_index(accuracy_index))?,
+        })
+    }
+
+    fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.sync_handle.pack_into(&mut buf[1..3])?;
+        buf[3] = self.sid;
+        buf[4] = self.address_type.into();
+        self.address.pack_into(&mut buf[5..5 + BT_ADDRESS_LEN])?;
+        let rest = &mut buf[5 + BT_ADDRESS_LEN..];
+        rest[0] = self.phy.into();
+        rest[1..3].copy_from_slice(&self.periodic_advertising_interval.to_bytes_le());
+        rest[3] = self.clock_accuracy.into();
+        Ok(())
+    }
+}
+/// LE Periodic Advertising Report Event. Data longer than one event is split over multiple
+/// reports (see `data_status`).
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PeriodicAdvertisingReport {
+    pub sync_handle: SyncHandle,
+    pub tx_power: Option<TxPowerLevel>,
+    pub rssi: Option<RSSI>,
+    /// Constant Tone Extension type (0xFF if none).
+    pub cte_type: u8,
+    pub data_status: DataStatus,
+    pub data: Vec<u8>,
+}
+impl PeriodicAdvertisingReport {
+    /// Length of the report without `data`.
+    pub const HEADER_LEN: usize = SyncHandle::BYTE_LEN + 1 + 1 + 1 + 1 + 1;
+    const NOT_AVAILABLE: u8 = 0x7F;
+}
+impl MetaEvent for PeriodicAdvertisingReport {
+    const META_CODE: MetaEventCode = MetaEventCode::PeriodicAdvertisingReport;
+
+    fn meta_byte_len(&self) -> usize {
+        Self::HEADER_LEN + self.data.len()
+    }
+
+    fn meta_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        if buf.len() < Self::HEADER_LEN {
+            return Err(PackError::BadLength {
+                expected: Self::HEADER_LEN,
+                got: buf.len(),
+            });
+        }
+        let data_len = usize::from(buf[6]);
+        PackError::expect_length(Self::HEADER_LEN + data_len, buf)?;
+        Ok(PeriodicAdvertisingReport {
+            sync_handle: SyncHandle::unpack_from(&buf[0..2])?,
+            tx_power: match buf[2] {
+                Self::NOT_AVAILABLE => None,
+                power => Some(TxPowerLevel::try_from(power).map_err(|_| PackError::bad_index(2))?),
+            },
+            rssi: RSSI::maybe_rssi(i8::from_le_bytes([buf[3]]))
+                .map_err(|_| PackError::bad_index(3))?,
+            cte_type: buf[4],
+            data_status: DataStatus::try_from(buf[5]).map_err(|_| PackError::bad_index(5))?,
+            data: buf[Self::HEADER_LEN..].to_vec(),
+        })
+    }
+
+    fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(self.meta_byte_len(), buf)?;
+        self.sync_handle.pack_into(&mut buf[0..2])?;
+        buf[2] = self.tx_power.map_or(Self::NOT_AVAILABLE, u8::from);
+        buf[3] = self.rssi.map_or(Self::NOT_AVAILABLE, u8::from);
+        buf[4] = self.cte_type;
+        buf[5] = self.data_status.into();
+        buf[6] = u8::try_from(self.data.len()).map_err(|_| PackError::InvalidFields)?;
+        buf[Self::HEADER_LEN..].copy_from_slice(&self.data);
+        Ok(())
+    }
+}
+/// LE Periodic Advertising Sync Lost Event. The controller stopped receiving the periodic
+/// advertising of `sync_handle` (before the sync timeout).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PeriodicAdvertisingSyncLost {
+    pub sync_handle: SyncHandle,
+}
+impl MetaEvent for PeriodicAdvertisingSyncLost {
+    const META_CODE: MetaEventCode = MetaEventCode::PeriodicAdvertisingSyncLost;
+
+    fn meta_byte_len(&self) -> usize {
+        SyncHandle::BYTE_LEN
+    }
+
+    fn meta_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(PeriodicAdvertisingSyncLost {
+            sync_handle: SyncHandle::unpack_from(buf)?,
+        })
+    }
+
+    fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.sync_handle.pack_into(buf)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{
+        ClockAccuracy, PeriodicAdvertiserListEntry, PeriodicAdvertisingCreateSync,
+        PeriodicAdvertisingReport, PeriodicAdvertisingSyncEstablished, PeriodicAdvertisingSyncLost,
+        SyncHandle, SyncTimeout,
+    };
+    use crate::hci::command::{AsyncCommand, Command};
+    use crate::hci::le::advertise::TxPowerLevel;
+    use crate::hci::le::phy::Phy;
+    use crate::hci::le::report::DataStatus;
+    use crate::hci::le::MetaEvent;
+    use crate::hci::ErrorCode;
+    use crate::le::advertiser::PeerAddressType;
+    use crate::le::report::AddressType;
+    use crate::{BTAddress, PackError, RSSI};
+    use alloc::vec;
+    use core::convert::TryFrom;
+
+    const ADDRESS: BTAddress = BTAddress([0x01, 0x02, 0x03, 0x04, 0x05, 0x06]);
+
+    fn established() -> PeriodicAdvertisingSyncEstablished {
+        PeriodicAdvertisingSyncEstablished {
+            status: ErrorCode::Ok,
+            sync_handle: SyncHandle::new(0x0123),
+            sid: 0x05,
+            address_type: AddressType::RandomDevice,
+            address: ADDRESS,
+            phy: Phy::LE2M,
+            periodic_advertising_interval: 0x0050,
+            clock_accuracy: ClockAccuracy::Ppm50,
+        }
+    }
+    #[test]
+    fn test_create_sync_pack_unpack() {
+        let mut create = PeriodicAdvertisingCreateSync::new(PeerAddressType::Random, ADDRESS, 0x0F);
+        create.reporting_initially_disabled = true;
+        create.skip = 0x0102;
+        create.sync_timeout = SyncTimeout::new(0x4000);
+        create.sync_cte_type = 0x04;
+        let mut buf = [0_u8; PeriodicAdvertisingCreateSync::BYTE_LEN];
+        assert_eq!(create.byte_len(), buf.len());
+        create.pack_into(&mut buf).unwrap();
+        assert_eq!(
+            buf,
+            [0x02, 0x0F, 0x01, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x02, 0x01, 0x00, 0x40, 0x04]
+        );
+        assert_eq!(PeriodicAdvertisingCreateSync::unpack_from(&buf), Ok(create));
+        assert_eq!(
+            PeriodicAdvertisingCreateSync::unpack_from(&buf[1..]),
+            Err(PackError::BadLength {
+                expected: PeriodicAdvertisingCreateSync::BYTE_LEN,
+                got: PeriodicAdvertisingCreateSync::BYTE_LEN - 1,
+            })
+        );
+        assert_eq!(
+            create.pack_into(&mut buf[1..]),
+            Err(PackError::BadLength {
+                expected: PeriodicAdvertisingCreateSync::BYTE_LEN,
+                got: PeriodicAdvertisingCreateSync::BYTE_LEN - 1,
+            })
+        );
+    }
+    #[test]
+    fn test_create_sync_out_of_range() {
+        let mut create = PeriodicAdvertisingCreateSync::new(PeerAddressType::Public, ADDRESS, 0);
+        let mut buf = [0_u8; PeriodicAdvertisingCreateSync::BYTE_LEN];
+        create.pack_into(&mut buf).unwrap();
+        let good = buf;
+
+        buf[0] = 0b100;
+        assert_eq!(
+            PeriodicAdvertisingCreateSync::unpack_from(&buf),
+            Err(PackError::bad_index(0))
+        );
+        buf = good;
+        buf[1] = PeriodicAdvertisingCreateSync::MAX_SID + 1;
+        assert_eq!(
+            PeriodicAdvertisingCreateSync::unpack_from(&buf),
+            Err(PackError::bad_index(1))
+        );
+        buf = good;
+        buf[2] = 0x02;
+        assert_eq!(
+            PeriodicAdvertisingCreateSync::unpack_from(&buf),
+            Err(PackError::bad_index(2))
+        );
+        for &timeout in &[SyncTimeout::MIN_U16 - 1, SyncTimeout::MAX_U16 + 1] {
+            buf = good;
+            buf[11..13].copy_from_slice(&timeout.to_le_bytes());
+            assert_eq!(
+                PeriodicAdvertisingCreateSync::unpack_from(&buf),
+                Err(PackError::bad_index(11))
+            );
+        }
+
+        create.sid = PeriodicAdvertisingCreateSync::MAX_SID + 1;
+        assert_eq!(create.pack_into(&mut buf), Err(PackError::InvalidFields));
+    }
+    #[test]
+    fn test_sync_timeout_range() {
+        assert!(SyncTimeout::try_from(SyncTimeout::MIN_U16 - 1).is_err());
+        assert!(SyncTimeout::try_from(SyncTimeout::MAX_U16 + 1).is_err());
+        assert_eq!(
+            SyncTimeout::try_from(SyncTimeout::MIN_U16),
+            Ok(SyncTimeout::new(SyncTimeout::MIN_U16))
+        );
+        assert_eq!(SyncTimeout::new(SyncTimeout::MAX_U16).as_millis(), 163_840);
+        assert!(SyncHandle::try_from(SyncHandle::MAX_HANDLE_U16 + 1).is_err());
+        assert_eq!(
+            SyncHandle::unpack_from(&[0x00, 0x0F]),
+            Err(PackError::bad_index(0))
+        );
+    }
+    #[test]
+    fn test_advertiser_list_entry_sid() {
+        let mut entry = PeriodicAdvertiserListEntry {
+            address_type: PeerAddressType::Public,
+            address: ADDRESS,
+            sid: 0x0A,
+        };
+        let mut buf = [0_u8; PeriodicAdvertiserListEntry::BYTE_LEN];
+        entry.pack_into(&mut buf).unwrap();
+        assert_eq!(PeriodicAdvertiserListEntry::unpack_from(&buf), Ok(entry));
+        buf[7] = 0x10;
+        assert_eq!(
+            PeriodicAdvertiserListEntry::unpack_from(&buf),
+            Err(PackError::bad_index(7))
+        );
+        assert!(PeriodicAdvertiserListEntry::unpack_from(&buf[..7]).is_err());
+        entry.sid = 0x10;
+        assert_eq!(entry.pack_into(&mut buf), Err(PackError::InvalidFields));
+    }
+    #[test]
+    fn test_create_sync_completion() {
+        let create = PeriodicAdvertisingCreateSync::new(PeerAddressType::Random, ADDRESS, 0x05);
+        let mut event = established();
+        assert!(create.is_completion(&event));
+        event.address_type = AddressType::RandomIdentity;
+        assert!(create.is_completion(&event));
+        event.address_type = AddressType::PublicDevice;
+        assert!(!create.is_completion(&event));
+
+        event = established();
+        event.sid = 0x06;
+        assert!(!create.is_completion(&event));
+        event = established();
+        event.address = BTAddress([0x06, 0x05, 0x04, 0x03, 0x02, 0x01]);
+        assert!(!create.is_completion(&event));
+
+        event.status = ErrorCode::OperationCancelledByHost;
+        assert!(create.is_completion(&event));
+        let mut list = create;
+        list.use_periodic_advertiser_list = true;
+        event = established();
+        event.sid = 0x0C;
+        assert!(list.is_completion(&event));
+    }
+    #[test]
+    fn test_sync_established_pack_unpack() {
+        let event = established();
+        let mut buf = [0_u8; PeriodicAdvertisingSyncEstablished::BYTE_LEN];
+        event.meta_pack_into(&mut buf).unwrap();
+        assert_eq!(
+            PeriodicAdvertisingSyncEstablished::meta_unpack_from(&buf),
+            Ok(event)
+        );
+        assert!(PeriodicAdvertisingSyncEstablished::meta_unpack_from(&buf[1..]).is_err());
+
+        let good = buf;
+        buf[11] = 0x04;
+        assert_eq!(
+            PeriodicAdvertisingSyncEstablished::meta_unpack_from(&buf),
+            Err(PackError::bad_index(11))
+        );
+        buf = good;
+        buf[14] = 0x08;
+        assert_eq!(
+            PeriodicAdvertisingSyncEstablished::meta_unpack_from(&buf),
+            Err(PackError::bad_index(14))
+        );
+
+        // A failed sync doesn't have to fill in valid fields.
+        buf = [0xFF; PeriodicAdvertisingSyncEstablished::BYTE_LEN];
+        buf[0] = ErrorCode::OperationCancelledByHost.into();
+        let failed = PeriodicAdvertisingSyncEstablished::meta_unpack_from(&buf).unwrap();
+        assert_eq!(failed.status, ErrorCode::OperationCancelledByHost);
+        assert_eq!(failed.sync_handle, SyncHandle::default());
+        assert_eq!(failed.phy, Phy::DEFAULT);
+    }
+    #[test]
+    fn test_report_pack_unpack() {
+        let report = PeriodicAdvertisingReport {
+            sync_handle: SyncHandle::new(0x0001),
+            tx_power: Some(TxPowerLevel::new(-4)),
+            rssi: None,
+            cte_type: 0xFF,
+            data_status: DataStatus::Incomplete,
+            data: vec![0x02, 0x01, 0x06],
+        };
+        let mut buf = vec![0_u8; report.meta_byte_len()];
+        report.meta_pack_into(&mut buf).unwrap();
+        assert_eq!(
+            buf,
+            [0x01, 0x00, 0xFC, 0x7F, 0xFF, 0x01, 0x03, 0x02, 0x01, 0x06]
+        );
+        assert_eq!(
+            PeriodicAdvertisingReport::meta_unpack_from(&buf),
+            Ok(report.clone())
+        );
+        assert_eq!(
+            PeriodicAdvertisingReport::meta_unpack_from(&buf[..buf.len() - 1]),
+            Err(PackError::BadLength {
+                expected: buf.len(),
+                got: buf.len() - 1,
+            })
+        );
+        assert_eq!(
+            PeriodicAdvertisingReport::meta_unpack_from(&buf[..6]),
+            Err(PackError::BadLength {
+                expected: PeriodicAdvertisingReport::HEADER_LEN,
+                got: 6,
+            })
+        );
+
+        buf[2] = 0x7F;
+        buf[3] = (-60_i8).to_le_bytes()[0];
+        let unpacked = PeriodicAdvertisingReport::meta_unpack_from(&buf).unwrap();
+        assert_eq!(unpacked.tx_power, None);
+        assert_eq!(unpacked.rssi, Some(RSSI::new(-60)));
+        buf[5] = 0b11;
+        assert_eq!(
+            PeriodicAdvertisingReport::meta_unpack_from(&buf),
+            Err(PackError::bad_index(5))
+        );
+    }
+    #[test]
+    fn test_sync_lost_unpack() {
+        let lost = PeriodicAdvertisingSyncLost {
+            sync_handle: SyncHandle::new(0x0EFF),
+        };
+        let mut buf = [0_u8; 2];
+        lost.meta_pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0xFF, 0x0E]);
+        assert_eq!(
+            PeriodicAdvertisingSyncLost::meta_unpack_from(&buf),
+            Ok(lost)
+        );
+        assert!(PeriodicAdvertisingSyncLost::meta_unpack_from(&buf[..1]).is_err());
+    }
+}
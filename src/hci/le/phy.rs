@@ -0,0 +1,541 @@
+//! LE PHY ([`Phy`]) types shared by the extended advertising, scanning and connection commands
+//! and the PHY management commands ([`ReadPhy`], [`SetDefaultPhy`], [`SetPhy`]) and
+//! [`PhyUpdateComplete`] event.
+use crate::bytes::ToFromBytesEndian;
+use crate::hci::command::{AsyncCommand, Command};
+use crate::hci::event::{CommandComplete, CommandStatus, ReturnParameters, StatusReturn};
+use crate::hci::le::{LEControllerOpcode, MetaEvent, MetaEventCode};
+use crate::hci::{ConnectionHandle, ErrorCode, Opcode};
+use crate::ConversionError;
+use crate::PackError;
+use core::convert::TryFrom;
+
+/// LE Physical Layer.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum Phy {
+    LE1M = 0x01,
+    LE2M = 0x02,
+    LECoded = 0x03,
+}
+impl Phy {
+    pub const DEFAULT: Phy = Phy::LE1M;
+    /// Bit of the PHY in a [`PhyMask`].
+    pub fn mask_bit(self) -> u8 {
+        1 << (self as u8 - 1)
+    }
+}
+impl Default for Phy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+impl From<Phy> for u8 {
+    fn from(p: Phy) -> Self {
+        p as u8
+    }
+}
+impl TryFrom<u8> for Phy {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01 => Ok(Phy::LE1M),
+            0x02 => Ok(Phy::LE2M),
+            0x03 => Ok(Phy::LECoded),
+            _ => Err(ConversionError(())),
+        }
+    }
+}
+/// Set of PHYs the host prefers to use (in [`SetDefaultPhy`] and [`SetPhy`]).
+/// ```
+/// use btle::hci::le::phy::{Phy, PhyMask};
+/// let mut mask = PhyMask::ZEROED;
+/// mask.enable(Phy::LE1M);
+/// mask.enable(Phy::LECoded);
+/// assert_eq!(u8::from(mask), 0b101);
+/// assert!(mask.contains(Phy::LECoded));
+/// assert!(!mask.contains(Phy::LE2M));
+/// ```
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct PhyMask(u8);
+impl PhyMask {
+    pub const ZEROED: PhyMask = PhyMask(0);
+    pub const LE_1M: PhyMask = PhyMask(0b001);
+    pub const LE_2M: PhyMask = PhyMask(0b010);
+    pub const LE_CODED: PhyMask = PhyMask(0b100);
+    pub const ALL: PhyMask = PhyMask(0b111);
+    pub fn enable(&mut self, phy: Phy) {
+        self.0 |= phy.mask_bit();
+    }
+    pub fn disable(&mut self, phy: Phy) {
+        self.0 &= !phy.mask_bit();
+    }
+    pub fn contains(self, phy: Phy) -> bool {
+        self.0 & phy.mask_bit() != 0
+    }
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+impl From<Phy> for PhyMask {
+    fn from(phy: Phy) -> Self {
+        PhyMask(phy.mask_bit())
+    }
+}
+impl From<PhyMask> for u8 {
+    fn from(m: PhyMask) -> Self {
+        m.0
+    }
+}
+impl TryFrom<u8> for PhyMask {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value & !Self::ALL.0 == 0 {
+            Ok(PhyMask(value))
+        } else {
+            Err(ConversionError(()))
+        }
+    }
+}
+/// Preferred TX and RX PHYs. `None` means the host has no preference.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct PhyPreference {
+    pub tx_phys: Option<PhyMask>,
+    pub rx_phys: Option<PhyMask>,
+}
+impl PhyPreference {
+    /// all_phys (1) + tx_phys (1) + rx_phys (1)
+    pub const BYTE_LEN: usize = 3;
+    const NO_TX_PREFERENCE_BIT: u8 = 1 << 0;
+    const NO_RX_PREFERENCE_BIT: u8 = 1 << 1;
+    pub const NO_PREFERENCE: PhyPreference = PhyPreference {
+        tx_phys: None,
+        rx_phys: None,
+    };
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        // A preference must contain at least one PHY.
+        if self.tx_phys.map_or(false, PhyMask::is_empty)
+            || self.rx_phys.map_or(false, PhyMask::is_empty)
+        {
+            return Err(PackError::InvalidFields);
+        }
+        buf[0] = self.tx_phys.map_or(Self::NO_TX_PREFERENCE_BIT, |_| 0)
+            | self.rx_phys.map_or(Self::NO_RX_PREFERENCE_BIT, |_| 0);
+        buf[1] = self.tx_phys.unwrap_or_default().into();
+        buf[2] = self.rx_phys.unwrap_or_default().into();
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        if buf[0] & !(Self::NO_TX_PREFERENCE_BIT | Self::NO_RX_PREFERENCE_BIT) != 0 {
+            return Err(PackError::bad_index(0));
+        }
+        let tx_phys = PhyMask::try_from(buf[1]).map_err(|_| PackError::bad_index(1))?;
+        let rx_phys = PhyMask::try_from(buf[2]).map_err(|_| PackError::bad_index(2))?;
+        Ok(PhyPreference {
+            tx_phys: if buf[0] & Self::NO_TX_PREFERENCE_BIT == 0 {
+                Some(tx_phys)
+            } else {
+                None
+            },
+            rx_phys: if buf[0] & Self::NO_RX_PREFERENCE_BIT == 0 {
+                Some(rx_phys)
+            } else {
+                None
+            },
+        })
+    }
+}
+/// Coding preferred when transmitting on the LE Coded PHY.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum CodedPhyOptions {
+    NoPreference = 0x00,
+    /// S=2 coding (500 kb/s).
+    S2 = 0x01,
+    /// S=8 coding (125 kb/s, longest range).
+    S8 = 0x02,
+}
+impl CodedPhyOptions {
+    pub const DEFAULT: CodedPhyOptions = CodedPhyOptions::NoPreference;
+}
+impl Default for CodedPhyOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+impl From<CodedPhyOptions> for u16 {
+    fn from(o: CodedPhyOptions) -> Self {
+        o as u16
+    }
+}
+impl TryFrom<u16> for CodedPhyOptions {
+    type Error = ConversionError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(CodedPhyOptions::NoPreference),
+            0x01 => Ok(CodedPhyOptions::S2),
+            0x02 => Ok(CodedPhyOptions::S8),
+            _ => Err(ConversionError(())),
+        }
+    }
+}
+/// LE Read PHY Command. Reads the current TX and RX PHY of a connection.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ReadPhy {
+    pub handle: ConnectionHandle,
+}
+impl Command for ReadPhy {
+    type Return = CommandComplete<ReadPhyReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ReadPhy.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        ConnectionHandle::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.handle.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(ReadPhy {
+            handle: ConnectionHandle::unpack_from(buf)?,
+        })
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ReadPhyReturn {
+    pub status: ErrorCode,
+    pub handle: ConnectionHandle,
+    pub tx_phy: Phy,
+    pub rx_phy: Phy,
+}
+impl ReadPhyReturn {
+    pub const BYTE_LEN: usize = 1 + ConnectionHandle::BYTE_LEN + 1 + 1;
+}
+impl ReturnParameters for ReadPhyReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.handle.pack_into(&mut buf[1..3])?;
+        buf[3] = self.tx_phy.into();
+        buf[4] = self.rx_phy.into();
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let status = ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?;
+        let (tx_phy, rx_phy) = unpack_phys(status, &buf[3..5], 3)?;
+        Ok(ReadPhyReturn {
+            status,
+            handle: ConnectionHandle::unpack_from(&buf[1..3])?,
+            tx_phy,
+            rx_phy,
+        })
+    }
+}
+/// Unpacks the TX and RX PHY at `buf[0]` and `buf[1]` (`index` is the offset of `buf` for
+/// errors). The PHYs are only valid (and checked) if `status` is `ErrorCode::Ok`.
+fn unpack_phys(status: ErrorCode, buf: &[u8], index: usize) -> Result<(Phy, Phy), PackError> {
+    let phy = |i: usize| match Phy::try_from(buf[i]) {
+        Ok(phy) => Ok(phy),
+        Err(_) if status != ErrorCode::Ok => Ok(Phy::DEFAULT),
+        Err(_) => Err(PackError::bad_index(index + i)),
+    };
+    Ok((phy(0)?, phy(1)?))
+}
+/// LE Set Default PHY Command. Sets the preferred PHYs for new connections.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct SetDefaultPhy(pub PhyPreference);
+impl Command for SetDefaultPhy {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetDefaultPhy.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        PhyPreference::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(SetDefaultPhy(PhyPreference::unpack_from(buf)?))
+    }
+}
+/// LE Set PHY Command. Requests a PHY change on a connection. Finishes with a
+/// [`PhyUpdateComplete`] event (even if the PHYs don't change).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SetPhy {
+    pub handle: ConnectionHandle,
+    pub preference: PhyPreference,
+    pub coded_phy_options: CodedPhyOptions,
+}
+impl SetPhy {
+    pub const BYTE_LEN: usize = ConnectionHandle::BYTE_LEN + PhyPreference::BYTE_LEN + 2;
+}
+impl Command for SetPhy {
+    type Return = CommandStatus;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetPhy.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        self.handle.pack_into(&mut buf[0..2])?;
+        self.preference.pack_into(&mut buf[2..5])?;
+        buf[5..7].copy_from_slice(&u16::from(self.coded_phy_options).to_bytes_le());
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(SetPhy {
+            handle: ConnectionHandle::unpack_from(&buf[0..2])?,
+            preference: PhyPreference::unpack_from(&buf[2..5]).map_err(|e| match e {
+                PackError::BadBytes { index: Some(i) } => PackError::bad_index(2 + i),
+                e => e,
+            })?,
+            coded_phy_options: CodedPhyOptions::try_from(u16::from_le_bytes([buf[5], buf[6]]))
+                .map_err(|_| PackError::bad_index(5))?,
+        })
+    }
+}
+impl AsyncCommand for SetPhy {
+    type Completion = PhyUpdateComplete;
+
+    fn is_completion(&self, completion: &Self::Completion) -> bool {
+        completion.handle == self.handle
+    }
+}
+/// LE PHY Update Complete Event. Sent when the PHYs of a connection change (requested locally
+/// with [`SetPhy`] or by the remote device). See
+/// [`Connection::apply_phy_update`](crate::hci::le::connection::Connection::apply_phy_update).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PhyUpdateComplete {
+    pub status: ErrorCode,
+    pub handle: ConnectionHandle,
+    pub tx_phy: Phy,
+    pub rx_phy: Phy,
+}
+impl PhyUpdateComplete {
+    pub const BYTE_LEN: usize = 1 + ConnectionHandle::BYTE_LEN + 1 + 1;
+}
+impl MetaEvent for PhyUpdateComplete {
+    const META_CODE: MetaEventCode = MetaEventCode::PHYUpdateCompleteEvent;
+
+    fn meta_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn meta_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let status = ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?;
+        let (tx_phy, rx_phy) = unpack_phys(status, &buf[3..5], 3)?;
+        Ok(PhyUpdateComplete {
+            status,
+            handle: ConnectionHandle::unpack_from(&buf[1..3])?,
+            tx_phy,
+            rx_phy,
+        })
+    }
+
+    fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.handle.pack_into(&mut buf[1..3])?;
+        buf[3] = self.tx_phy.into();
+        buf[4] = self.rx_phy.into();
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{
+        CodedPhyOptions, Phy, PhyMask, PhyPreference, PhyUpdateComplete, ReadPhy, ReadPhyReturn,
+        SetDefaultPhy, SetPhy,
+    };
+    use crate::hci::command::{AsyncCommand, Command};
+    use crate::hci::event::ReturnParameters;
+    use crate::hci::le::MetaEvent;
+    use crate::hci::{ConnectionHandle, ErrorCode};
+    use crate::PackError;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn test_phy_mask() {
+        for &phy in &[Phy::LE1M, Phy::LE2M, Phy::LECoded] {
+            let mask = PhyMask::from(phy);
+            assert!(mask.contains(phy));
+            assert_eq!(Phy::try_from(u8::from(phy)), Ok(phy));
+            let mut all = PhyMask::ALL;
+            all.disable(phy);
+            assert!(!all.contains(phy));
+        }
+        assert!(Phy::try_from(0x00).is_err());
+        assert!(Phy::try_from(0x04).is_err());
+        assert_eq!(PhyMask::try_from(0b111), Ok(PhyMask::ALL));
+        assert!(PhyMask::try_from(0b1000).is_err());
+        assert!(PhyMask::ZEROED.is_empty());
+    }
+    #[test]
+    fn test_phy_preference_pack_unpack() {
+        let preference = PhyPreference {
+            tx_phys: Some(PhyMask::LE_2M),
+            rx_phys: None,
+        };
+        let command = SetDefaultPhy(preference);
+        let mut buf = [0_u8; PhyPreference::BYTE_LEN];
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0b10, 0b010, 0b000]);
+        assert_eq!(SetDefaultPhy::unpack_from(&buf), Ok(command));
+        assert!(SetDefaultPhy::unpack_from(&buf[..2]).is_err());
+
+        assert_eq!(
+            SetDefaultPhy::unpack_from(&[0b100, 0, 0]),
+            Err(PackError::bad_index(0))
+        );
+        assert_eq!(
+            SetDefaultPhy::unpack_from(&[0, 0b1000, 0b001]),
+            Err(PackError::bad_index(1))
+        );
+        assert_eq!(
+            SetDefaultPhy::unpack_from(&[0, 0b001, 0b1000]),
+            Err(PackError::bad_index(2))
+        );
+        // A preference can't be empty.
+        let empty = SetDefaultPhy(PhyPreference {
+            tx_phys: Some(PhyMask::ZEROED),
+            rx_phys: None,
+        });
+        assert_eq!(empty.pack_into(&mut buf), Err(PackError::InvalidFields));
+    }
+    #[test]
+    fn test_set_phy_pack_unpack() {
+        let command = SetPhy {
+            handle: ConnectionHandle::new(0x0040),
+            preference: PhyPreference {
+                tx_phys: Some(PhyMask::ALL),
+                rx_phys: Some(PhyMask::LE_CODED),
+            },
+            coded_phy_options: CodedPhyOptions::S8,
+        };
+        let mut buf = [0_u8; SetPhy::BYTE_LEN];
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x40, 0x00, 0x00, 0b111, 0b100, 0x02, 0x00]);
+        assert_eq!(SetPhy::unpack_from(&buf), Ok(command));
+        assert_eq!(
+            SetPhy::unpack_from(&buf[..6]),
+            Err(PackError::BadLength {
+                expected: SetPhy::BYTE_LEN,
+                got: 6
+            })
+        );
+        let good = buf;
+
+        buf[4] = 0xF0;
+        assert_eq!(SetPhy::unpack_from(&buf), Err(PackError::bad_index(4)));
+        buf = good;
+        buf[5] = 0x03;
+        assert_eq!(SetPhy::unpack_from(&buf), Err(PackError::bad_index(5)));
+    }
+    #[test]
+    fn test_set_phy_completion() {
+        let command = SetPhy {
+            handle: ConnectionHandle::new(0x0001),
+            preference: PhyPreference::NO_PREFERENCE,
+            coded_phy_options: CodedPhyOptions::DEFAULT,
+        };
+        let mut update = PhyUpdateComplete {
+            status: ErrorCode::Ok,
+            handle: ConnectionHandle::new(0x0001),
+            tx_phy: Phy::LE2M,
+            rx_phy: Phy::LE2M,
+        };
+        assert!(command.is_completion(&update));
+        update.handle = ConnectionHandle::new(0x0002);
+        assert!(!command.is_completion(&update));
+    }
+    #[test]
+    fn test_read_phy_pack_unpack() {
+        let command = ReadPhy {
+            handle: ConnectionHandle::new(0x0EFF),
+        };
+        let mut buf = [0_u8; ConnectionHandle::BYTE_LEN];
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(ReadPhy::unpack_from(&buf), Ok(command));
+
+        let ret = ReadPhyReturn {
+            status: ErrorCode::Ok,
+            handle: ConnectionHandle::new(0x0001),
+            tx_phy: Phy::LE1M,
+            rx_phy: Phy::LECoded,
+        };
+        let mut buf = [0_u8; ReadPhyReturn::BYTE_LEN];
+        ret.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x00, 0x01, 0x00, 0x01, 0x03]);
+        assert_eq!(ReadPhyReturn::unpack_from(&buf), Ok(ret));
+        assert!(ReadPhyReturn::unpack_from(&buf[..4]).is_err());
+        buf[4] = 0x04;
+        assert_eq!(
+            ReadPhyReturn::unpack_from(&buf),
+            Err(PackError::bad_index(4))
+        );
+    }
+    #[test]
+    fn test_phy_update_complete_pack_unpack() {
+        let event = PhyUpdateComplete {
+            status: ErrorCode::Ok,
+            handle: ConnectionHandle::new(0x0003),
+            tx_phy: Phy::LE2M,
+            rx_phy: Phy::LE1M,
+        };
+        let mut buf = [0_u8; PhyUpdateComplete::BYTE_LEN];
+        event.meta_pack_into(&mut buf).unwrap();
+        assert_eq!(PhyUpdateComplete::meta_unpack_from(&buf), Ok(event));
+        assert!(PhyUpdateComplete::meta_unpack_from(&buf[..4]).is_err());
+        buf[3] = 0x00;
+        assert_eq!(
+            PhyUpdateComplete::meta_unpack_from(&buf),
+            Err(PackError::bad_index(3))
+        );
+        // The PHYs of a failed update aren't checked.
+        buf[0] = ErrorCode::UnsupportedFeatureOrParameterValue.into();
+        let failed = PhyUpdateComplete::meta_unpack_from(&buf).unwrap();
+        assert_eq!(failed.tx_phy, Phy::DEFAULT);
+        assert_eq!(failed.rx_phy, Phy::LE1M);
+    }
+}
@@ -0,0 +1,522 @@
+//! LE Resolving List commands ([`AddDeviceToResolvingList`], [`RemoveDeviceFromResolvingList`],
+//! [`ClearResolvingList`], [`ReadResolvingListSize`]) and the other controller based privacy
+//! commands ([`ReadPeerResolvableAddress`], [`ReadLocalResolvableAddress`],
+//! [`SetAddressResolutionEnable`], [`SetResolvablePrivateAddressTimeout`]).
+use crate::bytes::ToFromBytesEndian;
+use crate::hci::command::Command;
+use crate::hci::event::{CommandComplete, ReturnParameters, StatusReturn};
+use crate::hci::le::LEControllerOpcode;
+use crate::hci::{ErrorCode, Opcode};
+use crate::le::privacy::{IdentityAddress, IdentityResolvingKey};
+use crate::{BTAddress, ConversionError, PackError, BT_ADDRESS_LEN};
+use core::convert::TryFrom;
+use core::time::Duration;
+
+/// Single device in the Resolving List. The controller uses `peer_irk` to resolve the addresses
+/// of the peer and `local_irk` to generate our resolvable private addresses when talking to it.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ResolvingListEntry {
+    pub peer_identity: IdentityAddress,
+    pub peer_irk: IdentityResolvingKey,
+    pub local_irk: IdentityResolvingKey,
+}
+impl ResolvingListEntry {
+    pub const BYTE_LEN: usize = IdentityAddress::BYTE_LEN + IdentityResolvingKey::BYTE_LEN * 2;
+    pub fn new(
+        peer_identity: IdentityAddress,
+        peer_irk: IdentityResolvingKey,
+        local_irk: IdentityResolvingKey,
+    ) -> ResolvingListEntry {
+        ResolvingListEntry {
+            peer_identity,
+            peer_irk,
+            local_irk,
+        }
+    }
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let (identity, keys) = buf.split_at_mut(IdentityAddress::BYTE_LEN);
+        self.peer_identity.pack_into(identity)?;
+        let (peer_irk, local_irk) = keys.split_at_mut(IdentityResolvingKey::BYTE_LEN);
+        self.peer_irk.pack_into(peer_irk)?;
+        self.local_irk.pack_into(local_irk)
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let (identity, keys) = buf.split_at(IdentityAddress::BYTE_LEN);
+        let (peer_irk, local_irk) = keys.split_at(IdentityResolvingKey::BYTE_LEN);
+        Ok(ResolvingListEntry {
+            peer_identity: IdentityAddress::unpack_from(identity)?,
+            peer_irk: IdentityResolvingKey::unpack_from(peer_irk)?,
+            local_irk: IdentityResolvingKey::unpack_from(local_irk)?,
+        })
+    }
+}
+/// LE Add Device To Resolving List Command. Address resolution must be disabled (unless the
+/// controller isn't advertising, scanning or connecting).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct AddDeviceToResolvingList(pub ResolvingListEntry);
+impl Command for AddDeviceToResolvingList {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::AddDeviceToResolvingList.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        ResolvingListEntry::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(AddDeviceToResolvingList(ResolvingListEntry::unpack_from(
+            buf,
+        )?))
+    }
+}
+/// LE Remove Device From Resolving List Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct RemoveDeviceFromResolvingList(pub IdentityAddress);
+impl Command for RemoveDeviceFromResolvingList {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::RemoveDeviceFromResolvingList.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        IdentityAddress::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(RemoveDeviceFromResolvingList(IdentityAddress::unpack_from(
+            buf,
+        )?))
+    }
+}
+/// LE Clear Resolving List Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ClearResolvingList {}
+impl Command for ClearResolvingList {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ClearResolvingList.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ClearResolvingList {})
+    }
+}
+/// LE Read Resolving List Size Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadResolvingListSize {}
+impl Command for ReadResolvingListSize {
+    type Return = CommandComplete<ResolvingListSizeReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ReadResolvingListSize.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadResolvingListSize {})
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ResolvingListSizeReturn {
+    pub status: ErrorCode,
+    /// Total number of Resolving List entries that can be stored in the controller.
+    pub size: u8,
+}
+impl ResolvingListSizeReturn {
+    pub const BYTE_LEN: usize = 2;
+}
+impl ReturnParameters for ResolvingListSizeReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        buf[1] = self.size;
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(ResolvingListSizeReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            size: buf[1],
+        })
+    }
+}
+/// LE Read Peer Resolvable Address Command. Reads the current resolvable private address of a
+/// peer in the Resolving List.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ReadPeerResolvableAddress(pub IdentityAddress);
+impl Command for ReadPeerResolvableAddress {
+    type Return = CommandComplete<ResolvableAddressReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ReadPeerResolvableAddress.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        IdentityAddress::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(ReadPeerResolvableAddress(IdentityAddress::unpack_from(
+            buf,
+        )?))
+    }
+}
+/// LE Read Local Resolvable Address Command. Reads our current resolvable private address used
+/// with a peer in the Resolving List.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ReadLocalResolvableAddress(pub IdentityAddress);
+impl Command for ReadLocalResolvableAddress {
+    type Return = CommandComplete<ResolvableAddressReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ReadLocalResolvableAddress.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        IdentityAddress::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(ReadLocalResolvableAddress(IdentityAddress::unpack_from(
+            buf,
+        )?))
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ResolvableAddressReturn {
+    pub status: ErrorCode,
+    pub address: BTAddress,
+}
+impl ResolvableAddressReturn {
+    pub const BYTE_LEN: usize = 1 + BT_ADDRESS_LEN;
+}
+impl ReturnParameters for ResolvableAddressReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.address.pack_into(&mut buf[1..])
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(ResolvableAddressReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            address: BTAddress::unpack_from(&buf[1..])?,
+        })
+    }
+}
+/// LE Set Address Resolution Enable Command. Enables resolving addresses with the Resolving
+/// List (and generating our own resolvable private addresses).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct SetAddressResolutionEnable {
+    pub is_enabled: bool,
+}
+impl Command for SetAddressResolutionEnable {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetAddressResolutionEnable.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        1
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(1, buf)?;
+        buf[0] = self.is_enabled.into();
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(1, buf)?;
+        let is_enabled = match buf[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(PackError::bad_index(0)),
+        };
+        Ok(SetAddressResolutionEnable { is_enabled })
+    }
+}
+/// How long the controller uses a resolvable private address before generating a new one (in
+/// seconds). Range 0x0001 to 0x0E10 (1 s to 1 hour).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct RPATimeout(u16);
+impl RPATimeout {
+    pub const BYTE_LEN: usize = 2;
+    pub const MIN_U16: u16 = 0x0001;
+    pub const MAX_U16: u16 = 0x0E10;
+    pub const MIN: RPATimeout = RPATimeout(Self::MIN_U16);
+    pub const MAX: RPATimeout = RPATimeout(Self::MAX_U16);
+    /// 15 minutes.
+    pub const DEFAULT: RPATimeout = RPATimeout(0x0384);
+    /// Creates a new `RPATimeout`.
+    /// # Panics
+    /// Panics if `seconds < RPATimeout::MIN_U16 || seconds > RPATimeout::MAX_U16`.
+    pub fn new(seconds: u16) -> RPATimeout {
+        assert!(
+            seconds >= Self::MIN_U16 && seconds <= Self::MAX_U16,
+            "invalid rpa timeout '{}'",
+            seconds
+        );
+        RPATimeout(seconds)
+    }
+    pub fn as_duration(self) -> Duration {
+        Duration::from_secs(u64::from(self.0))
+    }
+}
+impl Default for RPATimeout {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+impl From<RPATimeout> for u16 {
+    fn from(t: RPATimeout) -> Self {
+        t.0
+    }
+}
+impl TryFrom<u16> for RPATimeout {
+    type Error = ConversionError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        if value >= Self::MIN_U16 && value <= Self::MAX_U16 {
+            Ok(RPATimeout(value))
+        } else {
+            Err(ConversionError(()))
+        }
+    }
+}
+/// LE Set Resolvable Private Address Timeout Command.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct SetResolvablePrivateAddressTimeout(pub RPATimeout);
+impl Command for SetResolvablePrivateAddressTimeout {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetResolvablePrivateAddressTimeout.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        RPATimeout::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(RPATimeout::BYTE_LEN, buf)?;
+        buf.copy_from_slice(&u16::from(self.0).to_bytes_le());
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(RPATimeout::BYTE_LEN, buf)?;
+        Ok(SetResolvablePrivateAddressTimeout(
+            RPATimeout::try_from(u16::from_le_bytes([buf[0], buf[1]]))
+                .map_err(|_| PackError::bad_index(0))?,
+        ))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{
+        AddDeviceToResolvingList, RPATimeout, RemoveDeviceFromResolvingList,
+        ResolvableAddressReturn, ResolvingListEntry, ResolvingListSizeReturn,
+        SetAddressResolutionEnable, SetResolvablePrivateAddressTimeout,
+    };
+    use crate::hci::command::Command;
+    use crate::hci::event::ReturnParameters;
+    use crate::hci::ErrorCode;
+    use crate::le::advertiser::PeerAddressType;
+    use crate::le::privacy::{IdentityAddress, IdentityResolvingKey};
+    use crate::{BTAddress, PackError};
+    use core::convert::TryFrom;
+
+    fn identity() -> IdentityAddress {
+        IdentityAddress::new(
+            PeerAddressType::Random,
+            BTAddress([0x01, 0x02, 0x03, 0x04, 0x05, 0xC6]),
+        )
+    }
+    #[test]
+    fn test_add_device_pack_unpack() {
+        let mut peer_irk = [0_u8; 16];
+        for (i, b) in peer_irk.iter_mut().enumerate() {
+            *b = u8::try_from(i).unwrap();
+        }
+        let command = AddDeviceToResolvingList(ResolvingListEntry::new(
+            identity(),
+            IdentityResolvingKey(peer_irk),
+            IdentityResolvingKey::ZEROED,
+        ));
+        let mut buf = [0_u8; ResolvingListEntry::BYTE_LEN];
+        assert_eq!(command.byte_len(), 39);
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(buf[..7], [0x01, 0x01, 0x02, 0x03, 0x04, 0x05, 0xC6]);
+        assert_eq!(buf[7..23], peer_irk);
+        assert_eq!(buf[23..], [0_u8; 16]);
+        assert_eq!(AddDeviceToResolvingList::unpack_from(&buf), Ok(command));
+        assert_eq!(
+            AddDeviceToResolvingList::unpack_from(&buf[..38]),
+            Err(PackError::BadLength {
+                expected: ResolvingListEntry::BYTE_LEN,
+                got: 38
+            })
+        );
+        assert!(command.pack_into(&mut buf[1..]).is_err());
+        buf[0] = 0x02;
+        assert_eq!(
+            AddDeviceToResolvingList::unpack_from(&buf),
+            Err(PackError::bad_index(0))
+        );
+    }
+    #[test]
+    fn test_remove_device_pack_unpack() {
+        let command = RemoveDeviceFromResolvingList(identity());
+        let mut buf = [0_u8; IdentityAddress::BYTE_LEN];
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(
+            RemoveDeviceFromResolvingList::unpack_from(&buf),
+            Ok(command)
+        );
+        assert!(RemoveDeviceFromResolvingList::unpack_from(&buf[..6]).is_err());
+        buf[0] = 0x03;
+        assert_eq!(
+            RemoveDeviceFromResolvingList::unpack_from(&buf),
+            Err(PackError::bad_index(0))
+        );
+    }
+    #[test]
+    fn test_privacy_returns() {
+        let size = ResolvingListSizeReturn {
+            status: ErrorCode::Ok,
+            size: 8,
+        };
+        let mut buf = [0_u8; ResolvingListSizeReturn::BYTE_LEN];
+        size.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x00, 0x08]);
+        assert_eq!(ResolvingListSizeReturn::unpack_from(&buf), Ok(size));
+        assert!(ResolvingListSizeReturn::unpack_from(&buf[..1]).is_err());
+
+        let address = ResolvableAddressReturn {
+            status: ErrorCode::NoConnection,
+            address: BTAddress([0xAA, 0xFB, 0x0D, 0x94, 0x81, 0x70]),
+        };
+        let mut buf = [0_u8; ResolvableAddressReturn::BYTE_LEN];
+        address.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x02, 0xAA, 0xFB, 0x0D, 0x94, 0x81, 0x70]);
+        assert_eq!(ResolvableAddressReturn::unpack_from(&buf), Ok(address));
+        assert!(ResolvableAddressReturn::unpack_from(&buf[..6]).is_err());
+    }
+    #[test]
+    fn test_set_address_resolution_enable() {
+        let command = SetAddressResolutionEnable { is_enabled: true };
+        let mut buf = [0_u8; 1];
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x01]);
+        assert_eq!(SetAddressResolutionEnable::unpack_from(&buf), Ok(command));
+        assert_eq!(
+            SetAddressResolutionEnable::unpack_from(&[0x02]),
+            Err(PackError::bad_index(0))
+        );
+        assert!(SetAddressResolutionEnable::unpack_from(&[]).is_err());
+    }
+    #[test]
+    fn test_rpa_timeout_range() {
+        assert!(RPATimeout::try_from(RPATimeout::MIN_U16 - 1).is_err());
+        assert!(RPATimeout::try_from(RPATimeout::MAX_U16 + 1).is_err());
+        assert_eq!(RPATimeout::MAX.as_duration().as_secs(), 60 * 60);
+        assert_eq!(RPATimeout::DEFAULT.as_duration().as_secs(), 15 * 60);
+
+        let command = SetResolvablePrivateAddressTimeout(RPATimeout::MAX);
+        let mut buf = [0_u8; RPATimeout::BYTE_LEN];
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x10, 0x0E]);
+        assert_eq!(
+            SetResolvablePrivateAddressTimeout::unpack_from(&buf),
+            Ok(command)
+        );
+        assert!(SetResolvablePrivateAddressTimeout::unpack_from(&buf[..1]).is_err());
+        for &seconds in &[0x0000_u16, 0x0E11] {
+            assert_eq!(
+                SetResolvablePrivateAddressTimeout::unpack_from(&seconds.to_le_bytes()),
+                Err(PackError::bad_index(0))
+            );
+        }
+    }
+}
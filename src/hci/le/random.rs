@@ -1,9 +1,9 @@
-//! LE [`Rand`] command and return parameters.
+//! LE [`Rand`] command and return parameters and the [`SetRandomAddress`] command.
 use crate::hci::command::Command;
-use crate::hci::event::{CommandComplete, ReturnParameters};
+use crate::hci::event::{CommandComplete, ReturnParameters, StatusReturn};
 use crate::hci::le::LEControllerOpcode;
 use crate::hci::{ErrorCode, Opcode};
-use crate::PackError;
+use crate::{BTAddress, PackError, BT_ADDRESS_LEN};
 use core::convert::{TryFrom, TryInto};
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
@@ -62,3 +62,97 @@ impl ReturnParameters for RandReturn {
         })
     }
 }
+/// Set the random device address used by the controller when `OwnAddressType::RandomDevice` is
+/// selected for advertising, scanning or initiating. See [`BTAddress::new_static_random`] and
+/// [`BTAddress::new_non_resolvable_private`] for building valid random addresses.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SetRandomAddress(pub BTAddress);
+impl Command for SetRandomAddress {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::SetRandomAddress.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        BT_ADDRESS_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(SetRandomAddress(BTAddress::unpack_from(buf)?))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::SetRandomAddress;
+    use crate::hci::command::Command;
+    use crate::{BTAddress, PackError, BT_ADDRESS_LEN};
+
+    #[test]
+    fn test_set_random_address_pack_unpack() {
+        let address = BTAddress([0x01, 0x02, 0x03, 0x04, 0x05, 0xC6]);
+        let mut buf = [0_u8; BT_ADDRESS_LEN];
+        SetRandomAddress(address).pack_into(&mut buf).unwrap();
+        assert_eq!(buf, address.0);
+        assert_eq!(
+            SetRandomAddress::unpack_from(&buf).unwrap(),
+            SetRandomAddress(address)
+        );
+        assert_eq!(
+            SetRandomAddress::unpack_from(&buf[..BT_ADDRESS_LEN - 1]),
+            Err(PackError::BadLength {
+                expected: BT_ADDRESS_LEN,
+                got: BT_ADDRESS_LEN - 1
+            })
+        );
+        assert!(SetRandomAddress(address)
+            .pack_into(&mut [0_u8; BT_ADDRESS_LEN + 1])
+            .is_err());
+    }
+    #[test]
+    fn test_random_address_type_bits() {
+        let random = [0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0x7F];
+        let address = BTAddress::new_static_random(random).unwrap();
+        assert_eq!(address.0, [0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF]);
+        let address = BTAddress::new_non_resolvable_private(random).unwrap();
+        assert_eq!(address.0, [0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0x3F]);
+    }
+    #[test]
+    fn test_random_address_all_zeros_or_ones() {
+        // Only the two type bits differ, the 46 random bits are all 0s or all 1s.
+        for random in &[[0x00; BT_ADDRESS_LEN], [0xFF; BT_ADDRESS_LEN]] {
+            assert_eq!(BTAddress::new_static_random(*random), None);
+            assert_eq!(BTAddress::new_non_resolvable_private(*random), None);
+        }
+        assert!(BTAddress::new_static_random([0x01, 0, 0, 0, 0, 0]).is_some());
+        assert!(
+            BTAddress::new_non_resolvable_private([0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]).is_some()
+        );
+    }
+    #[test]
+    fn test_generate_random_address_retries() {
+        // The first fill is invalid (all 0s) so `fill` has to be called again.
+        let mut calls = 0_u8;
+        let address = BTAddress::generate_static_random(|buf| {
+            calls += 1;
+            for b in buf.iter_mut() {
+                *b = if calls == 1 { 0x00 } else { calls };
+            }
+        });
+        assert_eq!(calls, 2);
+        assert_eq!(address.0, [0x02, 0x02, 0x02, 0x02, 0x02, 0xC2]);
+        let address = BTAddress::generate_non_resolvable_private(|buf| {
+            for b in buf.iter_mut() {
+                *b = 0xC1;
+            }
+        });
+        assert_eq!(address.0, [0xC1, 0xC1, 0xC1, 0xC1, 0xC1, 0x01]);
+    }
+}
@@ -1,9 +1,14 @@
-//! LE [`AdvertisingReport`] and [`ReportInfo`] types.
+//! LE [`AdvertisingReport`], [`DirectedAdvertisingReport`], [`ExtendedAdvertisingReport`] and
+//! [`ReportInfo`] types.
 use crate::bytes::Storage;
+use crate::hci::le::advertise::TxPowerLevel;
+use crate::hci::le::phy::Phy;
 use crate::hci::le::{MetaEvent, MetaEventCode};
 use crate::le::advertisement::{RawAdvertisement, StaticAdvBuffer, MAX_ADV_LEN};
 use crate::le::report::{AddressType, EventType, NumReports, ReportInfo};
+use crate::ConversionError;
 use crate::{BTAddress, PackError, BT_ADDRESS_LEN, RSSI};
+use alloc::vec::Vec;
 use core::convert::TryFrom;
 
 pub struct AdvertisingReport<T: AsRef<[ReportInfo<B>]>, B: AsRef<[u8]> = StaticAdvBuffer> {
@@ -174,3 +179,652 @@ impl<Buf: AsRef<[ReportInfo<ReportBuf>]>, ReportBuf: AsRef<[u8]> + Clone> Iterat
         Some(report.clone())
     }
 }
+/// One directed advertisement (`ADV_DIRECT_IND`) of a [`DirectedAdvertisingReport`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct DirectedReportInfo {
+    pub event_type: EventType,
+    pub address_type: AddressType,
+    pub address: BTAddress,
+    /// Address type of the advertisement's target (us).
+    pub direct_address_type: AddressType,
+    /// Target address of the advertisement (a resolvable private address the controller
+    /// couldn't resolve).
+    pub direct_address: BTAddress,
+    pub rssi: Option<RSSI>,
+}
+impl DirectedReportInfo {
+    pub const BYTE_LEN: usize = 1 + 1 + BT_ADDRESS_LEN + 1 + BT_ADDRESS_LEN + 1;
+    const RSSI_NOT_AVAILABLE: u8 = 0x7F;
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.event_type.into();
+        buf[1] = self.address_type.into();
+        self.address.pack_into(&mut buf[2..2 + BT_ADDRESS_LEN])?;
+        let buf = &mut buf[2 + BT_ADDRESS_LEN..];
+        buf[0] = self.direct_address_type.into();
+        self.direct_address
+            .pack_into(&mut buf[1..1 + BT_ADDRESS_LEN])?;
+        buf[1 + BT_ADDRESS_LEN] = self.rssi.map_or(Self::RSSI_NOT_AVAILABLE, u8::from);
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let direct_index = 2 + BT_ADDRESS_LEN;
+        let rssi_index = Self::BYTE_LEN - 1;
+        Ok(DirectedReportInfo {
+            event_type: EventType::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            address_type: AddressType::try_from(buf[1]).map_err(|_| PackError::bad_index(1))?,
+            address: BTAddress::unpack_from(&buf[2..direct_index])?,
+            direct_address_type: AddressType::try_from(buf[direct_index])
+                .map_err(|_| PackError::bad_index(direct_index))?,
+            direct_address: BTAddress::unpack_from(&buf[direct_index + 1..rssi_index])?,
+            rssi: RSSI::maybe_rssi(i8::from_le_bytes([buf[rssi_index]]))
+                .map_err(|_| PackError::bad_index(rssi_index))?,
+        })
+    }
+}
+/// LE Directed Advertising Report Event. Only sent when scanning with the
+/// `ScanningFilterPolicy::DirectedAll` or `ScanningFilterPolicy::DirectedWhitelisted` policies
+/// (and the event is enabled in the `MetaEventMask`).
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+pub struct DirectedAdvertisingReport {
+    pub reports: Vec<DirectedReportInfo>,
+}
+impl MetaEvent for DirectedAdvertisingReport {
+    const META_CODE: MetaEventCode = MetaEventCode::DirectedAdvertisingReport;
+
+    fn meta_byte_len(&self) -> usize {
+        1 + self.reports.len() * DirectedReportInfo::BYTE_LEN
+    }
+
+    fn meta_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        let num_reports = NumReports::try_from(*buf.first().ok_or(PackError::BadLength {
+            expected: 1,
+            got: 0,
+        })?)
+        .map_err(|_| PackError::bad_index(0))?;
+        let num_reports = usize::from(u8::from(num_reports));
+        PackError::expect_length(1 + num_reports * DirectedReportInfo::BYTE_LEN, buf)?;
+        let reports = buf[1..]
+            .chunks_exact(DirectedReportInfo::BYTE_LEN)
+            .enumerate()
+            .map(|(i, report)| {
+                DirectedReportInfo::unpack_from(report).map_err(|e| match e {
+                    PackError::BadBytes { index: Some(index) } => {
+                        PackError::bad_index(1 + i * DirectedReportInfo::BYTE_LEN + index)
+                    }
+                    e => e,
+                })
+            })
+            .collect::<Result<Vec<_>, PackError>>()?;
+        Ok(DirectedAdvertisingReport { reports })
+    }
+
+    fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(self.meta_byte_len(), buf)?;
+        let num_reports =
+            NumReports::try_from(self.reports.len()).map_err(|_| PackError::InvalidFields)?;
+        buf[0] = num_reports.into();
+        for (report, buf) in self
+            .reports
+            .iter()
+            .zip(buf[1..].chunks_exact_mut(DirectedReportInfo::BYTE_LEN))
+        {
+            report.pack_into(buf)?;
+        }
+        Ok(())
+    }
+}
+/// Whether the data of an [`ExtendedReportInfo`] is complete.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub enum DataStatus {
+    Complete = 0b00,
+    /// More data follows in the next report from the same advertiser.
+    Incomplete = 0b01,
+    /// The data is incomplete and no more is coming.
+    Truncated = 0b10,
+}
+impl From<DataStatus> for u8 {
+    fn from(s: DataStatus) -> Self {
+        s as u8
+    }
+}
+impl TryFrom<u8> for DataStatus {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b00 => Ok(DataStatus::Complete),
+            0b01 => Ok(DataStatus::Incomplete),
+            0b10 => Ok(DataStatus::Truncated),
+            _ => Err(ConversionError(())),
+        }
+    }
+}
+/// Bit positions of the flags in [`ExtendedEventType`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub enum ExtendedEventTypeFlags {
+    Connectable = 0,
+    Scannable = 1,
+    Directed = 2,
+    ScanResponse = 3,
+    /// Legacy advertising PDU.
+    Legacy = 4,
+}
+impl From<ExtendedEventTypeFlags> for u16 {
+    fn from(f: ExtendedEventTypeFlags) -> Self {
+        f as u16
+    }
+}
+/// Event type of an [`ExtendedReportInfo`]. Flags (see [`ExtendedEventTypeFlags`]) and the
+/// [`DataStatus`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+pub struct ExtendedEventType(pub u16);
+impl ExtendedEventType {
+    pub const BYTE_LEN: usize = 2;
+    const DATA_STATUS_SHIFT: u16 = 5;
+    const DATA_STATUS_MASK: u16 = 0b11 << Self::DATA_STATUS_SHIFT;
+    pub fn get_flag(self, flag: ExtendedEventTypeFlags) -> bool {
+        self.0 & (1 << u16::from(flag)) != 0
+    }
+    pub fn enable_flag(&mut self, flag: ExtendedEventTypeFlags) {
+        self.0 |= 1 << u16::from(flag);
+    }
+    pub fn disable_flag(&mut self, flag: ExtendedEventTypeFlags) {
+        self.0 &= !(1 << u16::from(flag));
+    }
+    /// Returns `None` if the data status bits are the reserved value.
+    pub fn data_status(self) -> Option<DataStatus> {
+        DataStatus::try_from(((self.0 & Self::DATA_STATUS_MASK) >> Self::DATA_STATUS_SHIFT) as u8)
+            .ok()
+    }
+    pub fn set_data_status(&mut self, status: DataStatus) {
+        self.0 = (self.0 & !Self::DATA_STATUS_MASK)
+            | (u16::from(u8::from(status)) << Self::DATA_STATUS_SHIFT);
+    }
+}
+/// One report of an [`ExtendedAdvertisingReport`]. Unlike [`ReportInfo`], the data can be up
+/// to 1650 bytes (once reassembled with [`ExtendedReportReassembler`]).
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct ExtendedReportInfo {
+    pub event_type: ExtendedEventType,
+    /// `None` for anonymous advertisements.
+    pub address_type: Option<AddressType>,
+    pub address: BTAddress,
+    pub primary_phy: Phy,
+    /// `None` if no packets were received on the secondary advertising channel.
+    pub secondary_phy: Option<Phy>,
+    /// Advertising SID or `None` if the advertisement had no `ADI` field.
+    pub sid: Option<u8>,
+    pub tx_power: Option<TxPowerLevel>,
+    pub rssi: Option<RSSI>,
+    /// Interval (in units of 1.25 ms) of the periodic advertising the advertiser is doing or
+    /// `None` if there is none.
+    pub periodic_advertising_interval: Option<u16>,
+    /// Address type and address the advertisement was directed to (only for directed
+    /// advertisements). The address type is `None` for an unresolved resolvable private address.
+    pub direct_address: Option<(Option<AddressType>, BTAddress)>,
+    pub data: Vec<u8>,
+}
+impl ExtendedReportInfo {
+    /// Length of the report without `data`.
+    pub const HEADER_LEN: usize = ExtendedEventType::BYTE_LEN
+        + 1
+        + BT_ADDRESS_LEN
+        + 1
+        + 1
+        + 1
+        + 1
+        + 1
+        + 2
+        + 1
+        + BT_ADDRESS_LEN
+        + 1;
+    const NOT_AVAILABLE: u8 = 0x7F;
+    const NO_ADDRESS: u8 = 0xFF;
+    const NO_SID: u8 = 0xFF;
+    const UNRESOLVED_DIRECT_ADDRESS: u8 = 0xFE;
+    pub fn byte_len(&self) -> usize {
+        Self::HEADER_LEN + self.data.len()
+    }
+    pub fn data_status(&self) -> Option<DataStatus> {
+        self.event_type.data_status()
+    }
+    /// Unpacks the report at the start of `buf`. Returns the report and its length.
+    pub fn unpack_from(buf: &[u8]) -> Result<(Self, usize), PackError> {
+        if buf.len() < Self::HEADER_LEN {
+            return Err(PackError::BadLength {
+                expected: Self::HEADER_LEN,
+                got: buf.len(),
+            });
+        }
+        let event_type = ExtendedEventType(u16::from_le_bytes([buf[0], buf[1]]));
+        let address_type = match buf[2] {
+            Self::NO_ADDRESS => None,
+            t => Some(AddressType::try_from(t).map_err(|_| PackError::bad_index(2))?),
+        };
+        let address = BTAddress::unpack_from(&buf[3..3 + BT_ADDRESS_LEN])?;
+        let buf_rest = &buf[3 + BT_ADDRESS_LEN..];
+        let rest_index = |i: usize| PackError::bad_index(3 + BT_ADDRESS_LEN + i);
+        let primary_phy = Phy::try_from(buf_rest[0]).map_err(|_| rest_index(0))?;
+        let secondary_phy = match buf_rest[1] {
+            0 => None,
+            phy => Some(Phy::try_from(phy).map_err(|_| rest_index(1))?),
+        };
+        let sid = match buf_rest[2] {
+            Self::NO_SID => None,
+            sid => Some(sid),
+        };
+        let tx_power = match buf_rest[3] {
+            Self::NOT_AVAILABLE => None,
+            power => Some(TxPowerLevel::try_from(power).map_err(|_| rest_index(3))?),
+        };
+        let rssi = RSSI::maybe_rssi(i8::from_le_bytes([buf_rest[4]])).map_err(|_| rest_index(4))?;
+        let periodic_advertising_interval = match u16::from_le_bytes([buf_rest[5], buf_rest[6]]) {
+            0 => None,
+            interval => Some(interval),
+        };
+        let direct_address_type = match buf_rest[7] {
+            Self::UNRESOLVED_DIRECT_ADDRESS => None,
+            t => Some(AddressType::try_from(t).map_err(|_| rest_index(7))?),
+        };
+        let direct_address = BTAddress::unpack_from(&buf_rest[8..8 + BT_ADDRESS_LEN])?;
+        let data_len = usize::from(buf_rest[8 + BT_ADDRESS_LEN]);
+        let data = buf
+            .get(Self::HEADER_LEN..Self::HEADER_LEN + data_len)
+            .ok_or(PackError::BadLength {
+                expected: Self::HEADER_LEN + data_len,
+                got: buf.len(),
+            })?;
+        Ok((
+            ExtendedReportInfo {
+                event_type,
+                address_type,
+                address,
+                primary_phy,
+                secondary_phy,
+                sid,
+                tx_power,
+                rssi,
+                periodic_advertising_interval,
+                direct_address: if event_type.get_flag(ExtendedEventTypeFlags::Directed) {
+                    Some((direct_address_type, direct_address))
+                } else {
+                    None
+                },
+                data: data.to_vec(),
+            },
+            Self::HEADER_LEN + data_len,
+        ))
+    }
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(self.byte_len(), buf)?;
+        let data_len = u8::try_from(self.data.len()).map_err(|_| PackError::InvalidFields)?;
+        buf[0..2].copy_from_slice(&self.event_type.0.to_le_bytes());
+        buf[2] = self.address_type.map_or(Self::NO_ADDRESS, u8::from);
+        self.address.pack_into(&mut buf[3..3 + BT_ADDRESS_LEN])?;
+        let (_, buf_rest) = buf.split_at_mut(3 + BT_ADDRESS_LEN);
+        buf_rest[0] = self.primary_phy.into();
+        buf_rest[1] = self.secondary_phy.map_or(0, u8::from);
+        buf_rest[2] = self.sid.unwrap_or(Self::NO_SID);
+        buf_rest[3] = self.tx_power.map_or(Self::NOT_AVAILABLE, u8::from);
+        buf_rest[4] = self.rssi.map_or(Self::NOT_AVAILABLE, u8::from);
+        buf_rest[5..7].copy_from_slice(
+            &self
+                .periodic_advertising_interval
+                .unwrap_or(0)
+                .to_le_bytes(),
+        );
+        let (direct_address_type, direct_address) =
+            self.direct_address.unwrap_or((None, BTAddress::ZEROED));
+        buf_rest[7] = direct_address_type.map_or(Self::UNRESOLVED_DIRECT_ADDRESS, u8::from);
+        direct_address.pack_into(&mut buf_rest[8..8 + BT_ADDRESS_LEN])?;
+        buf_rest[8 + BT_ADDRESS_LEN] = data_len;
+        buf[Self::HEADER_LEN..].copy_from_slice(&self.data);
+        Ok(())
+    }
+}
+/// LE Extended Advertising Report Event. Each report can be a fragment of the advertising data
+/// (see [`ExtendedReportReassembler`]).
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+pub struct ExtendedAdvertisingReport {
+    pub reports: Vec<ExtendedReportInfo>,
+}
+impl MetaEvent for ExtendedAdvertisingReport {
+    const META_CODE: MetaEventCode = MetaEventCode::ExtendedAdvertisingReport;
+
+    fn meta_byte_len(&self) -> usize {
+        1 + self
+            .reports
+            .iter()
+            .map(ExtendedReportInfo::byte_len)
+            .sum::<usize>()
+    }
+
+    fn meta_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        let num_reports = usize::from(*buf.first().ok_or(PackError::BadLength {
+            expected: 1,
+            got: 0,
+        })?);
+        let mut reports = Vec::with_capacity(num_reports);
+        let mut index = 1;
+        for _ in 0..num_reports {
+            let (report, len) = ExtendedReportInfo::unpack_from(&buf[index..])?;
+            reports.push(report);
+            index += len;
+        }
+        PackError::expect_length(index, buf)?;
+        Ok(ExtendedAdvertisingReport { reports })
+    }
+
+    fn meta_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(self.meta_byte_len(), buf)?;
+        buf[0] = u8::try_from(self.reports.len()).map_err(|_| PackError::InvalidFields)?;
+        let mut index = 1;
+        for report in &self.reports {
+            let len = report.byte_len();
+            report.pack_into(&mut buf[index..index + len])?;
+            index += len;
+        }
+        Ok(())
+    }
+}
+/// Joins the fragments of extended advertising data (reports with
+/// `DataStatus::Incomplete`) into whole [`ExtendedReportInfo`]s.
+/// ```
+/// use btle::hci::le::phy::Phy;
+/// use btle::hci::le::report::{
+///     DataStatus, ExtendedEventType, ExtendedReportInfo, ExtendedReportReassembler,
+/// };
+/// use btle::BTAddress;
+/// let fragment = |status, data: &[u8]| {
+///     let mut event_type = ExtendedEventType::default();
+///     event_type.set_data_status(status);
+///     ExtendedReportInfo {
+///         event_type,
+///         address_type: None,
+///         address: BTAddress::ZEROED,
+///         primary_phy: Phy::LE1M,
+///         secondary_phy: Some(Phy::LE2M),
+///         sid: Some(1),
+///         tx_power: None,
+///         rssi: None,
+///         periodic_advertising_interval: None,
+///         direct_address: None,
+///         data: data.to_vec(),
+///     }
+/// };
+/// let mut reassembler = ExtendedReportReassembler::new();
+/// assert!(reassembler.push(fragment(DataStatus::Incomplete, &[1, 2])).is_none());
+/// let report = reassembler.push(fragment(DataStatus::Complete, &[3])).unwrap();
+/// assert_eq!(report.data, vec![1, 2, 3]);
+/// assert_eq!(report.data_status(), Some(DataStatus::Complete));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ExtendedReportReassembler {
+    pending: Vec<ExtendedReportInfo>,
+}
+impl ExtendedReportReassembler {
+    /// Max number of advertisers with incomplete data at the same time. The oldest incomplete
+    /// report is dropped when more arrive.
+    pub const MAX_PENDING: usize = 8;
+    /// Max length of reassembled data. Reports getting longer are returned truncated.
+    pub const MAX_DATA_LEN: usize = 1650;
+    pub fn new() -> Self {
+        Self::default()
+    }
+    fn is_same_advertiser(a: &ExtendedReportInfo, b: &ExtendedReportInfo) -> bool {
+        a.address_type == b.address_type
+            && a.address == b.address
+            && a.sid == b.sid
+            && a.event_type.get_flag(ExtendedEventTypeFlags::ScanResponse)
+                == b.event_type.get_flag(ExtendedEventTypeFlags::ScanResponse)
+    }
+    /// Adds `report` (a fragment or a complete report). Returns the whole report once its last
+    /// fragment is pushed.
+    pub fn push(&mut self, report: ExtendedReportInfo) -> Option<ExtendedReportInfo> {
+        let pending = self
+            .pending
+            .iter()
+            .position(|pending| Self::is_same_advertiser(pending, &report));
+        let mut whole = match pending {
+            Some(index) => {
+                let mut whole = self.pending.remove(index);
+                whole.data.extend_from_slice(&report.data);
+                whole.event_type = report.event_type;
+                whole.rssi = report.rssi;
+                whole
+            }
+            None => report,
+        };
+        if whole.data_status() == Some(DataStatus::Incomplete) {
+            if whole.data.len() <= Self::MAX_DATA_LEN {
+                if self.pending.len() >= Self::MAX_PENDING {
+                    self.pending.remove(0);
+                }
+                self.pending.push(whole);
+                return None;
+            }
+            whole.data.truncate(Self::MAX_DATA_LEN);
+            whole.event_type.set_data_status(DataStatus::Truncated);
+        }
+        Some(whole)
+    }
+    /// Drops every incomplete report.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{
+        DataStatus, DirectedAdvertisingReport, DirectedReportInfo, ExtendedEventType,
+        ExtendedReportInfo, ExtendedReportReassembler, Phy,
+    };
+    use crate::hci::le::MetaEvent;
+    use crate::le::report::{AddressType, EventType, NumReports};
+    use crate::{BTAddress, PackError, RSSI};
+    use alloc::vec;
+    use core::convert::TryFrom;
+    fn fragment(address: u8, status: DataStatus, data: &[u8]) -> ExtendedReportInfo {
+        let mut event_type = ExtendedEventType::default();
+        event_type.set_data_status(status);
+        ExtendedReportInfo {
+            event_type,
+            address_type: None,
+            address: BTAddress::new(&[address; 6]),
+            primary_phy: Phy::LE1M,
+            secondary_phy: Some(Phy::LE2M),
+            sid: Some(1),
+            tx_power: None,
+            rssi: None,
+            periodic_advertising_interval: None,
+            direct_address: None,
+            data: data.to_vec(),
+        }
+    }
+    #[test]
+    fn test_reassemble_interleaved_advertisers() {
+        let mut reassembler = ExtendedReportReassembler::new();
+        assert!(reassembler
+            .push(fragment(1, DataStatus::Incomplete, &[1, 1]))
+            .is_none());
+        assert!(reassembler
+            .push(fragment(2, DataStatus::Incomplete, &[2, 2]))
+            .is_none());
+        assert!(reassembler
+            .push(fragment(1, DataStatus::Incomplete, &[1]))
+            .is_none());
+        let b = reassembler
+            .push(fragment(2, DataStatus::Complete, &[2]))
+            .unwrap();
+        assert_eq!(b.address, BTAddress::new(&[2; 6]));
+        assert_eq!(b.data, [2, 2, 2]);
+        let a = reassembler
+            .push(fragment(1, DataStatus::Complete, &[1]))
+            .unwrap();
+        assert_eq!(a.address, BTAddress::new(&[1; 6]));
+        assert_eq!(a.data, [1, 1, 1, 1]);
+        assert_eq!(a.data_status(), Some(DataStatus::Complete));
+    }
+    #[test]
+    fn test_reassemble_evicts_oldest_pending() {
+        let mut reassembler = ExtendedReportReassembler::new();
+        let pending = u8::try_from(ExtendedReportReassembler::MAX_PENDING).unwrap();
+        for address in 0..=pending {
+            assert!(reassembler
+                .push(fragment(address, DataStatus::Incomplete, &[address]))
+                .is_none());
+        }
+        assert_eq!(
+            reassembler.pending.len(),
+            ExtendedReportReassembler::MAX_PENDING
+        );
+        // The first advertiser was evicted so only its last fragment is left.
+        let evicted = reassembler
+            .push(fragment(0, DataStatus::Complete, &[0xFF]))
+            .unwrap();
+        assert_eq!(evicted.data, [0xFF]);
+        let kept = reassembler
+            .push(fragment(pending, DataStatus::Complete, &[0xFF]))
+            .unwrap();
+        assert_eq!(kept.data, [pending, 0xFF]);
+    }
+    #[test]
+    fn test_reassemble_truncates_long_data() {
+        let mut reassembler = ExtendedReportReassembler::new();
+        let chunk = [0xAA_u8; 251];
+        let mut len = 0;
+        let report = loop {
+            len += chunk.len();
+            if let Some(report) = reassembler.push(fragment(1, DataStatus::Incomplete, &chunk)) {
+                break report;
+            }
+            assert!(len <= ExtendedReportReassembler::MAX_DATA_LEN);
+        };
+        assert!(len > ExtendedReportReassembler::MAX_DATA_LEN);
+        assert_eq!(report.data.len(), ExtendedReportReassembler::MAX_DATA_LEN);
+        assert_eq!(report.data_status(), Some(DataStatus::Truncated));
+        assert!(reassembler.pending.is_empty());
+    }
+    #[test]
+    fn test_extended_report_info_short_buffer() {
+        let report = fragment(1, DataStatus::Complete, &[1, 2, 3]);
+        let mut buf = alloc::vec![0_u8; report.byte_len()];
+        report.pack_into(&mut buf).unwrap();
+        let (unpacked, len) = ExtendedReportInfo::unpack_from(&buf).unwrap();
+        assert_eq!(unpacked, report);
+        assert_eq!(len, buf.len());
+        assert!(ExtendedReportInfo::unpack_from(&buf[..buf.len() - 1]).is_err());
+        assert!(
+            ExtendedReportInfo::unpack_from(&buf[..ExtendedReportInfo::HEADER_LEN - 1]).is_err()
+        );
+    }
+    fn directed(address: u8) -> DirectedReportInfo {
+        DirectedReportInfo {
+            event_type: EventType::AdvDirectInd,
+            address_type: AddressType::PublicDevice,
+            address: BTAddress::new(&[address; 6]),
+            direct_address_type: AddressType::RandomDevice,
+            direct_address: BTAddress::new(&[0x4A, 0x55, 0x66, 0x77, 0x88, 0x99]),
+            rssi: Some(RSSI::new(-70)),
+        }
+    }
+    #[test]
+    fn test_directed_report_pack_unpack() {
+        let mut second = directed(2);
+        second.rssi = None;
+        let report = DirectedAdvertisingReport {
+            reports: vec![directed(1), second],
+        };
+        let mut buf = vec![0_u8; report.meta_byte_len()];
+        assert_eq!(buf.len(), 1 + 2 * DirectedReportInfo::BYTE_LEN);
+        report.meta_pack_into(&mut buf).unwrap();
+        assert_eq!(
+            buf[..=DirectedReportInfo::BYTE_LEN],
+            [
+                0x02, 0x01, 0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x4A, 0x55, 0x66, 0x77,
+                0x88, 0x99, 0xBA
+            ]
+        );
+        assert_eq!(buf[buf.len() - 1], 0x7F);
+        assert_eq!(
+            DirectedAdvertisingReport::meta_unpack_from(&buf),
+            Ok(report.clone())
+        );
+        assert_eq!(
+            DirectedAdvertisingReport::meta_unpack_from(&buf[..buf.len() - 1]),
+            Err(PackError::BadLength {
+                expected: buf.len(),
+                got: buf.len() - 1,
+            })
+        );
+        assert_eq!(
+            DirectedAdvertisingReport::meta_unpack_from(&[]),
+            Err(PackError::BadLength {
+                expected: 1,
+                got: 0
+            })
+        );
+        assert!(report.meta_pack_into(&mut buf[1..]).is_err());
+    }
+    #[test]
+    fn test_directed_report_out_of_range() {
+        let report = DirectedAdvertisingReport {
+            reports: vec![directed(1)],
+        };
+        let mut buf = vec![0_u8; report.meta_byte_len()];
+        report.meta_pack_into(&mut buf).unwrap();
+        let good = buf.clone();
+
+        buf[0] = 0;
+        assert_eq!(
+            DirectedAdvertisingReport::meta_unpack_from(&buf),
+            Err(PackError::bad_index(0))
+        );
+        buf.copy_from_slice(&good);
+        buf[1] = 0x05;
+        assert_eq!(
+            DirectedAdvertisingReport::meta_unpack_from(&buf),
+            Err(PackError::bad_index(1))
+        );
+        let info = &mut buf[1..];
+        info[1] = 0x04;
+        assert_eq!(
+            DirectedReportInfo::unpack_from(info),
+            Err(PackError::bad_index(0))
+        );
+        info[0] = EventType::AdvDirectInd.into();
+        assert_eq!(
+            DirectedReportInfo::unpack_from(info),
+            Err(PackError::bad_index(1))
+        );
+        info[1] = AddressType::PublicDevice.into();
+        info[8] = 0x04;
+        assert_eq!(
+            DirectedReportInfo::unpack_from(info),
+            Err(PackError::bad_index(8))
+        );
+        info[8] = AddressType::RandomDevice.into();
+        info[15] = 21;
+        assert_eq!(
+            DirectedReportInfo::unpack_from(info),
+            Err(PackError::bad_index(15))
+        );
+
+        let too_many = DirectedAdvertisingReport {
+            reports: vec![directed(1); usize::from(NumReports::NUM_REPORTS_MAX) + 1],
+        };
+        let mut buf = vec![0_u8; too_many.meta_byte_len()];
+        assert_eq!(
+            too_many.meta_pack_into(&mut buf),
+            Err(PackError::InvalidFields)
+        );
+    }
+}
@@ -4,8 +4,11 @@ use crate::hci::command::Command;
 use crate::hci::event::{CommandComplete, StatusReturn};
 use crate::hci::le::LEControllerOpcode;
 use crate::hci::Opcode;
-use crate::le::scan::ScanParameters;
+use crate::le::scan::{
+    OwnAddressType, ScanInterval, ScanParameters, ScanType, ScanWindow, ScanningFilterPolicy,
+};
 use crate::PackError;
+use core::convert::TryFrom;
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
 pub struct SetScanEnable {
@@ -92,10 +95,124 @@ impl Command for SetScanParameters {
         Ok(())
     }
 
-    fn unpack_from(_buf: &[u8]) -> Result<Self, PackError>
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
     where
         Self: Sized,
     {
-        unimplemented!()
+        PackError::expect_length(SET_SCAN_PARAMETERS_LEN, buf)?;
+        Ok(SetScanParameters(ScanParameters {
+            scan_type: ScanType::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            scan_interval: ScanInterval::try_from(u16::from_le_bytes([buf[1], buf[2]]))
+                .map_err(|_| PackError::bad_index(1))?,
+            scan_window: ScanWindow::try_from(u16::from_le_bytes([buf[3], buf[4]]))
+                .map_err(|_| PackError::bad_index(3))?,
+            own_address_type: OwnAddressType::try_from(buf[5])
+                .map_err(|_| PackError::bad_index(5))?,
+            scanning_filter_policy: ScanningFilterPolicy::try_from(buf[6])
+                .map_err(|_| PackError::bad_index(6))?,
+        }))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{SetScanEnable, SetScanParameters, SET_SCAN_PARAMETERS_LEN};
+    use crate::hci::command::Command;
+    use crate::le::scan::{
+        OwnAddressType, ScanInterval, ScanParameters, ScanType, ScanWindow, ScanningFilterPolicy,
+    };
+    use crate::PackError;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn test_set_scan_parameters_directed_policies() {
+        for &policy in &[
+            ScanningFilterPolicy::DirectedAll,
+            ScanningFilterPolicy::DirectedWhitelisted,
+        ] {
+            assert!(policy.is_directed());
+            let command = SetScanParameters(ScanParameters {
+                scan_type: ScanType::Active,
+                scan_interval: ScanInterval::new(0x0020),
+                scan_window: ScanWindow::new(0x0010),
+                own_address_type: OwnAddressType::PrivateOrRandom,
+                scanning_filter_policy: policy,
+            });
+            let mut buf = [0_u8; SET_SCAN_PARAMETERS_LEN];
+            command.pack_into(&mut buf).unwrap();
+            assert_eq!(buf, [0x01, 0x20, 0x00, 0x10, 0x00, 0x03, u8::from(policy)]);
+            assert_eq!(SetScanParameters::unpack_from(&buf), Ok(command));
+        }
+        assert!(!ScanningFilterPolicy::All.is_directed());
+        assert!(!ScanningFilterPolicy::Whitelisted.is_directed());
+        assert!(ScanningFilterPolicy::try_from(0x04).is_err());
+    }
+    #[test]
+    fn test_set_scan_parameters_out_of_range() {
+        let mut command = SetScanParameters::DEFAULT;
+        let mut buf = [0_u8; SET_SCAN_PARAMETERS_LEN];
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(SetScanParameters::unpack_from(&buf), Ok(command));
+        assert_eq!(
+            SetScanParameters::unpack_from(&buf[..6]),
+            Err(PackError::BadLength {
+                expected: SET_SCAN_PARAMETERS_LEN,
+                got: 6
+            })
+        );
+        let good = buf;
+
+        buf[0] = 0x02;
+        assert_eq!(
+            SetScanParameters::unpack_from(&buf),
+            Err(PackError::bad_index(0))
+        );
+        buf = good;
+        buf[1..3].copy_from_slice(&0x4001_u16.to_le_bytes());
+        assert_eq!(
+            SetScanParameters::unpack_from(&buf),
+            Err(PackError::bad_index(1))
+        );
+        buf = good;
+        buf[3..5].copy_from_slice(&0x0003_u16.to_le_bytes());
+        assert_eq!(
+            SetScanParameters::unpack_from(&buf),
+            Err(PackError::bad_index(3))
+        );
+        buf = good;
+        buf[5] = 0x04;
+        assert_eq!(
+            SetScanParameters::unpack_from(&buf),
+            Err(PackError::bad_index(5))
+        );
+        buf = good;
+        buf[6] = 0x04;
+        assert_eq!(
+            SetScanParameters::unpack_from(&buf),
+            Err(PackError::bad_index(6))
+        );
+
+        command.0.scan_window = ScanWindow::new(0x0020);
+        command.0.scan_interval = ScanInterval::new(0x0010);
+        assert_eq!(command.pack_into(&mut buf), Err(PackError::InvalidFields));
+    }
+    #[test]
+    fn test_set_scan_enable_pack_unpack() {
+        let command = SetScanEnable {
+            is_enabled: true,
+            filter_duplicates: false,
+        };
+        let mut buf = [0_u8; SetScanEnable::BYTE_LEN];
+        command.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x01, 0x00]);
+        assert_eq!(SetScanEnable::unpack_from(&buf), Ok(command));
+        assert!(SetScanEnable::unpack_from(&buf[..1]).is_err());
+        assert_eq!(
+            SetScanEnable::unpack_from(&[0x02, 0x00]),
+            Err(PackError::bad_index(0))
+        );
+        assert_eq!(
+            SetScanEnable::unpack_from(&[0x00, 0x02]),
+            Err(PackError::bad_index(1))
+        );
     }
 }
@@ -0,0 +1,267 @@
+//! LE White List commands ([`ReadWhitelistSize`], [`ClearWhitelist`], [`AddDeviceToWhitelist`],
+//! [`RemoveDeviceFromWhitelist`]) and [`Whitelist`] for tracking what is loaded on the controller.
+use crate::hci::command::Command;
+use crate::hci::event::{CommandComplete, ReturnParameters, StatusReturn};
+use crate::hci::le::LEControllerOpcode;
+use crate::hci::{ErrorCode, Opcode};
+use crate::{BTAddress, ConversionError, PackError, BT_ADDRESS_LEN};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// Address type of a White List entry.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum WhitelistAddressType {
+    Public = 0x00,
+    Random = 0x01,
+    /// Devices sending anonymous advertisements (no address). The address of the entry is
+    /// ignored by the controller.
+    Anonymous = 0xFF,
+}
+impl From<WhitelistAddressType> for u8 {
+    fn from(t: WhitelistAddressType) -> Self {
+        t as u8
+    }
+}
+impl TryFrom<u8> for WhitelistAddressType {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(WhitelistAddressType::Public),
+            0x01 => Ok(WhitelistAddressType::Random),
+            0xFF => Ok(WhitelistAddressType::Anonymous),
+            _ => Err(ConversionError(())),
+        }
+    }
+}
+/// Single device in the White List.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct WhitelistEntry {
+    pub address_type: WhitelistAddressType,
+    pub address: BTAddress,
+}
+impl WhitelistEntry {
+    pub const BYTE_LEN: usize = 1 + BT_ADDRESS_LEN;
+    pub fn new(address_type: WhitelistAddressType, address: BTAddress) -> WhitelistEntry {
+        WhitelistEntry {
+            address_type,
+            address,
+        }
+    }
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.address_type.into();
+        self.address.pack_into(&mut buf[1..])
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(WhitelistEntry {
+            address_type: WhitelistAddressType::try_from(buf[0])
+                .map_err(|_| PackError::bad_index(0))?,
+            address: BTAddress::unpack_from(&buf[1..])?,
+        })
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadWhitelistSize {}
+impl Command for ReadWhitelistSize {
+    type Return = CommandComplete<WhitelistSizeReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ReadWhitelistSize.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ReadWhitelistSize {})
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct WhitelistSizeReturn {
+    pub status: ErrorCode,
+    /// Total number of White List entries that can be stored in the controller.
+    pub size: u8,
+}
+impl WhitelistSizeReturn {
+    pub const BYTE_LEN: usize = 2;
+}
+impl ReturnParameters for WhitelistSizeReturn {
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        buf[1] = self.size;
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(WhitelistSizeReturn {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            size: buf[1],
+        })
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ClearWhitelist {}
+impl Command for ClearWhitelist {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::ClearWhitelist.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        0
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(0, buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(0, buf)?;
+        Ok(ClearWhitelist {})
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct AddDeviceToWhitelist(pub WhitelistEntry);
+impl Command for AddDeviceToWhitelist {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::AddDeviceToWhitelist.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        WhitelistEntry::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(AddDeviceToWhitelist(WhitelistEntry::unpack_from(buf)?))
+    }
+}
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct RemoveDeviceFromWhitelist(pub WhitelistEntry);
+impl Command for RemoveDeviceFromWhitelist {
+    type Return = CommandComplete<StatusReturn>;
+
+    fn opcode() -> Opcode {
+        LEControllerOpcode::RemoveDeviceFromWhitelist.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        WhitelistEntry::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.0.pack_into(buf)
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        Ok(RemoveDeviceFromWhitelist(WhitelistEntry::unpack_from(buf)?))
+    }
+}
+/// Host side copy of the controller's White List. Only tracks entries, it doesn't send any
+/// commands by itself. See `LEAdapter::whitelist_add` for the adapter API that keeps this in sync
+/// with the controller.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Whitelist {
+    capacity: usize,
+    entries: Vec<WhitelistEntry>,
+}
+impl Whitelist {
+    /// Creates a new empty `Whitelist` that can hold `capacity` entries (usually from
+    /// [`ReadWhitelistSize`]).
+    pub fn new(capacity: usize) -> Whitelist {
+        Whitelist {
+            capacity,
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn is_full(&self) -> bool {
+        self.entries.len() >= self.capacity
+    }
+    pub fn contains(&self, entry: &WhitelistEntry) -> bool {
+        self.entries.contains(entry)
+    }
+    pub fn entries(&self) -> &[WhitelistEntry] {
+        &self.entries[..]
+    }
+    /// Checks if `entry` can be inserted. Returns `Ok(false)` if `entry` is already in the
+    /// `Whitelist` and `Ok(true)` if it needs to be added.
+    /// # Errors
+    /// Returns `ErrorCode::MemoryFull` (the same error the controller would return) if the
+    /// `Whitelist` is full.
+    pub fn check_insert(&self, entry: &WhitelistEntry) -> Result<bool, ErrorCode> {
+        if self.contains(entry) {
+            Ok(false)
+        } else if self.is_full() {
+            Err(ErrorCode::MemoryFull)
+        } else {
+            Ok(true)
+        }
+    }
+    /// Inserts `entry`. Returns `Ok(false)` if `entry` was already in the `Whitelist`.
+    /// # Errors
+    /// Returns `ErrorCode::MemoryFull` if the `Whitelist` is full.
+    pub fn insert(&mut self, entry: WhitelistEntry) -> Result<bool, ErrorCode> {
+        let needs_insert = self.check_insert(&entry)?;
+        if needs_insert {
+            self.entries.push(entry);
+        }
+        Ok(needs_insert)
+    }
+    /// Removes `entry`. Returns `true` if `entry` was in the `Whitelist`.
+    pub fn remove(&mut self, entry: &WhitelistEntry) -> bool {
+        match self.entries.iter().position(|e| e == entry) {
+            Some(index) => {
+                self.entries.swap_remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -1,5 +1,9 @@
-//! Link Controller module (WIP).
-use crate::hci::{Opcode, OCF, OGF};
+//! Link Controller module (WIP). Contains [`Disconnect`] and [`DisconnectionComplete`].
+use crate::hci::command::{AsyncCommand, Command};
+use crate::hci::event::{CommandStatus, Event, EventCode};
+use crate::hci::{ConnectionHandle, ErrorCode, Opcode, OCF, OGF};
+use crate::PackError;
+use core::convert::TryFrom;
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
 #[repr(u16)]
@@ -42,3 +46,94 @@ impl From<LinkControlOpcode> for Opcode {
         Self(OGF::LinkControl, opcode.into())
     }
 }
+/// Disconnect (terminate) an existing connection. Only returns a `CommandStatus`, the
+/// [`DisconnectionComplete`] event is sent once the connection is terminated.
+///
+/// `reason` should be one of `ErrorCode::AuthenticationFailure`,
+/// `ErrorCode::OtherEndTerminatedConnectionUserEndedConnection`,
+/// `ErrorCode::OtherEndTerminatedConnectionLowResources`,
+/// `ErrorCode::OtherEndTerminatedConnectionAboutToPowerOff`,
+/// `ErrorCode::UnsupportedRemoteFeature` or `ErrorCode::PairingWithUnitKeyNotSupported`.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct Disconnect {
+    pub handle: ConnectionHandle,
+    pub reason: ErrorCode,
+}
+impl Disconnect {
+    pub const BYTE_LEN: usize = ConnectionHandle::BYTE_LEN + 1;
+}
+impl Command for Disconnect {
+    type Return = CommandStatus;
+
+    fn opcode() -> Opcode {
+        LinkControlOpcode::Disconnect.into()
+    }
+
+    fn byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        self.handle.pack_into(&mut buf[..2])?;
+        buf[2] = self.reason.into();
+        Ok(())
+    }
+
+    fn unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(Disconnect {
+            handle: ConnectionHandle::unpack_from(&buf[..2])?,
+            reason: ErrorCode::try_from(buf[2]).map_err(|_| PackError::bad_index(2))?,
+        })
+    }
+}
+impl AsyncCommand for Disconnect {
+    type Completion = DisconnectionComplete;
+
+    fn is_completion(&self, completion: &Self::Completion) -> bool {
+        completion.handle == self.handle
+    }
+}
+/// Disconnection Complete Event. Sent when a connection is terminated (locally with
+/// [`Disconnect`] or by the remote device/link loss). `reason` is why the connection was
+/// terminated.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct DisconnectionComplete {
+    pub status: ErrorCode,
+    pub handle: ConnectionHandle,
+    pub reason: ErrorCode,
+}
+impl DisconnectionComplete {
+    pub const BYTE_LEN: usize = 1 + ConnectionHandle::BYTE_LEN + 1;
+}
+impl Event for DisconnectionComplete {
+    const EVENT_CODE: EventCode = EventCode::DisconnectionComplete;
+
+    fn event_byte_len(&self) -> usize {
+        Self::BYTE_LEN
+    }
+
+    fn event_unpack_from(buf: &[u8]) -> Result<Self, PackError>
+    where
+        Self: Sized,
+    {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(DisconnectionComplete {
+            status: ErrorCode::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            handle: ConnectionHandle::unpack_from(&buf[1..3])?,
+            reason: ErrorCode::try_from(buf[3]).map_err(|_| PackError::bad_index(3))?,
+        })
+    }
+
+    fn event_pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.status.into();
+        self.handle.pack_into(&mut buf[1..3])?;
+        buf[3] = self.reason.into();
+        Ok(())
+    }
+}
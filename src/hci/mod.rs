@@ -6,6 +6,7 @@ pub mod adapters;
 pub mod baseband;
 pub mod command;
 pub mod event;
+pub mod info;
 pub mod le;
 pub mod link_control;
 pub mod packet;
@@ -14,6 +15,7 @@ pub mod remote;
 #[cfg(all(unix, feature = "bluez"))]
 pub mod socket;
 pub mod stream;
+pub mod timer;
 #[cfg(feature = "hci_usb")]
 pub mod usb;
 
@@ -27,6 +29,11 @@ pub enum StreamError {
     BadPacketCode,
     StreamClosed,
     StreamFailed,
+    /// No response to a command arrived before the timeout. See
+    /// [`stream::Stream::send_command_timeout`].
+    CommandTimeout,
+    /// Events were dropped because they weren't read fast enough.
+    EventOverflow,
 }
 use crate::bytes::ToFromBytesEndian;
 use crate::ConversionError;
@@ -163,6 +170,21 @@ pub enum ErrorCode {
     EIRTooLarge = 0x36,
     SimplePairingNotSupported = 0x37,
     HostBusyPairing = 0x38,
+    ConnectionRejectedNoSuitableChannel = 0x39,
+    ControllerBusy = 0x3A,
+    UnacceptableConnectionParameters = 0x3B,
+    /// Advertising Timeout. Also returned when periodic advertising sync is lost.
+    AdvertisingTimeout = 0x3C,
+    ConnectionTerminatedMICFailure = 0x3D,
+    /// Connection Failed to be Established. Also used for Synchronization Timeout.
+    ConnectionFailedToBeEstablished = 0x3E,
+    MACConnectionFailed = 0x3F,
+    CoarseClockAdjustmentRejected = 0x40,
+    Type0SubmapNotDefined = 0x41,
+    UnknownAdvertisingIdentifier = 0x42,
+    LimitReached = 0x43,
+    OperationCancelledByHost = 0x44,
+    PacketTooLong = 0x45,
 }
 impl ErrorCode {
     pub fn is_ok(self) -> bool {
@@ -241,12 +263,158 @@ impl ErrorCode {
             ErrorCode::EIRTooLarge => "EIRTooLarge",
             ErrorCode::SimplePairingNotSupported => "SimplePairingNotSupported",
             ErrorCode::HostBusyPairing => "HostBusyPairing",
+            ErrorCode::ConnectionRejectedNoSuitableChannel => "ConnectionRejectedNoSuitableChannel",
+            ErrorCode::ControllerBusy => "ControllerBusy",
+            ErrorCode::UnacceptableConnectionParameters => "UnacceptableConnectionParameters",
+            ErrorCode::AdvertisingTimeout => "AdvertisingTimeout",
+            ErrorCode::ConnectionTerminatedMICFailure => "ConnectionTerminatedMICFailure",
+            ErrorCode::ConnectionFailedToBeEstablished => "ConnectionFailedToBeEstablished",
+            ErrorCode::MACConnectionFailed => "MACConnectionFailed",
+            ErrorCode::CoarseClockAdjustmentRejected => "CoarseClockAdjustmentRejected",
+            ErrorCode::Type0SubmapNotDefined => "Type0SubmapNotDefined",
+            ErrorCode::UnknownAdvertisingIdentifier => "UnknownAdvertisingIdentifier",
+            ErrorCode::LimitReached => "LimitReached",
+            ErrorCode::OperationCancelledByHost => "OperationCancelledByHost",
+            ErrorCode::PacketTooLong => "PacketTooLong",
         }
     }
+    /// Human-readable name of the error as listed in the Bluetooth Core Spec (Vol 1, Part F).
+    pub fn description(self) -> &'static str {
+        match self {
+            ErrorCode::Ok => "Success",
+            ErrorCode::UnknownHCICommand => "Unknown HCI Command",
+            ErrorCode::NoConnection => "Unknown Connection Identifier",
+            ErrorCode::HardwareFailure => "Hardware Failure",
+            ErrorCode::PageTimeout => "Page Timeout",
+            ErrorCode::AuthenticationFailure => "Authentication Failure",
+            ErrorCode::KeyMissing => "PIN or Key Missing",
+            ErrorCode::MemoryFull => "Memory Capacity Exceeded",
+            ErrorCode::ConnectionTimeout => "Connection Timeout",
+            ErrorCode::MaxNumberOfConnections => "Connection Limit Exceeded",
+            ErrorCode::MaxNumberOfSCOConnectionsToADevice => {
+                "Synchronous Connection Limit To A Device Exceeded"
+            }
+            ErrorCode::ACLConnectionAlreadyExists => "Connection Already Exists",
+            ErrorCode::CommandDisallowed => "Command Disallowed",
+            ErrorCode::HostRejectedDueToLimitedResources => {
+                "Connection Rejected due to Limited Resources"
+            }
+            ErrorCode::HostRejectedDueToSecurityReasons => {
+                "Connection Rejected Due To Security Reasons"
+            }
+            ErrorCode::HostRejectedDueToARemoteDeviceOnlyAPersonalDevice => {
+                "Connection Rejected due to Unacceptable BD_ADDR"
+            }
+            ErrorCode::HostTimeout => "Connection Accept Timeout Exceeded",
+            ErrorCode::UnsupportedFeatureOrParameterValue => {
+                "Unsupported Feature or Parameter Value"
+            }
+            ErrorCode::InvalidHCICommandParameters => "Invalid HCI Command Parameters",
+            ErrorCode::OtherEndTerminatedConnectionUserEndedConnection => {
+                "Remote User Terminated Connection"
+            }
+            ErrorCode::OtherEndTerminatedConnectionLowResources => {
+                "Remote Device Terminated Connection due to Low Resources"
+            }
+            ErrorCode::OtherEndTerminatedConnectionAboutToPowerOff => {
+                "Remote Device Terminated Connection due to Power Off"
+            }
+            ErrorCode::ConnectionTerminatedByLocalHost => "Connection Terminated By Local Host",
+            ErrorCode::RepeatedAttempts => "Repeated Attempts",
+            ErrorCode::PairingNotAllowed => "Pairing Not Allowed",
+            ErrorCode::UnknownLMPPDU => "Unknown LMP PDU",
+            ErrorCode::UnsupportedRemoteFeature => "Unsupported Remote Feature",
+            ErrorCode::SCOOffsetRejected => "SCO Offset Rejected",
+            ErrorCode::SCOIntervalRejected => "SCO Interval Rejected",
+            ErrorCode::SCOAirModeRejected => "SCO Air Mode Rejected",
+            ErrorCode::InvalidLMPParameters => "Invalid LMP Parameters / Invalid LL Parameters",
+            ErrorCode::UnspecifiedError => "Unspecified Error",
+            ErrorCode::UnsupportedLMPParameter => {
+                "Unsupported LMP Parameter Value / Unsupported LL Parameter Value"
+            }
+            ErrorCode::RoleChangeNotAllowed => "Role Change Not Allowed",
+            ErrorCode::LMPResponseTimeout => "LMP Response Timeout / LL Response Timeout",
+            ErrorCode::LMPErrorTransactionCollision => {
+                "LMP Error Transaction Collision / LL Procedure Collision"
+            }
+            ErrorCode::LMPPDUNotAllowed => "LMP PDU Not Allowed",
+            ErrorCode::EncryptionModeNotAcceptable => "Encryption Mode Not Acceptable",
+            ErrorCode::UnitKeyUsed => "Link Key cannot be Changed",
+            ErrorCode::QoSNotSupported => "Requested QoS Not Supported",
+            ErrorCode::InstantPassed => "Instant Passed",
+            ErrorCode::PairingWithUnitKeyNotSupported => "Pairing With Unit Key Not Supported",
+            ErrorCode::TransactionCollision => "Different Transaction Collision",
+            ErrorCode::QOSUnacceptableParameter => "QoS Unacceptable Parameter",
+            ErrorCode::QOSRejected => "QoS Rejected",
+            ErrorCode::ClassificationNotSupported => "Channel Classification Not Supported",
+            ErrorCode::InsufficientSecurity => "Insufficient Security",
+            ErrorCode::ParameterOutOfRange => "Parameter Out Of Mandatory Range",
+            ErrorCode::RoleSwitchPending => "Role Switch Pending",
+            ErrorCode::SlotViolation => "Reserved Slot Violation",
+            ErrorCode::RoleSwitchFailed => "Role Switch Failed",
+            ErrorCode::EIRTooLarge => "Extended Inquiry Response Too Large",
+            ErrorCode::SimplePairingNotSupported => "Secure Simple Pairing Not Supported By Host",
+            ErrorCode::HostBusyPairing => "Host Busy - Pairing",
+            ErrorCode::ConnectionRejectedNoSuitableChannel => {
+                "Connection Rejected due to No Suitable Channel Found"
+            }
+            ErrorCode::ControllerBusy => "Controller Busy",
+            ErrorCode::UnacceptableConnectionParameters => "Unacceptable Connection Parameters",
+            ErrorCode::AdvertisingTimeout => "Advertising Timeout",
+            ErrorCode::ConnectionTerminatedMICFailure => "Connection Terminated due to MIC Failure",
+            ErrorCode::ConnectionFailedToBeEstablished => {
+                "Connection Failed to be Established / Synchronization Timeout"
+            }
+            ErrorCode::MACConnectionFailed => "MAC Connection Failed",
+            ErrorCode::CoarseClockAdjustmentRejected => {
+                "Coarse Clock Adjustment Rejected but Will Try to Adjust Using Clock Dragging"
+            }
+            ErrorCode::Type0SubmapNotDefined => "Type0 Submap Not Defined",
+            ErrorCode::UnknownAdvertisingIdentifier => "Unknown Advertising Identifier",
+            ErrorCode::LimitReached => "Limit Reached",
+            ErrorCode::OperationCancelledByHost => "Operation Cancelled by Host",
+            ErrorCode::PacketTooLong => "Packet Too Long",
+        }
+    }
+    /// Returns `true` if the error is caused by a temporary condition (busy controller, a
+    /// colliding procedure, etc) so sending the same command again later could succeed.
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            ErrorCode::HostRejectedDueToLimitedResources
+                | ErrorCode::LMPResponseTimeout
+                | ErrorCode::LMPErrorTransactionCollision
+                | ErrorCode::InstantPassed
+                | ErrorCode::TransactionCollision
+                | ErrorCode::RoleSwitchPending
+                | ErrorCode::HostBusyPairing
+                | ErrorCode::ControllerBusy
+                | ErrorCode::ConnectionFailedToBeEstablished
+        )
+    }
+    /// Returns `true` if the error is a reason for a connection ending (as reported in
+    /// `DisconnectionComplete`).
+    pub fn is_connection_termination(self) -> bool {
+        matches!(
+            self,
+            ErrorCode::AuthenticationFailure
+                | ErrorCode::ConnectionTimeout
+                | ErrorCode::OtherEndTerminatedConnectionUserEndedConnection
+                | ErrorCode::OtherEndTerminatedConnectionLowResources
+                | ErrorCode::OtherEndTerminatedConnectionAboutToPowerOff
+                | ErrorCode::ConnectionTerminatedByLocalHost
+                | ErrorCode::UnsupportedRemoteFeature
+                | ErrorCode::LMPResponseTimeout
+                | ErrorCode::PairingWithUnitKeyNotSupported
+                | ErrorCode::UnacceptableConnectionParameters
+                | ErrorCode::ConnectionTerminatedMICFailure
+                | ErrorCode::ConnectionFailedToBeEstablished
+        )
+    }
 }
 impl core::fmt::Display for ErrorCode {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        write!(f, "{}", self.as_str())
+        write!(f, "{} (0x{:02X})", self.description(), u8::from(*self))
     }
 }
 impl From<ErrorCode> for u8 {
@@ -301,6 +469,31 @@ impl TryFrom<u8> for ErrorCode {
             0x27 => Ok(ErrorCode::QoSNotSupported),
             0x28 => Ok(ErrorCode::InstantPassed),
             0x29 => Ok(ErrorCode::PairingWithUnitKeyNotSupported),
+            0x2A => Ok(ErrorCode::TransactionCollision),
+            0x2C => Ok(ErrorCode::QOSUnacceptableParameter),
+            0x2D => Ok(ErrorCode::QOSRejected),
+            0x2E => Ok(ErrorCode::ClassificationNotSupported),
+            0x2F => Ok(ErrorCode::InsufficientSecurity),
+            0x30 => Ok(ErrorCode::ParameterOutOfRange),
+            0x32 => Ok(ErrorCode::RoleSwitchPending),
+            0x34 => Ok(ErrorCode::SlotViolation),
+            0x35 => Ok(ErrorCode::RoleSwitchFailed),
+            0x36 => Ok(ErrorCode::EIRTooLarge),
+            0x37 => Ok(ErrorCode::SimplePairingNotSupported),
+            0x38 => Ok(ErrorCode::HostBusyPairing),
+            0x39 => Ok(ErrorCode::ConnectionRejectedNoSuitableChannel),
+            0x3A => Ok(ErrorCode::ControllerBusy),
+            0x3B => Ok(ErrorCode::UnacceptableConnectionParameters),
+            0x3C => Ok(ErrorCode::AdvertisingTimeout),
+            0x3D => Ok(ErrorCode::ConnectionTerminatedMICFailure),
+            0x3E => Ok(ErrorCode::ConnectionFailedToBeEstablished),
+            0x3F => Ok(ErrorCode::MACConnectionFailed),
+            0x40 => Ok(ErrorCode::CoarseClockAdjustmentRejected),
+            0x41 => Ok(ErrorCode::Type0SubmapNotDefined),
+            0x42 => Ok(ErrorCode::UnknownAdvertisingIdentifier),
+            0x43 => Ok(ErrorCode::LimitReached),
+            0x44 => Ok(ErrorCode::OperationCancelledByHost),
+            0x45 => Ok(ErrorCode::PacketTooLong),
             _ => Err(ConversionError(())),
         }
     }
@@ -420,3 +613,96 @@ impl TryFrom<u16> for Opcode {
         Ok(Opcode(ogf, ocf))
     }
 }
+/// 12-bit Connection Handle assigned by the controller to a connection. Range 0x0000-0x0EFF.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+pub struct ConnectionHandle(u16);
+impl ConnectionHandle {
+    pub const MAX_HANDLE_U16: u16 = 0x0EFF;
+    pub const BYTE_LEN: usize = 2;
+    /// Creates a new `ConnectionHandle`.
+    /// # Panics
+    /// Panics if `handle > ConnectionHandle::MAX_HANDLE_U16` (0x0EFF).
+    pub fn new(handle: u16) -> ConnectionHandle {
+        assert!(
+            handle <= Self::MAX_HANDLE_U16,
+            "invalid connection handle '{}'",
+            handle
+        );
+        ConnectionHandle(handle)
+    }
+    /// # Errors
+    /// returns `PackError::BadLength` if `buf.len() != ConnectionHandle::BYTE_LEN`.
+    pub fn pack_into(self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf.copy_from_slice(&self.0.to_bytes_le());
+        Ok(())
+    }
+    /// # Errors
+    /// returns `PackError::BadLength` if `buf.len() != ConnectionHandle::BYTE_LEN`.
+    /// returns `PackError::BadBytes` if `buf` doesn't contain a valid handle.
+    pub fn unpack_from(buf: &[u8]) -> Result<ConnectionHandle, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        ConnectionHandle::try_from(u16::from_le_bytes([buf[0], buf[1]]))
+            .map_err(|_| PackError::bad_index(0))
+    }
+}
+impl From<ConnectionHandle> for u16 {
+    fn from(handle: ConnectionHandle) -> Self {
+        handle.0
+    }
+}
+impl TryFrom<u16> for ConnectionHandle {
+    type Error = ConversionError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        if value <= Self::MAX_HANDLE_U16 {
+            Ok(ConnectionHandle(value))
+        } else {
+            Err(ConversionError(()))
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{adapter, ErrorCode, Opcode};
+    use core::convert::TryFrom;
+
+    #[test]
+    fn test_error_code_round_trip() {
+        let mut count = 0;
+        for value in 0..=u8::MAX {
+            match ErrorCode::try_from(value) {
+                Ok(code) => {
+                    assert_eq!(u8::from(code), value);
+                    assert!(!code.description().is_empty());
+                    assert!(!code.as_str().is_empty());
+                    count += 1;
+                }
+                // Reserved codes and everything after `PacketTooLong`.
+                Err(_) => assert!(value == 0x2B || value == 0x31 || value == 0x33 || value > 0x45),
+            }
+        }
+        assert_eq!(count, 0x46 - 3);
+    }
+    #[test]
+    fn test_error_code_display() {
+        assert_eq!(
+            alloc::format!("{}", ErrorCode::ControllerBusy),
+            "Controller Busy (0x3A)"
+        );
+        assert_eq!(ErrorCode::Ok.error(), Ok(()));
+        assert_eq!(ErrorCode::MemoryFull.error(), Err(ErrorCode::MemoryFull));
+    }
+    #[test]
+    fn test_error_classification() {
+        assert!(ErrorCode::ControllerBusy.is_retryable());
+        assert!(!ErrorCode::Ok.is_retryable());
+        assert!(!ErrorCode::UnknownHCICommand.is_retryable());
+        assert!(ErrorCode::ConnectionTimeout.is_connection_termination());
+        assert!(!ErrorCode::ControllerBusy.is_connection_termination());
+        assert!(adapter::Error::ErrorCode(ErrorCode::ControllerBusy).is_retryable());
+        assert!(adapter::Error::CommandTimeout(Opcode::nop()).is_retryable());
+        assert!(!adapter::Error::ErrorCode(ErrorCode::MemoryFull).is_retryable());
+        assert!(!adapter::Error::BadParameter.is_retryable());
+    }
+}
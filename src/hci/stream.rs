@@ -5,13 +5,15 @@ use crate::error;
 use crate::hci::command::Command;
 use crate::hci::event::{Event, EventCode, EventPacket};
 use crate::hci::packet::{PacketType, RawPacket};
+use crate::hci::timer::{self, Timer};
 use crate::hci::{Opcode, StreamError, FULL_COMMAND_MAX_LEN};
 use crate::PackError;
 use core::convert::{TryFrom, TryInto};
 use core::future::Future;
 use core::pin::Pin;
 use core::task::{Context, Poll};
-use futures_util::future::poll_fn;
+use core::time::Duration;
+use futures_util::future::{poll_fn, LocalBoxFuture};
 
 impl From<PackError> for StreamError {
     fn from(e: PackError) -> Self {
@@ -138,7 +140,6 @@ pub trait HCIReader: Unpin {
 pub struct Stream<S: HCIReader> {
     pub stream: S,
 }
-pub const HCI_EVENT_READ_TRIES: usize = 20;
 impl<S: HCIReader> Stream<S> {
     pub fn new(stream: S) -> Self {
         Self { stream }
@@ -156,9 +157,35 @@ impl<S: HCIReader> Stream<S> {
         }
         poll_fn(|cx| self.as_mut().stream_pinned().poll_flush(cx)).await
     }
+    /// Send `command` and wait for its `Cmd::Return` event. Waits forever, see
+    /// [`Stream::send_command_timeout`].
     pub async fn send_command<Cmd: Command>(
+        self: Pin<&mut Self>,
+        command: Cmd,
+    ) -> Result<Cmd::Return, StreamError>
+    where
+        S: HCIWriter + HCIFilterable,
+    {
+        self.send_command_until(command, None).await
+    }
+    /// Same as [`Stream::send_command`] but returns `StreamError::CommandTimeout` if the
+    /// `Cmd::Return` event doesn't arrive within `timeout` (measured with `timer`).
+    pub async fn send_command_timeout<Cmd: Command>(
+        self: Pin<&mut Self>,
+        command: Cmd,
+        timer: &dyn Timer,
+        timeout: Duration,
+    ) -> Result<Cmd::Return, StreamError>
+    where
+        S: HCIWriter + HCIFilterable,
+    {
+        self.send_command_until(command, Some(timer.sleep(timeout)))
+            .await
+    }
+    async fn send_command_until<Cmd: Command>(
         mut self: Pin<&mut Self>,
         command: Cmd,
+        sleep: Option<LocalBoxFuture<'static, ()>>,
     ) -> Result<Cmd::Return, StreamError>
     where
         S: HCIWriter + HCIFilterable,
@@ -192,18 +219,22 @@ impl<S: HCIReader> Stream<S> {
         self.as_mut().send_exact(&buf[..len]).await?;
 
         // Wait for response
-        for _try_i in 0..HCI_EVENT_READ_TRIES {
-            // Reuse `buf` to read the RawPacket
-            let event = EventPacket::try_from(self.as_mut().read_packet(&mut buf[..]).await?)?;
-            if event.event_code() == Cmd::Return::EVENT_CODE {
-                if Opcode::unpack(&event.parameters().as_ref()[1..3])? == Cmd::opcode() {
-                    self.stream_pinned().set_filter(&old_filter)?;
-                    return Cmd::Return::event_unpack_from(event.parameters())
-                        .map_err(StreamError::CommandError);
+        let read = async {
+            loop {
+                // Reuse `buf` to read the RawPacket
+                let event = EventPacket::try_from(self.as_mut().read_packet(&mut buf[..]).await?)?;
+                if let Some(ret) =
+                    Cmd::unpack_return(event.as_ref()).map_err(StreamError::CommandError)?
+                {
+                    return Ok(ret);
                 }
             }
-        }
-        Err(StreamError::StreamFailed)
+        };
+        let ret = timer::timeout(sleep, read)
+            .await
+            .unwrap_or(Err(StreamError::CommandTimeout));
+        self.stream_pinned().set_filter(&old_filter)?;
+        ret
     }
     pub async fn read_bytes(
         mut self: Pin<&mut Self>,
@@ -0,0 +1,176 @@
+//! Pluggable timers for HCI command timeouts. `no_std` targets implement [`Timer`] with whatever
+//! timer their executor provides. [`ThreadTimer`] is a `std` implementation.
+use alloc::boxed::Box;
+use core::future::Future;
+use core::time::Duration;
+use futures_util::future::{select, Either, LocalBoxFuture};
+
+/// Source of sleep futures.
+pub trait Timer {
+    /// Returns a future that completes once `duration` has passed.
+    fn sleep(&self, duration: Duration) -> LocalBoxFuture<'static, ()>;
+}
+impl<T: Timer + ?Sized> Timer for &T {
+    fn sleep(&self, duration: Duration) -> LocalBoxFuture<'static, ()> {
+        T::sleep(self, duration)
+    }
+}
+impl<T: Timer + ?Sized> Timer for Box<T> {
+    fn sleep(&self, duration: Duration) -> LocalBoxFuture<'static, ()> {
+        T::sleep(self, duration)
+    }
+}
+/// Returned by [`timeout`] if the timer finished before the future.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct Elapsed(());
+/// Polls `future` until it finishes or `sleep` (usually from [`Timer::sleep`]) finishes. If
+/// `sleep` is `None`, `future` never times out.
+pub async fn timeout<F: Future>(
+    sleep: Option<LocalBoxFuture<'_, ()>>,
+    future: F,
+) -> Result<F::Output, Elapsed> {
+    let future = Box::pin(future);
+    match sleep {
+        None => Ok(future.await),
+        Some(sleep) => match select(future, sleep).await {
+            Either::Left((output, _)) => Ok(output),
+            Either::Right(((), _)) => Err(Elapsed(())),
+        },
+    }
+}
+/// [`Timer`] backed by one background thread shared by every clone. Works with any executor. The
+/// thread stops once every clone is dropped and every pending sleep has finished.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct ThreadTimer {
+    handle: std::sync::Arc<thread_timer::Handle>,
+}
+#[cfg(feature = "std")]
+impl ThreadTimer {
+    /// Starts the timer thread.
+    pub fn new() -> ThreadTimer {
+        ThreadTimer {
+            handle: std::sync::Arc::new(thread_timer::Handle::spawn()),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl Default for ThreadTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+#[cfg(feature = "std")]
+impl Timer for ThreadTimer {
+    fn sleep(&self, duration: Duration) -> LocalBoxFuture<'static, ()> {
+        self.handle.sleep(duration)
+    }
+}
+#[cfg(feature = "std")]
+mod thread_timer {
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+    use core::task::{Poll, Waker};
+    use core::time::Duration;
+    use futures_util::future::LocalBoxFuture;
+    use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+    use std::time::Instant;
+
+    /// (finished, waker to wake when finished)
+    type SleepState = Arc<Mutex<(bool, Option<Waker>)>>;
+    #[derive(Default)]
+    struct State {
+        sleeps: Vec<(Instant, SleepState)>,
+        stopped: bool,
+    }
+    #[derive(Default)]
+    struct Shared {
+        state: Mutex<State>,
+        changed: Condvar,
+    }
+    impl Shared {
+        fn lock(&self) -> MutexGuard<'_, State> {
+            self.state.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+    }
+    /// Owns the timer thread. Dropping it (the last `ThreadTimer`) lets the thread stop.
+    pub(super) struct Handle {
+        shared: Arc<Shared>,
+    }
+    impl core::fmt::Debug for Handle {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("Handle")
+                .field("sleeps", &self.shared.lock().sleeps.len())
+                .finish()
+        }
+    }
+    impl Handle {
+        pub(super) fn spawn() -> Handle {
+            let shared = Arc::new(Shared::default());
+            let thread_shared = shared.clone();
+            std::thread::spawn(move || run(&thread_shared));
+            Handle { shared }
+        }
+        pub(super) fn sleep(&self, duration: Duration) -> LocalBoxFuture<'static, ()> {
+            let sleep: SleepState = Arc::new(Mutex::new((false, None)));
+            self.shared
+                .lock()
+                .sleeps
+                .push((Instant::now() + duration, sleep.clone()));
+            self.shared.changed.notify_one();
+            Box::pin(futures_util::future::poll_fn(move |cx| {
+                let mut sleep = sleep.lock().unwrap_or_else(PoisonError::into_inner);
+                if sleep.0 {
+                    Poll::Ready(())
+                } else {
+                    sleep.1 = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }))
+        }
+    }
+    impl Drop for Handle {
+        fn drop(&mut self) {
+            self.shared.lock().stopped = true;
+            self.shared.changed.notify_one();
+        }
+    }
+    /// Timer thread. Finishes sleeps as their deadlines pass.
+    fn run(shared: &Shared) {
+        let mut state = shared.lock();
+        loop {
+            let now = Instant::now();
+            // Finish elapsed sleeps and forget the ones whose future was dropped.
+            state.sleeps.retain(|(deadline, sleep)| {
+                if Arc::strong_count(sleep) == 1 {
+                    return false;
+                }
+                if *deadline > now {
+                    return true;
+                }
+                let mut sleep = sleep.lock().unwrap_or_else(PoisonError::into_inner);
+                sleep.0 = true;
+                if let Some(waker) = sleep.1.take() {
+                    waker.wake();
+                }
+                false
+            });
+            let next = state.sleeps.iter().map(|(deadline, _)| *deadline).min();
+            state = match next {
+                Some(deadline) => {
+                    let wait = deadline.saturating_duration_since(Instant::now());
+                    shared
+                        .changed
+                        .wait_timeout(state, wait)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+                None if state.stopped => return,
+                None => shared
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+}
@@ -118,7 +118,11 @@ impl TryFrom<u8> for PeerAddressType {
 pub enum OwnAddressType {
     PublicDevice = 0x00,
     RandomDevice = 0x01,
+    /// Resolvable private address generated by the controller from the Resolving List (see
+    /// `LEAdapter::load_resolving_list`). Falls back to the public address if there's no entry.
     PrivateOrPublic = 0x02,
+    /// Resolvable private address generated by the controller from the Resolving List. Falls
+    /// back to the random address if there's no entry.
     PrivateOrRandom = 0x03,
 }
 impl OwnAddressType {
@@ -0,0 +1,405 @@
+//! LE Connection parameters and types.
+use crate::le::scan::{OwnAddressType, ScanInterval, ScanWindow};
+use crate::ConversionError;
+use core::convert::TryFrom;
+use core::time::Duration;
+
+/// Connection interval. Range 0x0006 --> 0x0C80. Time = N * 1.25 ms.
+/// Time Range 7.5 ms --> 4 s.
+/// # Examples
+/// ```
+/// use btle::le::connection::ConnectionInterval;
+/// use core::convert::TryFrom;
+/// use core::time::Duration;
+/// let interval = ConnectionInterval::try_from(Duration::from_micros(7_500)).unwrap();
+/// assert_eq!(u16::from(interval), 0x0006);
+/// assert_eq!(Duration::from(interval), Duration::from_micros(7_500));
+/// assert!(ConnectionInterval::try_from(Duration::from_secs(5)).is_err());
+/// ```
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ConnectionInterval(u16);
+impl ConnectionInterval {
+    pub const BYTE_LEN: usize = 2;
+    pub const MIN_U16: u16 = 0x0006u16;
+    pub const MIN: ConnectionInterval = ConnectionInterval(Self::MIN_U16);
+    pub const MAX_U16: u16 = 0x0C80u16;
+    pub const MAX: ConnectionInterval = ConnectionInterval(Self::MAX_U16);
+    /// 50 ms.
+    pub const DEFAULT_U16: u16 = 0x0028u16;
+    pub const DEFAULT: ConnectionInterval = ConnectionInterval(Self::DEFAULT_U16);
+    /// Creates a new `ConnectionInterval`.
+    /// # Panics
+    /// Panics if
+    /// `interval < ConnectionInterval::MIN_U16 || interval > ConnectionInterval::MAX_U16`.
+    pub fn new(interval: u16) -> ConnectionInterval {
+        assert!(
+            interval >= Self::MIN_U16 && interval <= Self::MAX_U16,
+            "invalid connection interval '{}'",
+            interval
+        );
+        ConnectionInterval(interval)
+    }
+    pub fn as_microseconds(self) -> u32 {
+        u32::from(u16::from(self)) * 1250
+    }
+}
+impl Default for ConnectionInterval {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+impl TryFrom<u16> for ConnectionInterval {
+    type Error = ConversionError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        if value >= Self::MIN_U16 && value <= Self::MAX_U16 {
+            Ok(Self(value))
+        } else {
+            Err(ConversionError(()))
+        }
+    }
+}
+impl From<ConnectionInterval> for u16 {
+    fn from(i: ConnectionInterval) -> Self {
+        i.0
+    }
+}
+impl From<ConnectionInterval> for Duration {
+    fn from(i: ConnectionInterval) -> Self {
+        Duration::from_micros(i.as_microseconds().into())
+    }
+}
+impl TryFrom<Duration> for ConnectionInterval {
+    type Error = ConversionError;
+
+    /// Converts `Duration` to a `ConnectionInterval` (rounded down to the nearest 1.25 ms).
+    fn try_from(value: Duration) -> Result<Self, Self::Error> {
+        let units = u16::try_from(value.as_micros() / 1250).map_err(|_| ConversionError(()))?;
+        Self::try_from(units)
+    }
+}
+/// Slave latency in number of connection events. Range 0x0000 --> 0x01F3.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SlaveLatency(u16);
+impl SlaveLatency {
+    pub const BYTE_LEN: usize = 2;
+    pub const MIN_U16: u16 = 0x0000u16;
+    pub const MIN: SlaveLatency = SlaveLatency(Self::MIN_U16);
+    pub const MAX_U16: u16 = 0x01F3u16;
+    pub const MAX: SlaveLatency = SlaveLatency(Self::MAX_U16);
+    pub const DEFAULT_U16: u16 = 0x0000u16;
+    pub const DEFAULT: SlaveLatency = SlaveLatency(Self::DEFAULT_U16);
+    /// Creates a new `SlaveLatency`.
+    /// # Panics
+    /// Panics if `latency > SlaveLatency::MAX_U16`.
+    pub fn new(latency: u16) -> SlaveLatency {
+        assert!(
+            latency <= Self::MAX_U16,
+            "invalid slave latency '{}'",
+            latency
+        );
+        SlaveLatency(latency)
+    }
+}
+impl Default for SlaveLatency {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+impl TryFrom<u16> for SlaveLatency {
+    type Error = ConversionError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        if value <= Self::MAX_U16 {
+            Ok(Self(value))
+        } else {
+            Err(ConversionError(()))
+        }
+    }
+}
+impl From<SlaveLatency> for u16 {
+    fn from(l: SlaveLatency) -> Self {
+        l.0
+    }
+}
+/// Supervision timeout. Range 0x000A --> 0x0C80. Time = N * 10 ms. Time Range 100 ms --> 32 s.
+///
+/// # Important
+/// The supervision timeout (in ms) should be larger than
+/// `(1 + SlaveLatency) * ConnectionInterval * 2` (see [`SupervisionTimeout::is_valid_for`]).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SupervisionTimeout(u16);
+impl SupervisionTimeout {
+    pub const BYTE_LEN: usize = 2;
+    pub const MIN_U16: u16 = 0x000Au16;
+    pub const MIN: SupervisionTimeout = SupervisionTimeout(Self::MIN_U16);
+    pub const MAX_U16: u16 = 0x0C80u16;
+    pub const MAX: SupervisionTimeout = SupervisionTimeout(Self::MAX_U16);
+    /// 4 s.
+    pub const DEFAULT_U16: u16 = 0x0190u16;
+    pub const DEFAULT: SupervisionTimeout = SupervisionTimeout(Self::DEFAULT_U16);
+    /// Creates a new `SupervisionTimeout`.
+    /// # Panics
+    /// Panics if
+    /// `timeout < SupervisionTimeout::MIN_U16 || timeout > SupervisionTimeout::MAX_U16`.
+    pub fn new(timeout: u16) -> SupervisionTimeout {
+        assert!(
+            timeout >= Self::MIN_U16 && timeout <= Self::MAX_U16,
+            "invalid supervision timeout '{}'",
+            timeout
+        );
+        SupervisionTimeout(timeout)
+    }
+    pub fn as_microseconds(self) -> u32 {
+        u32::from(u16::from(self)) * 10_000
+    }
+    /// Returns `true` if the timeout is larger than `(1 + latency) * interval_max * 2` as
+    /// required by the spec.
+    pub fn is_valid_for(self, interval_max: ConnectionInterval, latency: SlaveLatency) -> bool {
+        u64::from(self.as_microseconds())
+            > (1 + u64::from(u16::from(latency))) * u64::from(interval_max.as_microseconds()) * 2
+    }
+}
+impl Default for SupervisionTimeout {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+impl TryFrom<u16> for SupervisionTimeout {
+    type Error = ConversionError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        if value >= Self::MIN_U16 && value <= Self::MAX_U16 {
+            Ok(Self(value))
+        } else {
+            Err(ConversionError(()))
+        }
+    }
+}
+impl From<SupervisionTimeout> for u16 {
+    fn from(t: SupervisionTimeout) -> Self {
+        t.0
+    }
+}
+impl From<SupervisionTimeout> for Duration {
+    fn from(t: SupervisionTimeout) -> Self {
+        Duration::from_micros(t.as_microseconds().into())
+    }
+}
+impl TryFrom<Duration> for SupervisionTimeout {
+    type Error = ConversionError;
+
+    /// Converts `Duration` to a `SupervisionTimeout` (rounded down to the nearest 10 ms).
+    fn try_from(value: Duration) -> Result<Self, Self::Error> {
+        let units = u16::try_from(value.as_millis() / 10).map_err(|_| ConversionError(()))?;
+        Self::try_from(units)
+    }
+}
+/// Length of connection event. Range 0x0000 --> 0xFFFF. Time = N * 0.625 ms.
+/// Only a hint to the controller.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct CELength(pub u16);
+impl CELength {
+    pub const BYTE_LEN: usize = 2;
+    pub const ZEROED: CELength = CELength(0);
+    pub fn new(length: u16) -> CELength {
+        CELength(length)
+    }
+    pub fn as_microseconds(self) -> u32 {
+        u32::from(u16::from(self)) * 625
+    }
+}
+impl From<u16> for CELength {
+    fn from(length: u16) -> Self {
+        CELength(length)
+    }
+}
+impl From<CELength> for u16 {
+    fn from(l: CELength) -> Self {
+        l.0
+    }
+}
+impl From<CELength> for Duration {
+    fn from(l: CELength) -> Self {
+        Duration::from_micros(l.as_microseconds().into())
+    }
+}
+impl TryFrom<Duration> for CELength {
+    type Error = ConversionError;
+
+    /// Converts `Duration` to a `CELength` (rounded down to the nearest 0.625 ms).
+    fn try_from(value: Duration) -> Result<Self, Self::Error> {
+        Ok(CELength(
+            u16::try_from(value.as_micros() / 625).map_err(|_| ConversionError(()))?,
+        ))
+    }
+}
+
+/// Role of the local device in a connection.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub enum Role {
+    Master = 0x00,
+    Slave = 0x01,
+}
+impl From<Role> for u8 {
+    fn from(r: Role) -> Self {
+        r as u8
+    }
+}
+impl TryFrom<u8> for Role {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(Role::Master),
+            0x01 => Ok(Role::Slave),
+            _ => Err(ConversionError(())),
+        }
+    }
+}
+/// Which advertisers the initiator connects to.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub enum InitiatorFilterPolicy {
+    /// Only connect to the peer address given in the connect command. White List isn't used.
+    PeerAddress = 0x00,
+    /// Connect to any device in the White List. The peer address is ignored.
+    Whitelist = 0x01,
+}
+impl InitiatorFilterPolicy {
+    pub const DEFAULT: InitiatorFilterPolicy = InitiatorFilterPolicy::PeerAddress;
+}
+impl Default for InitiatorFilterPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+impl From<InitiatorFilterPolicy> for u8 {
+    fn from(p: InitiatorFilterPolicy) -> Self {
+        p as u8
+    }
+}
+impl TryFrom<u8> for InitiatorFilterPolicy {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(InitiatorFilterPolicy::PeerAddress),
+            0x01 => Ok(InitiatorFilterPolicy::Whitelist),
+            _ => Err(ConversionError(())),
+        }
+    }
+}
+/// Clock accuracy of the master of a connection.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub enum MasterClockAccuracy {
+    PPM500 = 0x00,
+    PPM250 = 0x01,
+    PPM150 = 0x02,
+    PPM100 = 0x03,
+    PPM75 = 0x04,
+    PPM50 = 0x05,
+    PPM30 = 0x06,
+    PPM20 = 0x07,
+}
+impl MasterClockAccuracy {
+    /// Clock accuracy in parts per million.
+    pub fn as_ppm(self) -> u16 {
+        match self {
+            MasterClockAccuracy::PPM500 => 500,
+            MasterClockAccuracy::PPM250 => 250,
+            MasterClockAccuracy::PPM150 => 150,
+            MasterClockAccuracy::PPM100 => 100,
+            MasterClockAccuracy::PPM75 => 75,
+            MasterClockAccuracy::PPM50 => 50,
+            MasterClockAccuracy::PPM30 => 30,
+            MasterClockAccuracy::PPM20 => 20,
+        }
+    }
+}
+impl From<MasterClockAccuracy> for u8 {
+    fn from(a: MasterClockAccuracy) -> Self {
+        a as u8
+    }
+}
+impl TryFrom<u8> for MasterClockAccuracy {
+    type Error = ConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(MasterClockAccuracy::PPM500),
+            0x01 => Ok(MasterClockAccuracy::PPM250),
+            0x02 => Ok(MasterClockAccuracy::PPM150),
+            0x03 => Ok(MasterClockAccuracy::PPM100),
+            0x04 => Ok(MasterClockAccuracy::PPM75),
+            0x05 => Ok(MasterClockAccuracy::PPM50),
+            0x06 => Ok(MasterClockAccuracy::PPM30),
+            0x07 => Ok(MasterClockAccuracy::PPM20),
+            _ => Err(ConversionError(())),
+        }
+    }
+}
+/// Connection parameters that can be changed on an existing connection (LE Connection Update or
+/// a reply to a remote connection parameter request).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct ConnectionUpdateParameters {
+    pub interval_min: ConnectionInterval,
+    pub interval_max: ConnectionInterval,
+    pub latency: SlaveLatency,
+    pub supervision_timeout: SupervisionTimeout,
+    pub min_ce_length: CELength,
+    pub max_ce_length: CELength,
+}
+impl ConnectionUpdateParameters {
+    /// interval_min (2) + interval_max (2) + latency (2) + supervision_timeout (2) +
+    /// min_ce_length (2) + max_ce_length (2)
+    pub const BYTE_LEN: usize = ConnectionInterval::BYTE_LEN * 2
+        + SlaveLatency::BYTE_LEN
+        + SupervisionTimeout::BYTE_LEN
+        + CELength::BYTE_LEN * 2;
+    pub const DEFAULT: ConnectionUpdateParameters = ConnectionUpdateParameters {
+        interval_min: ConnectionInterval::DEFAULT,
+        interval_max: ConnectionInterval::DEFAULT,
+        latency: SlaveLatency::DEFAULT,
+        supervision_timeout: SupervisionTimeout::DEFAULT,
+        min_ce_length: CELength::ZEROED,
+        max_ce_length: CELength::ZEROED,
+    };
+    /// Returns `true` if `interval_min <= interval_max`, `min_ce_length <= max_ce_length` and
+    /// the supervision timeout is long enough (see [`SupervisionTimeout::is_valid_for`]).
+    pub fn is_valid(&self) -> bool {
+        self.interval_min <= self.interval_max
+            && self.min_ce_length <= self.max_ce_length
+            && self
+                .supervision_timeout
+                .is_valid_for(self.interval_max, self.latency)
+    }
+}
+impl Default for ConnectionUpdateParameters {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+/// Parameters used when initiating a connection (everything in LE Create Connection except
+/// the peer address).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct ConnectionParameters {
+    pub scan_interval: ScanInterval,
+    pub scan_window: ScanWindow,
+    pub initiator_filter_policy: InitiatorFilterPolicy,
+    pub own_address_type: OwnAddressType,
+    pub connection: ConnectionUpdateParameters,
+}
+impl ConnectionParameters {
+    pub const DEFAULT: ConnectionParameters = ConnectionParameters {
+        scan_interval: ScanInterval::DEFAULT,
+        scan_window: ScanWindow::DEFAULT,
+        initiator_filter_policy: InitiatorFilterPolicy::DEFAULT,
+        own_address_type: OwnAddressType::Public,
+        connection: ConnectionUpdateParameters::DEFAULT,
+    };
+}
+impl Default for ConnectionParameters {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
@@ -2,5 +2,7 @@ pub mod adapter;
 pub mod advertisement;
 pub mod advertisement_structures;
 pub mod advertiser;
+pub mod connection;
+pub mod privacy;
 pub mod report;
 pub mod scan;
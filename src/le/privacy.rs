@@ -0,0 +1,451 @@
+//! LE Privacy types ([`IdentityResolvingKey`], [`IdentityAddress`]). Devices using privacy
+//! advertise with resolvable private addresses that only the holders of their IRK can map back
+//! to their identity address. [`generate_rpa`], [`resolve_rpa`] and [`IdentityResolvingKeySet`]
+//! do that on the host for controllers without (or with a too small) Resolving List.
+use crate::crypto::{self, CryptoProvider};
+use crate::le::advertisement::StaticAdvBuffer;
+use crate::le::advertiser::PeerAddressType;
+use crate::le::report::{AddressType, ReportInfo};
+use crate::{BTAddress, PackError, BT_ADDRESS_LEN};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+pub const IRK_LEN: usize = 16;
+/// Identity Resolving Key (IRK). Stored least significant octet first (the order used by HCI
+/// and the Security Manager).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct IdentityResolvingKey(pub [u8; IRK_LEN]);
+impl IdentityResolvingKey {
+    pub const BYTE_LEN: usize = IRK_LEN;
+    /// All zero IRK. Used in the resolving list for devices that don't use privacy.
+    pub const ZEROED: IdentityResolvingKey = IdentityResolvingKey([0_u8; IRK_LEN]);
+    /// Creates an IRK from `bytes` in most significant octet first order (how keys are usually
+    /// written down).
+    pub fn from_be_bytes(mut bytes: [u8; IRK_LEN]) -> IdentityResolvingKey {
+        bytes.reverse();
+        IdentityResolvingKey(bytes)
+    }
+    /// Returns the IRK in most significant octet first order.
+    pub fn to_be_bytes(self) -> [u8; IRK_LEN] {
+        let mut bytes = self.0;
+        bytes.reverse();
+        bytes
+    }
+    pub fn is_zeroed(&self) -> bool {
+        *self == Self::ZEROED
+    }
+    /// Random address hash `ah(irk, prand)`. `prand` and the returned hash are least significant
+    /// octet first (the order they're stored in a `BTAddress`).
+    pub fn hash(&self, mut prand: [u8; PRAND_LEN]) -> [u8; HASH_LEN] {
+        prand.reverse();
+        let mut hash = crypto::ah(&self.to_be_bytes(), prand);
+        hash.reverse();
+        hash
+    }
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf.copy_from_slice(&self.0[..]);
+        Ok(())
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        let mut key = [0_u8; IRK_LEN];
+        key.copy_from_slice(buf);
+        Ok(IdentityResolvingKey(key))
+    }
+}
+/// Public or static random address that identifies a device using privacy.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct IdentityAddress {
+    pub address_type: PeerAddressType,
+    pub address: BTAddress,
+}
+impl IdentityAddress {
+    pub const BYTE_LEN: usize = 1 + BT_ADDRESS_LEN;
+    pub fn new(address_type: PeerAddressType, address: BTAddress) -> IdentityAddress {
+        IdentityAddress {
+            address_type,
+            address,
+        }
+    }
+    pub fn pack_into(&self, buf: &mut [u8]) -> Result<(), PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        buf[0] = self.address_type.into();
+        self.address.pack_into(&mut buf[1..])
+    }
+    pub fn unpack_from(buf: &[u8]) -> Result<Self, PackError> {
+        PackError::expect_length(Self::BYTE_LEN, buf)?;
+        Ok(IdentityAddress {
+            address_type: PeerAddressType::try_from(buf[0]).map_err(|_| PackError::bad_index(0))?,
+            address: BTAddress::unpack_from(&buf[1..])?,
+        })
+    }
+}
+pub const PRAND_LEN: usize = 3;
+pub const HASH_LEN: usize = 3;
+/// Mask for the two most significant bits of `prand` (the address type bits of the address).
+const PRAND_TYPE_MASK: u8 = 0b1100_0000;
+const RESOLVABLE_BITS: u8 = 0b0100_0000;
+/// Creates a resolvable private address from `irk` and `prand` (least significant octet first).
+/// The two most significant bits of `prand` are overwritten with `0b01`. Returns `None` if the
+/// remaining 22 random bits are all 0s or all 1s.
+/// # Examples
+/// Core spec Vol 3, Part H, D.7 sample data:
+/// ```
+/// use btle::BTAddress;
+/// use btle::le::privacy::{new_rpa, resolve_rpa, IdentityResolvingKey};
+/// let irk = IdentityResolvingKey::from_be_bytes([
+///     0xEC, 0x02, 0x34, 0xA3, 0x57, 0xC8, 0xAD, 0x05,
+///     0x34, 0x10, 0x10, 0xA6, 0x0A, 0x39, 0x7D, 0x9B,
+/// ]);
+/// let address = BTAddress([0xAA, 0xFB, 0x0D, 0x94, 0x81, 0x70]);
+/// assert_eq!(new_rpa(&irk, [0x94, 0x81, 0x70]), Some(address));
+/// assert!(resolve_rpa(address, &irk));
+/// assert!(!resolve_rpa(address, &IdentityResolvingKey::ZEROED));
+/// ```
+pub fn new_rpa(irk: &IdentityResolvingKey, prand: [u8; PRAND_LEN]) -> Option<BTAddress> {
+    let prand = prand_with_type_bits(prand)?;
+    Some(rpa_from_parts(irk.hash(prand), prand))
+}
+/// Sets the two most significant bits of `prand` to `0b01`. Returns `None` if the remaining 22
+/// random bits are all 0s or all 1s.
+fn prand_with_type_bits(mut prand: [u8; PRAND_LEN]) -> Option<[u8; PRAND_LEN]> {
+    prand[PRAND_LEN - 1] = (prand[PRAND_LEN - 1] & !PRAND_TYPE_MASK) | RESOLVABLE_BITS;
+    let top = prand[PRAND_LEN - 1] & !PRAND_TYPE_MASK;
+    let rest = &prand[..PRAND_LEN - 1];
+    if (top == 0 && rest.iter().all(|b| *b == 0))
+        || (top == !PRAND_TYPE_MASK && rest.iter().all(|b| *b == 0xFF))
+    {
+        None
+    } else {
+        Some(prand)
+    }
+}
+fn rpa_from_parts(hash: [u8; HASH_LEN], prand: [u8; PRAND_LEN]) -> BTAddress {
+    let mut address = [0_u8; BT_ADDRESS_LEN];
+    address[..HASH_LEN].copy_from_slice(&hash);
+    address[HASH_LEN..].copy_from_slice(&prand);
+    BTAddress(address)
+}
+/// Generates a resolvable private address for `irk` using `fill` to fill a byte buffer with
+/// random bytes. `fill` is called until it produces a valid `prand` (usually only once).
+pub fn generate_rpa<F: FnMut(&mut [u8])>(irk: &IdentityResolvingKey, mut fill: F) -> BTAddress {
+    loop {
+        let mut prand = [0_u8; PRAND_LEN];
+        fill(&mut prand[..]);
+        if let Some(address) = new_rpa(irk, prand) {
+            return address;
+        }
+    }
+}
+/// Generates a resolvable private address for `irk` using `provider` for the random bytes and
+/// the encryption (so the controller can do it, see `LEAdapter`).
+pub async fn generate_rpa_with<P: CryptoProvider>(
+    provider: &mut P,
+    irk: &IdentityResolvingKey,
+) -> Result<BTAddress, P::Error> {
+    loop {
+        let mut random = [0_u8; PRAND_LEN];
+        provider.rand(&mut random[..]).await?;
+        if let Some(prand) = prand_with_type_bits(random) {
+            let mut r = prand;
+            r.reverse();
+            let mut hash = crypto::ah_with(provider, irk.to_be_bytes(), r).await?;
+            hash.reverse();
+            return Ok(rpa_from_parts(hash, prand));
+        }
+    }
+}
+/// Returns `true` if `address` is a resolvable private address generated from `irk`.
+pub fn resolve_rpa(address: BTAddress, irk: &IdentityResolvingKey) -> bool {
+    if !address.is_resolvable_private() {
+        return false;
+    }
+    let mut prand = [0_u8; PRAND_LEN];
+    prand.copy_from_slice(&address.0[HASH_LEN..]);
+    address.0[..HASH_LEN] == irk.hash(prand)
+}
+/// Identity addresses and IRKs of peers, used to resolve their resolvable private addresses on
+/// the host.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct IdentityResolvingKeySet {
+    keys: Vec<(IdentityAddress, IdentityResolvingKey)>,
+}
+impl IdentityResolvingKeySet {
+    pub fn new() -> IdentityResolvingKeySet {
+        IdentityResolvingKeySet { keys: Vec::new() }
+    }
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &(IdentityAddress, IdentityResolvingKey)> {
+        self.keys.iter()
+    }
+    pub fn get(&self, identity: &IdentityAddress) -> Option<&IdentityResolvingKey> {
+        self.keys
+            .iter()
+            .find(|(i, _)| i == identity)
+            .map(|(_, irk)| irk)
+    }
+    /// Insert the `irk` of `identity`. Returns the old IRK if `identity` was already in the set.
+    pub fn insert(
+        &mut self,
+        identity: IdentityAddress,
+        irk: IdentityResolvingKey,
+    ) -> Option<IdentityResolvingKey> {
+        if let Some((_, old)) = self.keys.iter_mut().find(|(i, _)| *i == identity) {
+            Some(core::mem::replace(old, irk))
+        } else {
+            self.keys.push((identity, irk));
+            None
+        }
+    }
+    pub fn remove(&mut self, identity: &IdentityAddress) -> Option<IdentityResolvingKey> {
+        let index = self.keys.iter().position(|(i, _)| i == identity)?;
+        Some(self.keys.remove(index).1)
+    }
+    /// Returns the identity `address` resolves to if it's a resolvable private address generated
+    /// from one of the IRKs in the set.
+    pub fn resolve(&self, address: BTAddress) -> Option<&IdentityAddress> {
+        if !address.is_resolvable_private() {
+            return None;
+        }
+        self.keys
+            .iter()
+            .find(|(_, irk)| resolve_rpa(address, irk))
+            .map(|(identity, _)| identity)
+    }
+    /// Returns the identity in the set that sent `report`. Resolvable private addresses are
+    /// resolved with the IRKs, identity addresses (including the ones already resolved by the
+    /// controller) are looked up directly.
+    pub fn resolve_report<T>(&self, report: &ReportInfo<T>) -> Option<&IdentityAddress> {
+        let address_type = match report.address_type {
+            AddressType::RandomDevice if report.address.is_resolvable_private() => {
+                return self.resolve(report.address)
+            }
+            AddressType::PublicDevice | AddressType::PublicIdentity => PeerAddressType::Public,
+            AddressType::RandomDevice | AddressType::RandomIdentity => PeerAddressType::Random,
+        };
+        let identity = IdentityAddress::new(address_type, report.address);
+        self.keys.iter().map(|(i, _)| i).find(|i| **i == identity)
+    }
+    /// Tags `report` with the identity it resolves to (if any).
+    pub fn tag<T>(&self, report: ReportInfo<T>) -> TaggedReportInfo<T> {
+        TaggedReportInfo {
+            identity: self.resolve_report(&report).copied(),
+            report,
+        }
+    }
+}
+/// [`ReportInfo`] tagged with the identity of the advertiser (if it was resolved).
+#[derive(Copy, Clone)]
+pub struct TaggedReportInfo<T = StaticAdvBuffer> {
+    pub report: ReportInfo<T>,
+    pub identity: Option<IdentityAddress>,
+}
+impl<T: AsRef<[u8]>> core::fmt::Debug for TaggedReportInfo<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TaggedReportInfo")
+            .field("report", &self.report)
+            .field("identity", &self.identity)
+            .finish()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{
+        generate_rpa, generate_rpa_with, new_rpa, resolve_rpa, IdentityAddress,
+        IdentityResolvingKey, IdentityResolvingKeySet, IRK_LEN,
+    };
+    use crate::crypto::SoftwareCrypto;
+    use crate::le::advertisement::RawAdvertisement;
+    use crate::le::advertiser::PeerAddressType;
+    use crate::le::report::{AddressType, EventType, ReportInfo};
+    use crate::{BTAddress, PackError};
+    use futures_util::FutureExt;
+
+    /// Core spec Vol 3, Part H, D.7 sample data.
+    const SAMPLE_RPA: BTAddress = BTAddress([0xAA, 0xFB, 0x0D, 0x94, 0x81, 0x70]);
+    fn sample_irk() -> IdentityResolvingKey {
+        IdentityResolvingKey::from_be_bytes([
+            0xEC, 0x02, 0x34, 0xA3, 0x57, 0xC8, 0xAD, 0x05, 0x34, 0x10, 0x10, 0xA6, 0x0A, 0x39,
+            0x7D, 0x9B,
+        ])
+    }
+    fn identity(last: u8) -> IdentityAddress {
+        IdentityAddress::new(
+            PeerAddressType::Random,
+            BTAddress([0x01, 0x02, 0x03, 0x04, 0x05, last]),
+        )
+    }
+    fn report(address_type: AddressType, address: BTAddress) -> ReportInfo<&'static [u8]> {
+        ReportInfo {
+            event_type: EventType::AdvInd,
+            address_type,
+            address,
+            data: RawAdvertisement(&[]),
+            rssi: None,
+        }
+    }
+
+    #[test]
+    fn test_irk_byte_order() {
+        let mut be = [0_u8; IRK_LEN];
+        be[0] = 0xEC;
+        be[IRK_LEN - 1] = 0x9B;
+        let irk = IdentityResolvingKey::from_be_bytes(be);
+        assert_eq!(irk.0[0], 0x9B);
+        assert_eq!(irk.0[IRK_LEN - 1], 0xEC);
+        assert_eq!(irk.to_be_bytes(), be);
+        assert!(!irk.is_zeroed());
+        assert!(IdentityResolvingKey::default().is_zeroed());
+
+        let mut buf = [0_u8; IdentityResolvingKey::BYTE_LEN];
+        irk.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, irk.0);
+        assert_eq!(IdentityResolvingKey::unpack_from(&buf), Ok(irk));
+        assert_eq!(
+            IdentityResolvingKey::unpack_from(&buf[..15]),
+            Err(PackError::BadLength {
+                expected: IRK_LEN,
+                got: 15
+            })
+        );
+    }
+    #[test]
+    fn test_identity_address_pack_unpack() {
+        let identity = IdentityAddress::new(
+            PeerAddressType::Public,
+            BTAddress([0x01, 0x02, 0x03, 0x04, 0x05, 0x06]),
+        );
+        let mut buf = [0_u8; IdentityAddress::BYTE_LEN];
+        identity.pack_into(&mut buf).unwrap();
+        assert_eq!(buf, [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06]);
+        assert_eq!(IdentityAddress::unpack_from(&buf), Ok(identity));
+        assert!(IdentityAddress::unpack_from(&buf[..6]).is_err());
+        assert!(identity.pack_into(&mut buf[..6]).is_err());
+        buf[0] = 0x02;
+        assert_eq!(
+            IdentityAddress::unpack_from(&buf),
+            Err(PackError::bad_index(0))
+        );
+    }
+    #[test]
+    fn test_new_rpa() {
+        let irk = sample_irk();
+        // The two most significant bits of `prand` are always `0b01`.
+        assert_eq!(new_rpa(&irk, [0x94, 0x81, 0xB0]), Some(SAMPLE_RPA));
+        assert!(SAMPLE_RPA.is_resolvable_private());
+        // All 0s or all 1s random bits.
+        assert_eq!(new_rpa(&irk, [0x00, 0x00, 0x00]), None);
+        assert_eq!(new_rpa(&irk, [0x00, 0x00, 0x40]), None);
+        assert_eq!(new_rpa(&irk, [0xFF, 0xFF, 0xFF]), None);
+        assert!(new_rpa(&irk, [0xFF, 0xFF, 0x7E]).is_some());
+    }
+    #[test]
+    fn test_generate_rpa_retries_invalid_prand() {
+        let irk = sample_irk();
+        let mut calls = 0;
+        let address = generate_rpa(&irk, |buf| {
+            calls += 1;
+            if calls == 1 {
+                buf.copy_from_slice(&[0xFF, 0xFF, 0xFF]);
+            } else {
+                buf.copy_from_slice(&[0x94, 0x81, 0x70]);
+            }
+        });
+        assert_eq!(calls, 2);
+        assert_eq!(address, SAMPLE_RPA);
+        assert!(resolve_rpa(
+            generate_rpa(&irk, |buf| buf.copy_from_slice(&[0x5A; 3])),
+            &irk
+        ));
+    }
+    #[test]
+    fn test_resolve_rpa() {
+        let irk = sample_irk();
+        assert!(resolve_rpa(SAMPLE_RPA, &irk));
+        let mut wrong_hash = SAMPLE_RPA;
+        wrong_hash.0[0] ^= 0x01;
+        assert!(!resolve_rpa(wrong_hash, &irk));
+        // Same hash and prand but not a resolvable private address.
+        let mut static_random = SAMPLE_RPA;
+        static_random.0[5] |= 0xC0;
+        assert!(!resolve_rpa(static_random, &irk));
+        assert!(!resolve_rpa(SAMPLE_RPA, &IdentityResolvingKey::ZEROED));
+    }
+    #[test]
+    fn test_irk_set() {
+        let mut set = IdentityResolvingKeySet::new();
+        assert!(set.is_empty());
+        assert_eq!(
+            set.insert(identity(0xC1), IdentityResolvingKey::ZEROED),
+            None
+        );
+        assert_eq!(
+            set.insert(identity(0xC2), IdentityResolvingKey::ZEROED),
+            None
+        );
+        assert_eq!(
+            set.insert(identity(0xC2), sample_irk()),
+            Some(IdentityResolvingKey::ZEROED)
+        );
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.get(&identity(0xC2)), Some(&sample_irk()));
+        assert_eq!(set.resolve(SAMPLE_RPA), Some(&identity(0xC2)));
+        assert_eq!(set.resolve(identity(0xC2).address), None);
+
+        assert_eq!(set.remove(&identity(0xC2)), Some(sample_irk()));
+        assert_eq!(set.remove(&identity(0xC2)), None);
+        assert_eq!(set.resolve(SAMPLE_RPA), None);
+        set.clear();
+        assert!(set.is_empty());
+    }
+    #[test]
+    fn test_irk_set_tag_reports() {
+        let mut set = IdentityResolvingKeySet::new();
+        set.insert(identity(0xC2), sample_irk());
+        let public = IdentityAddress::new(PeerAddressType::Public, BTAddress([0x11; 6]));
+        set.insert(public, IdentityResolvingKey::ZEROED);
+
+        let tagged = set.tag(report(AddressType::RandomDevice, SAMPLE_RPA));
+        assert_eq!(tagged.identity, Some(identity(0xC2)));
+        // Already resolved by the controller.
+        let tagged = set.tag(report(AddressType::RandomIdentity, identity(0xC2).address));
+        assert_eq!(tagged.identity, Some(identity(0xC2)));
+        let tagged = set.tag(report(AddressType::PublicDevice, public.address));
+        assert_eq!(tagged.identity, Some(public));
+        // Right address but wrong address type.
+        let tagged = set.tag(report(AddressType::RandomDevice, public.address));
+        assert_eq!(tagged.identity, None);
+        let mut unknown = SAMPLE_RPA;
+        unknown.0[0] ^= 0x01;
+        let tagged = set.tag(report(AddressType::RandomDevice, unknown));
+        assert_eq!(tagged.identity, None);
+        assert_eq!(tagged.report.address, unknown);
+    }
+    #[test]
+    fn test_generate_rpa_with_provider() {
+        let mut calls = 0;
+        let mut provider = SoftwareCrypto::new(|buf: &mut [u8]| {
+            calls += 1;
+            let prand: &[u8] = if calls == 1 {
+                &[0x00, 0x00, 0x00]
+            } else {
+                &[0x94, 0x81, 0x70]
+            };
+            buf.copy_from_slice(prand);
+        });
+        let address = generate_rpa_with(&mut provider, &sample_irk())
+            .now_or_never()
+            .unwrap();
+        assert_eq!(address, Ok(SAMPLE_RPA));
+        assert_eq!(calls, 2);
+    }
+}
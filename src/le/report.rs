@@ -104,6 +104,15 @@ impl TryFrom<u8> for AddressType {
         }
     }
 }
+impl AddressType {
+    /// Returns `true` for `RandomDevice` and `RandomIdentity`.
+    pub fn is_random(self) -> bool {
+        match self {
+            AddressType::PublicDevice | AddressType::PublicIdentity => false,
+            AddressType::RandomDevice | AddressType::RandomIdentity => true,
+        }
+    }
+}
 impl From<AddressType> for u8 {
     fn from(a: AddressType) -> Self {
         a as u8
@@ -9,6 +9,16 @@ pub enum ScanningFilterPolicy {
     DirectedAll = 0x02,
     DirectedWhitelisted = 0x03,
 }
+impl ScanningFilterPolicy {
+    /// If the policy also reports directed advertisements addressed to a resolvable private
+    /// address (requires the LE Extended Scanner Filter Policies feature).
+    pub fn is_directed(self) -> bool {
+        matches!(
+            self,
+            ScanningFilterPolicy::DirectedAll | ScanningFilterPolicy::DirectedWhitelisted
+        )
+    }
+}
 impl From<ScanningFilterPolicy> for u8 {
     fn from(p: ScanningFilterPolicy) -> Self {
         p as u8
@@ -53,6 +63,17 @@ impl From<ScanInterval> for u16 {
         i.0
     }
 }
+impl TryFrom<u16> for ScanInterval {
+    type Error = ConversionError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        if value >= INTERVAL_MIN && value <= INTERVAL_MAX {
+            Ok(ScanInterval(value))
+        } else {
+            Err(ConversionError(()))
+        }
+    }
+}
 impl Default for ScanInterval {
     fn default() -> Self {
         Self::DEFAULT
@@ -78,6 +99,17 @@ impl From<ScanWindow> for u16 {
         w.0
     }
 }
+impl TryFrom<u16> for ScanWindow {
+    type Error = ConversionError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        if value >= INTERVAL_MIN && value <= INTERVAL_MAX {
+            Ok(ScanWindow(value))
+        } else {
+            Err(ConversionError(()))
+        }
+    }
+}
 impl Default for ScanWindow {
     fn default() -> Self {
         Self::DEFAULT
@@ -88,7 +120,11 @@ impl Default for ScanWindow {
 pub enum OwnAddressType {
     Public = 0x00,
     Random = 0x01,
+    /// Resolvable private address generated by the controller from the Resolving List (see
+    /// `LEAdapter::load_resolving_list`). Falls back to the public address if there's no entry.
     PrivateOrPublic = 0x02,
+    /// Resolvable private address generated by the controller from the Resolving List. Falls
+    /// back to the random address if there's no entry.
     PrivateOrRandom = 0x03,
 }
 
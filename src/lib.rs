@@ -29,6 +29,7 @@ pub type BoxFuture<'a, T> = core::pin::Pin<Box<dyn core::future::Future<Output =
 pub type BoxStream<'a, T> = core::pin::Pin<Box<dyn Stream<Item = T> + 'a>>;
 extern crate core;
 pub mod bytes;
+pub mod crypto;
 pub mod error;
 #[cfg(feature = "hci")]
 pub mod hci;
@@ -181,6 +182,129 @@ impl BTAddress {
         bytes.copy_from_slice(&self.0[..]);
         Ok(())
     }
+    /// Mask for the two most significant bits of a random address (stored in the last byte
+    /// because addresses are little endian).
+    const RANDOM_TYPE_MASK: u8 = 0b1100_0000;
+    const STATIC_RANDOM_BITS: u8 = 0b1100_0000;
+    const NON_RESOLVABLE_BITS: u8 = 0b0000_0000;
+    const RESOLVABLE_BITS: u8 = 0b0100_0000;
+    fn random_type_bits(self) -> u8 {
+        self.0[BT_ADDRESS_LEN - 1] & Self::RANDOM_TYPE_MASK
+    }
+    /// Returns `true` if the two most significant bits are `0b11`. Only meaningful for random
+    /// addresses (public addresses can have any bits set).
+    pub fn is_static_random(self) -> bool {
+        self.random_type_bits() == Self::STATIC_RANDOM_BITS
+    }
+    /// Returns `true` if the two most significant bits are `0b00`. Only meaningful for random
+    /// addresses.
+    pub fn is_non_resolvable_private(self) -> bool {
+        self.random_type_bits() == Self::NON_RESOLVABLE_BITS
+    }
+    /// Returns `true` if the two most significant bits are `0b01`. Only meaningful for random
+    /// addresses.
+    pub fn is_resolvable_private(self) -> bool {
+        self.random_type_bits() == Self::RESOLVABLE_BITS
+    }
+    /// Classifies the address. `is_random` tells if the address is a random address (from the
+    /// address type that came with it). Returns `None` for random addresses with the reserved
+    /// `0b10` most significant bits.
+    /// # Examples
+    /// ```
+    /// use btle::{AddressKind, BTAddress};
+    /// let address = BTAddress([0xAA, 0xFB, 0x0D, 0x94, 0x81, 0x70]);
+    /// assert_eq!(address.kind(false), Some(AddressKind::Public));
+    /// assert_eq!(address.kind(true), Some(AddressKind::ResolvablePrivate));
+    /// assert_eq!(BTAddress([0xFF; 6]).kind(true), Some(AddressKind::StaticRandom));
+    /// assert_eq!(BTAddress([0x80; 6]).kind(true), None);
+    /// ```
+    pub fn kind(self, is_random: bool) -> Option<AddressKind> {
+        if !is_random {
+            return Some(AddressKind::Public);
+        }
+        match self.random_type_bits() {
+            Self::STATIC_RANDOM_BITS => Some(AddressKind::StaticRandom),
+            Self::NON_RESOLVABLE_BITS => Some(AddressKind::NonResolvablePrivate),
+            Self::RESOLVABLE_BITS => Some(AddressKind::ResolvablePrivate),
+            _ => None,
+        }
+    }
+    /// Returns `true` if the 46 random bits of the address are all 0s or all 1s. Such addresses
+    /// are forbidden for static and non-resolvable private addresses.
+    fn random_part_invalid(self) -> bool {
+        let top = self.0[BT_ADDRESS_LEN - 1] & !Self::RANDOM_TYPE_MASK;
+        let rest = &self.0[..BT_ADDRESS_LEN - 1];
+        (top == 0 && rest.iter().all(|b| *b == 0))
+            || (top == !Self::RANDOM_TYPE_MASK && rest.iter().all(|b| *b == 0xFF))
+    }
+    fn with_random_type_bits(mut random: [u8; BT_ADDRESS_LEN], bits: u8) -> BTAddress {
+        random[BT_ADDRESS_LEN - 1] = (random[BT_ADDRESS_LEN - 1] & !Self::RANDOM_TYPE_MASK) | bits;
+        BTAddress(random)
+    }
+    /// Creates a new static random address from `random` bytes. The two most significant bits
+    /// are overwritten with `0b11`. Returns `None` if the remaining 46 random bits are all 0s or
+    /// all 1s.
+    /// # Examples
+    /// ```
+    /// use btle::BTAddress;
+    /// let address = BTAddress::new_static_random([0x01, 0x02, 0x03, 0x04, 0x05, 0x06]).unwrap();
+    /// assert_eq!(address.0, [0x01, 0x02, 0x03, 0x04, 0x05, 0xC6]);
+    /// assert_eq!(BTAddress::new_static_random([0xFF; 6]), None);
+    /// ```
+    pub fn new_static_random(random: [u8; BT_ADDRESS_LEN]) -> Option<BTAddress> {
+        let address = Self::with_random_type_bits(random, Self::STATIC_RANDOM_BITS);
+        if address.random_part_invalid() {
+            None
+        } else {
+            Some(address)
+        }
+    }
+    /// Creates a new non-resolvable private address from `random` bytes. The two most
+    /// significant bits are overwritten with `0b00`. Returns `None` if the remaining 46 random
+    /// bits are all 0s or all 1s.
+    ///
+    /// # Important
+    /// A non-resolvable private address must not be equal to the public address of the device.
+    /// That can't be checked here so the caller should compare against its public address.
+    pub fn new_non_resolvable_private(random: [u8; BT_ADDRESS_LEN]) -> Option<BTAddress> {
+        let address = Self::with_random_type_bits(random, Self::NON_RESOLVABLE_BITS);
+        if address.random_part_invalid() {
+            None
+        } else {
+            Some(address)
+        }
+    }
+    /// Generates a static random address using `fill` to fill a byte buffer with random bytes.
+    /// `fill` is called until it produces a valid static random address (usually only once).
+    pub fn generate_static_random<F: FnMut(&mut [u8])>(mut fill: F) -> BTAddress {
+        loop {
+            let mut random = [0_u8; BT_ADDRESS_LEN];
+            fill(&mut random[..]);
+            if let Some(address) = Self::new_static_random(random) {
+                return address;
+            }
+        }
+    }
+    /// Generates a non-resolvable private address using `fill` to fill a byte buffer with random
+    /// bytes. `fill` is called until it produces a valid non-resolvable private address (usually
+    /// only once).
+    pub fn generate_non_resolvable_private<F: FnMut(&mut [u8])>(mut fill: F) -> BTAddress {
+        loop {
+            let mut random = [0_u8; BT_ADDRESS_LEN];
+            fill(&mut random[..]);
+            if let Some(address) = Self::new_non_resolvable_private(random) {
+                return address;
+            }
+        }
+    }
+}
+/// Kind of a [`BTAddress`]. Random addresses are told apart by their two most significant bits.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum AddressKind {
+    Public,
+    StaticRandom,
+    NonResolvablePrivate,
+    ResolvablePrivate,
 }
 /// 16-bit Bluetooth Company Identifier. Companies are assigned unique Company Identifiers to
 /// Bluetooth SIG members requesting them. [See here for more](https://www.bluetooth.com/specifications/assigned-numbers/company-identifiers/)